@@ -0,0 +1,609 @@
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use crate::models::announcement::AnnouncementKind;
+
+pub mod schedule;
+pub mod symbols;
+
+/// Rules used when no rules file is configured
+const DEFAULT_RULES: &str = include_str!("default_rules.toml");
+
+/// Symbol pattern used when a rules file does not define any, matches "(BTC)" or "[ETH]"
+const DEFAULT_SYMBOL_PATTERN: &str = r"[\(\[]([\w]{2,10})[\)\]]";
+
+/// Confidence when a rule matched both the title and the content
+const CONFIDENCE_TITLE_AND_CONTENT: f32 = 0.95;
+/// Confidence when a rule matched the title only
+const CONFIDENCE_TITLE: f32 = 0.85;
+/// Confidence when a rule matched the content only, titles are a much stronger signal
+const CONFIDENCE_CONTENT: f32 = 0.6;
+/// Confidence for announcements that matched no rule
+const CONFIDENCE_UNMATCHED: f32 = 0.3;
+
+lazy_static! {
+    /// The rule set used by `Announcement::analyze_for_new_listing`.
+    ///
+    /// Exchange monitors classify announcements deep inside their fetch logic, so the
+    /// active rules are shared globally (like the proxy configuration) instead of being
+    /// threaded through every monitor. Swapping the `Arc` makes reloads atomic: an
+    /// announcement is always classified against one complete rule set.
+    static ref ACTIVE_RULES: RwLock<Arc<RuleSet>> =
+        RwLock::new(Arc::new(RuleSet::builtin().expect("Built-in classification rules are invalid")));
+}
+
+/// Returns the currently active rule set
+pub fn active_rules() -> Arc<RuleSet> {
+    ACTIVE_RULES.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Replace the active rule set
+pub fn install_rules(rules: RuleSet) {
+    *ACTIVE_RULES.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(rules);
+}
+
+/// Where a rule is allowed to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleScope {
+    /// Only the title
+    Title,
+    /// Only the content
+    Content,
+    /// Title or content
+    #[default]
+    Any,
+}
+
+/// The part of an announcement a rule matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchLocation {
+    Title,
+    Content,
+    TitleAndContent,
+}
+
+impl fmt::Display for MatchLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchLocation::Title => f.write_str("title"),
+            MatchLocation::Content => f.write_str("content"),
+            MatchLocation::TitleAndContent => f.write_str("title and content"),
+        }
+    }
+}
+
+/// On-disk format of a rules file
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    symbol_patterns: Vec<String>,
+    /// Extra words that are never reported as symbols, on top of the built-in list
+    #[serde(default)]
+    symbol_stopwords: Vec<String>,
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+}
+
+/// On-disk format of a single rule
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    name: String,
+    kind: AnnouncementKind,
+    #[serde(default)]
+    priority: i32,
+    /// Exchanges the rule applies to, all exchanges if empty
+    #[serde(default)]
+    exchanges: Vec<String>,
+    #[serde(default)]
+    scope: RuleScope,
+    /// The rule fires if any of these patterns matches...
+    include: Vec<String>,
+    /// ...and all of these match as well...
+    #[serde(default)]
+    require: Vec<String>,
+    /// ...and none of these match
+    #[serde(default)]
+    exclude: Vec<String>,
+    /// Fixed confidence, derived from where the rule matched if absent
+    confidence: Option<f32>,
+}
+
+/// A rule with its patterns compiled
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    pub kind: AnnouncementKind,
+    pub priority: i32,
+    pub exchanges: Vec<String>,
+    pub scope: RuleScope,
+    include: Vec<Regex>,
+    require: Vec<Regex>,
+    exclude: Vec<Regex>,
+    confidence: Option<f32>,
+}
+
+impl Rule {
+    fn applies_to(&self, exchange: &str) -> bool {
+        self.exchanges.is_empty() || self.exchanges.iter().any(|e| e.eq_ignore_ascii_case(exchange))
+    }
+
+    /// Match the rule against a piece of text, returning the include pattern that fired
+    fn matches(&self, text: &str) -> Option<&Regex> {
+        let pattern = self.include.iter().find(|re| re.is_match(text))?;
+
+        if !self.require.iter().all(|re| re.is_match(text)) {
+            return None;
+        }
+        if self.exclude.iter().any(|re| re.is_match(text)) {
+            return None;
+        }
+
+        Some(pattern)
+    }
+}
+
+/// Result of classifying an announcement
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub kind: AnnouncementKind,
+    pub confidence: f32,
+    /// Name of the rule that fired, None if no rule matched
+    pub rule: Option<String>,
+    /// Priority of the rule that fired
+    pub priority: i32,
+    /// Where the rule matched
+    pub matched_in: Option<MatchLocation>,
+    /// The include pattern that matched
+    pub pattern: Option<String>,
+}
+
+/// A compiled set of classification rules
+#[derive(Debug)]
+pub struct RuleSet {
+    /// Rules sorted by descending priority, ties keep file order
+    rules: Vec<Rule>,
+    symbol_patterns: Vec<Regex>,
+    /// Upper-case words that are never reported as symbols
+    symbol_stopwords: HashSet<String>,
+    /// File the rules were loaded from, None for the built-in rules
+    source: Option<PathBuf>,
+}
+
+impl RuleSet {
+    /// The built-in rule set
+    pub fn builtin() -> Result<Self> {
+        Self::parse(DEFAULT_RULES, RulesFormat::Toml, None)
+    }
+
+    /// Load a rule set from a TOML or YAML file, chosen by the file extension
+    pub async fn load(path: &Path) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => RulesFormat::Yaml,
+            _ => RulesFormat::Toml,
+        };
+
+        Self::parse(&contents, format, Some(path.to_path_buf()))
+            .with_context(|| format!("Invalid rules file {}", path.display()))
+    }
+
+    fn parse(contents: &str, format: RulesFormat, source: Option<PathBuf>) -> Result<Self> {
+        let file: RulesFile = match format {
+            RulesFormat::Toml => toml::from_str(contents).context("Failed to parse TOML rules")?,
+            RulesFormat::Yaml => serde_yaml::from_str(contents).context("Failed to parse YAML rules")?,
+        };
+
+        let mut rules = file.rules.into_iter()
+            .map(|rule| {
+                let name = rule.name.clone();
+                Self::compile_rule(rule).with_context(|| format!("Invalid rule '{}'", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Stable sort keeps the file order for rules of equal priority
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+
+        let symbol_patterns = if file.symbol_patterns.is_empty() {
+            vec![DEFAULT_SYMBOL_PATTERN.to_string()]
+        } else {
+            file.symbol_patterns
+        };
+        let symbol_patterns = symbol_patterns.iter()
+            .map(|pattern| {
+                let re = Regex::new(pattern)
+                    .with_context(|| format!("Invalid symbol pattern '{}'", pattern))?;
+                if re.captures_len() < 2 {
+                    anyhow::bail!("Symbol pattern '{}' needs a capture group for the symbol", pattern);
+                }
+                Ok(re)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let symbol_stopwords = symbols::DEFAULT_STOPWORDS.iter()
+            .map(|word| word.to_string())
+            .chain(file.symbol_stopwords.iter().map(|word| word.to_uppercase()))
+            .collect();
+
+        Ok(Self {
+            rules,
+            symbol_patterns,
+            symbol_stopwords,
+            source,
+        })
+    }
+
+    fn compile_rule(rule: RuleConfig) -> Result<Rule> {
+        if rule.include.is_empty() {
+            anyhow::bail!("A rule needs at least one include pattern");
+        }
+        if let Some(confidence) = rule.confidence {
+            if !(0.0..=1.0).contains(&confidence) {
+                anyhow::bail!("Confidence must be between 0.0 and 1.0, got {}", confidence);
+            }
+        }
+
+        Ok(Rule {
+            name: rule.name,
+            kind: rule.kind,
+            priority: rule.priority,
+            exchanges: rule.exchanges,
+            scope: rule.scope,
+            include: compile_patterns(&rule.include)?,
+            require: compile_patterns(&rule.require)?,
+            exclude: compile_patterns(&rule.exclude)?,
+            confidence: rule.confidence,
+        })
+    }
+
+    /// All rules, highest priority first
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Patterns used to extract token symbols
+    pub fn symbol_patterns(&self) -> &[Regex] {
+        &self.symbol_patterns
+    }
+
+    /// Check if an upper-case word must not be reported as a symbol
+    pub fn is_symbol_stopword(&self, word: &str) -> bool {
+        self.symbol_stopwords.contains(word)
+    }
+
+    /// Human readable description of where the rules came from
+    pub fn source(&self) -> String {
+        match &self.source {
+            Some(path) => path.display().to_string(),
+            None => "built-in rules".to_string(),
+        }
+    }
+
+    /// Classify an announcement of the given exchange.
+    ///
+    /// The title is classified on its own first, because exchanges put the nature of
+    /// the announcement there ("Binance Will Delist ..."), while bodies often mention
+    /// several things at once. The content is only used to confirm the title or as a
+    /// weaker fallback when no rule matches the title.
+    pub fn classify(&self, exchange: &str, title: &str, content: &str) -> Classification {
+        let candidates = || self.rules.iter().filter(|rule| rule.applies_to(exchange));
+
+        let title_match = candidates()
+            .filter(|rule| rule.scope != RuleScope::Content)
+            .find_map(|rule| rule.matches(title).map(|pattern| (rule, pattern)));
+
+        if let Some((rule, pattern)) = title_match {
+            let confirmed = rule.scope != RuleScope::Title && rule.matches(content).is_some();
+            let (matched_in, default_confidence) = if confirmed {
+                (MatchLocation::TitleAndContent, CONFIDENCE_TITLE_AND_CONTENT)
+            } else {
+                (MatchLocation::Title, CONFIDENCE_TITLE)
+            };
+            return Classification::from_rule(rule, pattern, matched_in, default_confidence);
+        }
+
+        let content_match = candidates()
+            .filter(|rule| rule.scope != RuleScope::Title)
+            .find_map(|rule| rule.matches(content).map(|pattern| (rule, pattern)));
+
+        match content_match {
+            Some((rule, pattern)) => {
+                Classification::from_rule(rule, pattern, MatchLocation::Content, CONFIDENCE_CONTENT)
+            }
+            None => Classification {
+                kind: AnnouncementKind::Other,
+                confidence: CONFIDENCE_UNMATCHED,
+                rule: None,
+                priority: 0,
+                matched_in: None,
+                pattern: None,
+            },
+        }
+    }
+}
+
+impl Classification {
+    fn from_rule(rule: &Rule, pattern: &Regex, matched_in: MatchLocation, default_confidence: f32) -> Self {
+        Self {
+            kind: rule.kind,
+            confidence: rule.confidence.unwrap_or(default_confidence),
+            rule: Some(rule.name.clone()),
+            priority: rule.priority,
+            matched_in: Some(matched_in),
+            pattern: Some(pattern.as_str().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RulesFormat {
+    Toml,
+    Yaml,
+}
+
+/// Compile rule patterns, case-insensitive so rule authors do not need `(?i)`
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns.iter()
+        .map(|pattern| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .with_context(|| format!("Invalid pattern '{}'", pattern))
+        })
+        .collect()
+}
+
+/// Load the rules file at `path` and make it the active rule set
+pub async fn load_and_install(path: &Path) -> Result<()> {
+    let rules = RuleSet::load(path).await?;
+    tracing::info!(
+        path = %path.display(),
+        rules = rules.rules().len(),
+        "Loaded {} classification rules from {}",
+        rules.rules().len(), path.display()
+    );
+    install_rules(rules);
+    Ok(())
+}
+
+/// Watch the rules file and reload it whenever it changes.
+///
+/// The file's modification time is polled rather than relying on filesystem
+/// notifications, which behave differently across platforms and editors (many
+/// editors replace the file instead of writing to it). An invalid file is logged
+/// and the previous rules stay active, so a typo never disables classification.
+pub fn spawn_rules_reloader(path: PathBuf, poll_interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_modified = modified_time(&path).await;
+        let mut interval = tokio::time::interval(poll_interval);
+
+        loop {
+            interval.tick().await;
+
+            let modified = modified_time(&path).await;
+            if modified.is_none() || modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            match load_and_install(&path).await {
+                Ok(()) => {
+                    tracing::info!(path = %path.display(), "Reloaded classification rules");
+                }
+                Err(e) => {
+                    tracing::error!(
+                        path = %path.display(),
+                        error = %format!("{:#}", e),
+                        "Failed to reload classification rules, keeping the previous rules"
+                    );
+                }
+            }
+        }
+    })
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(title: &str) -> AnnouncementKind {
+        RuleSet::builtin().unwrap().classify("Binance", title, "").kind
+    }
+
+    #[test]
+    fn tells_listings_from_delistings() {
+        assert_eq!(kind("Binance Will List Notcoin (NOT) with Seed Tag Applied"), AnnouncementKind::SpotListing);
+        assert_eq!(kind("Binance Will Delist BTCST, DREP, MOB on 2024-06-10"), AnnouncementKind::Delisting);
+        assert_eq!(kind("Notice of Removal of Spot Trading Pairs - 2024-06-14"), AnnouncementKind::Delisting);
+        assert_eq!(kind("Bybit Will Not List the XYZ Futures"), AnnouncementKind::Other);
+        assert_eq!(kind("Weekly Market Review"), AnnouncementKind::Other);
+        // Mentioning listings without announcing one is not a listing
+        assert_eq!(kind("Notice on listing review of XYZ"), AnnouncementKind::Other);
+        assert_eq!(kind("Bybit Announces the Listing of LayerZero (ZRO)"), AnnouncementKind::SpotListing);
+        assert_eq!(kind("[Initial Listing] Notcoin (NOT) Spot Trading"), AnnouncementKind::SpotListing);
+    }
+
+    #[test]
+    fn tells_spot_from_futures_and_margin_listings() {
+        assert_eq!(
+            kind("Binance Futures Will Launch USDⓈ-M NOTUSDT Perpetual Contract With Up to 50x Leverage"),
+            AnnouncementKind::FuturesListing
+        );
+        assert_eq!(
+            kind("Binance Will Add NOT on Cross Margin & Isolated Margin, BTC/USDT Margin Trading Pairs"),
+            AnnouncementKind::MarginListing
+        );
+        assert_eq!(kind("OKX to list perpetual futures for ZRO"), AnnouncementKind::FuturesListing);
+        // Mentioning futures without announcing a listing is not a futures listing
+        assert_eq!(kind("Futures Trading Competition Results"), AnnouncementKind::Other);
+    }
+
+    #[test]
+    fn recognises_launchpools_airdrops_and_maintenance() {
+        assert_eq!(kind("Introducing Notcoin (NOT) on Binance Launchpool"), AnnouncementKind::Launchpool);
+        assert_eq!(kind("Bybit Launchpad: Subscribe to win XYZ"), AnnouncementKind::Launchpad);
+        assert_eq!(kind("NOT Airdrop for BNB Holders"), AnnouncementKind::Airdrop);
+        assert_eq!(kind("Scheduled System Maintenance on June 20"), AnnouncementKind::Maintenance);
+    }
+
+    #[test]
+    fn recognises_korean_upbit_notices() {
+        let classify = |title: &str| RuleSet::builtin().unwrap().classify("Upbit", title, "").kind;
+
+        assert_eq!(classify("노트코인(NOT) KRW, USDT 마켓 디지털 자산 추가"), AnnouncementKind::SpotListing);
+        assert_eq!(classify("[거래] KRW 마켓 지토(JTO) 거래지원 안내"), AnnouncementKind::SpotListing);
+        assert_eq!(classify("[거래지원종료] 비트코인에스브이(BSV) 거래지원 종료 안내"), AnnouncementKind::Delisting);
+        assert_eq!(classify("[입출금] 이더리움 네트워크 업그레이드에 따른 입출금 일시 중단 안내"), AnnouncementKind::Maintenance);
+    }
+
+    #[test]
+    fn derives_confidence_from_where_the_rule_matched() {
+        let rules = RuleSet::builtin().unwrap();
+
+        let title_only = rules.classify("Binance", "Binance Will List Notcoin (NOT)", "");
+        assert_eq!(title_only.confidence, CONFIDENCE_TITLE);
+        assert_eq!(title_only.matched_in, Some(MatchLocation::Title));
+
+        let confirmed = rules.classify("Binance", "Binance Will List Notcoin (NOT)", "Binance will list NOT at 12:00");
+        assert_eq!(confirmed.confidence, CONFIDENCE_TITLE_AND_CONTENT);
+
+        let content_only = rules.classify("Binance", "Notcoin (NOT)", "Binance will list NOT at 12:00");
+        assert_eq!(content_only.kind, AnnouncementKind::SpotListing);
+        assert_eq!(content_only.confidence, CONFIDENCE_CONTENT);
+        assert_eq!(content_only.matched_in, Some(MatchLocation::Content));
+
+        let unmatched = rules.classify("Binance", "Weekly Market Review", "");
+        assert_eq!(unmatched.confidence, CONFIDENCE_UNMATCHED);
+        assert!(unmatched.rule.is_none());
+    }
+
+    #[test]
+    fn title_wins_over_content() {
+        let classification = RuleSet::builtin().unwrap().classify(
+            "Binance",
+            "Binance Will Delist BTCST",
+            "Users can still list their open orders... new listing schedule below",
+        );
+        assert_eq!(classification.kind, AnnouncementKind::Delisting);
+    }
+
+    fn parse(contents: &str) -> Result<RuleSet> {
+        RuleSet::parse(contents, RulesFormat::Toml, None)
+    }
+
+    #[test]
+    fn higher_priority_rules_win() {
+        let rules = parse(r#"
+            [[rules]]
+            name = "listing"
+            kind = "spot_listing"
+            include = ['will list']
+
+            [[rules]]
+            name = "perps"
+            kind = "futures_listing"
+            priority = 10
+            include = ['perpetual']
+        "#).unwrap();
+
+        let classification = rules.classify("OKX", "OKX will list ZRO perpetual", "");
+        assert_eq!(classification.kind, AnnouncementKind::FuturesListing);
+        assert_eq!(classification.rule.as_deref(), Some("perps"));
+        assert_eq!(rules.rules()[0].name, "perps");
+    }
+
+    #[test]
+    fn applies_scope_exchanges_require_and_exclude() {
+        let rules = parse(r#"
+            [[rules]]
+            name = "upbit-only"
+            kind = "spot_listing"
+            exchanges = ["upbit"]
+            include = ['market support']
+
+            [[rules]]
+            name = "title-only"
+            kind = "airdrop"
+            scope = "title"
+            include = ['reward']
+
+            [[rules]]
+            name = "guarded"
+            kind = "launchpad"
+            include = ['sale']
+            require = ['token']
+            exclude = ['garage']
+            confidence = 0.5
+        "#).unwrap();
+
+        assert_eq!(rules.classify("Upbit", "KRW market support for ABC", "").kind, AnnouncementKind::SpotListing);
+        assert_eq!(rules.classify("Bithumb", "KRW market support for ABC", "").kind, AnnouncementKind::Other);
+        assert_eq!(rules.classify("OKX", "Notice", "reward campaign").kind, AnnouncementKind::Other);
+
+        let sale = rules.classify("OKX", "Token SALE of ABC", "");
+        assert_eq!(sale.kind, AnnouncementKind::Launchpad);
+        assert_eq!(sale.confidence, 0.5);
+        assert_eq!(rules.classify("OKX", "Sale of ABC", "").kind, AnnouncementKind::Other);
+        assert_eq!(rules.classify("OKX", "Token garage sale", "").kind, AnnouncementKind::Other);
+    }
+
+    #[test]
+    fn parses_yaml_rules_and_symbol_settings() {
+        let rules = RuleSet::parse(r#"
+symbol_patterns: ['\$([A-Z]{2,10})']
+symbol_stopwords: [new]
+rules:
+  - name: listing
+    kind: spot_listing
+    include: ['listing']
+"#, RulesFormat::Yaml, None).unwrap();
+
+        assert_eq!(rules.classify("OKX", "New listing", "").kind, AnnouncementKind::SpotListing);
+        assert_eq!(rules.symbol_patterns()[0].as_str(), "\\$([A-Z]{2,10})");
+        assert!(rules.is_symbol_stopword("NEW"));
+        assert!(rules.is_symbol_stopword("UTC"));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let error = |contents: &str| format!("{:#}", parse(contents).unwrap_err());
+
+        assert!(error("[[rules]]\nname = 'a'\nkind = 'airdrop'\ninclude = []").contains("at least one include"));
+        assert!(error("[[rules]]\nname = 'a'\nkind = 'airdrop'\ninclude = ['(']").contains("Invalid pattern '('"));
+        assert!(error("[[rules]]\nname = 'a'\nkind = 'airdrop'\ninclude = ['x']\nconfidence = 2.0").contains("between 0.0 and 1.0"));
+        assert!(error("[[rules]]\nname = 'a'\nkind = 'listing'\ninclude = ['x']").contains("unknown variant"));
+        assert!(error("[[rules]]\nname = 'a'\nkind = 'airdrop'\ninclude = ['x']\nincludes = ['y']").contains("unknown field"));
+        assert!(error("symbol_patterns = ['[A-Z]+']").contains("needs a capture group"));
+    }
+
+    #[tokio::test]
+    async fn picks_the_format_from_the_file_extension() {
+        let dir = std::env::temp_dir().join(format!("eam-rules-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rules.yml");
+        std::fs::write(&path, "rules:\n  - name: listing\n    kind: spot_listing\n    include: ['listing']\n").unwrap();
+
+        let rules = RuleSet::load(&path).await.unwrap();
+        assert_eq!(rules.rules().len(), 1);
+        assert_eq!(rules.source(), path.display().to_string());
+
+        let error = RuleSet::load(&dir.join("missing.toml")).await.unwrap_err();
+        assert!(error.to_string().starts_with("Failed to read rules file"));
+    }
+}
@@ -0,0 +1,315 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+/// Scheduled times extracted from a listing announcement
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    pub deposit_open_at: Option<DateTime<Utc>>,
+    pub trading_open_at: Option<DateTime<Utc>>,
+    pub withdrawal_open_at: Option<DateTime<Utc>>,
+}
+
+/// The event a timestamp refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScheduleEvent {
+    Deposit,
+    Trading,
+    Withdrawal,
+}
+
+/// Keywords announcing each event, matched ignoring case
+const EVENT_KEYWORDS: &[(ScheduleEvent, &[&str])] = &[
+    (ScheduleEvent::Deposit, &["deposit", "입금", "充值"]),
+    (ScheduleEvent::Trading, &["trading", "trade", "거래", "交易"]),
+    (ScheduleEvent::Withdrawal, &["withdrawal", "withdraw", "출금", "提现", "提币"]),
+];
+
+/// How far before a timestamp we look for the event keyword, in bytes
+const KEYWORD_LOOKBEHIND: usize = 150;
+/// How far after a timestamp we look if nothing was found before it, in bytes
+const KEYWORD_LOOKAHEAD: usize = 60;
+
+/// Optional timezone suffix shared by all date formats:
+/// "UTC", "(UTC)", "UTC+8", "(UTC+08:00)", "GMT+8", "KST", "SGT", "HKT"
+const TIMEZONE_SUFFIX: &str = r"(?:\s*\(?\s*(?P<tz>UTC|GMT|KST|SGT|HKT)\s*(?P<offset>[+-]\s*\d{1,2}(?::?\d{2})?)?\s*\)?)?";
+
+lazy_static! {
+    /// "2024-05-01 10:00", "2024/05/01 10:00:00", "2024.05.01, 10:00 PM"
+    static ref NUMERIC_DATETIME: Regex = Regex::new(&format!(
+        r"(?i)(?P<year>\d{{4}})[-/.](?P<month>\d{{1,2}})[-/.](?P<day>\d{{1,2}})[\sT,]*(?P<hour>\d{{1,2}}):(?P<minute>\d{{2}})(?::(?P<second>\d{{2}}))?\s*(?P<ampm>AM|PM)?{}",
+        TIMEZONE_SUFFIX
+    )).unwrap();
+
+    /// "May 1, 2024, 10:00 AM", "May 1, 2024 at 10AM"
+    static ref TEXTUAL_DATETIME: Regex = Regex::new(&format!(
+        r"(?i)(?P<monthname>jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(?P<day>\d{{1,2}})(?:st|nd|rd|th)?,?\s+(?P<year>\d{{4}}),?\s*(?:at\s*)?(?P<hour>\d{{1,2}})(?::(?P<minute>\d{{2}}))?(?::(?P<second>\d{{2}}))?\s*(?P<ampm>AM|PM)?{}",
+        TIMEZONE_SUFFIX
+    )).unwrap();
+
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+
+    /// Any of `EVENT_KEYWORDS`, with one capture group per event in the same order
+    static ref EVENT_KEYWORD: Regex = Regex::new(&format!(
+        "(?i){}",
+        EVENT_KEYWORDS.iter()
+            .map(|(_, keywords)| format!("({})", keywords.join("|")))
+            .collect::<Vec<_>>()
+            .join("|")
+    )).unwrap();
+}
+
+/// Default timezone of an exchange, used when a timestamp has no timezone.
+///
+/// Korean exchanges publish times in KST without saying so; everyone else
+/// either states the timezone or uses UTC.
+pub fn default_offset(exchange: &str) -> FixedOffset {
+    match exchange.to_lowercase().as_str() {
+        "upbit" | "bithumb" => FixedOffset::east_opt(9 * 3600).unwrap(),
+        _ => FixedOffset::east_opt(0).unwrap(),
+    }
+}
+
+/// Extract deposit, trading and withdrawal open times from an announcement body.
+///
+/// Every timestamp found in the text is attributed to the closest event keyword
+/// preceding it (or, failing that, following it shortly after), without looking past
+/// the previous timestamp. This handles both "Trading: 2024-05-01 10:00 (UTC)" lists
+/// and prose such as "will open trading for ABC/USDT at 2024-05-01 10:00 (UTC)".
+/// The first time found for an event wins.
+pub fn extract_schedule(content: &str, default_offset: FixedOffset) -> Schedule {
+    let text = html_to_text(content);
+
+    let mut matches: Vec<(usize, usize, DateTime<Utc>)> = Vec::new();
+    for re in [&*NUMERIC_DATETIME, &*TEXTUAL_DATETIME] {
+        for cap in re.captures_iter(&text) {
+            let whole = cap.get(0).unwrap();
+            if let Some(datetime) = parse_datetime(&cap, default_offset) {
+                matches.push((whole.start(), whole.end(), datetime));
+            }
+        }
+    }
+    matches.sort_by_key(|(start, _, _)| *start);
+
+    let mut schedule = Schedule::default();
+    let mut previous_end = 0;
+
+    for (index, (start, end, datetime)) in matches.iter().enumerate() {
+        let window_start = floor_char_boundary(&text, start.saturating_sub(KEYWORD_LOOKBEHIND).max(previous_end));
+        let next_start = matches.get(index + 1).map_or(text.len(), |m| m.0);
+        let window_end = floor_char_boundary(&text, (end + KEYWORD_LOOKAHEAD).min(next_start));
+
+        let event = event_keywords(&text[window_start..*start]).last()
+            .or_else(|| event_keywords(&text[*end..window_end]).next());
+
+        match event {
+            Some(ScheduleEvent::Deposit) => {
+                schedule.deposit_open_at.get_or_insert(*datetime);
+            }
+            Some(ScheduleEvent::Trading) => {
+                schedule.trading_open_at.get_or_insert(*datetime);
+            }
+            Some(ScheduleEvent::Withdrawal) => {
+                schedule.withdrawal_open_at.get_or_insert(*datetime);
+            }
+            None => {}
+        }
+
+        previous_end = *end;
+    }
+
+    schedule
+}
+
+/// Events of the keywords in the text, in order of appearance
+fn event_keywords(text: &str) -> impl Iterator<Item = ScheduleEvent> + '_ {
+    EVENT_KEYWORD.captures_iter(text).filter_map(|cap| {
+        EVENT_KEYWORDS.iter()
+            .enumerate()
+            .find(|(group, _)| cap.get(group + 1).is_some())
+            .map(|(_, (event, _))| *event)
+    })
+}
+
+/// Build a UTC timestamp from the captures of one of the datetime patterns
+fn parse_datetime(cap: &Captures, default_offset: FixedOffset) -> Option<DateTime<Utc>> {
+    let number = |name: &str| cap.name(name).and_then(|m| m.as_str().parse::<u32>().ok());
+
+    let year = cap.name("year")?.as_str().parse::<i32>().ok()?;
+    let month = match cap.name("monthname") {
+        Some(name) => month_from_name(name.as_str())?,
+        None => number("month")?,
+    };
+    let day = number("day")?;
+
+    let mut hour = number("hour")?;
+    let minute = number("minute").unwrap_or(0);
+    let second = number("second").unwrap_or(0);
+
+    // A bare "10" without minutes or AM/PM is more likely a day or count than a time
+    if cap.name("minute").is_none() && cap.name("ampm").is_none() {
+        return None;
+    }
+
+    if let Some(ampm) = cap.name("ampm") {
+        if hour == 0 || hour > 12 {
+            return None;
+        }
+        let is_pm = ampm.as_str().eq_ignore_ascii_case("pm");
+        hour = match (hour, is_pm) {
+            (12, false) => 0,
+            (12, true) => 12,
+            (hour, true) => hour + 12,
+            (hour, false) => hour,
+        };
+    }
+
+    let naive = NaiveDateTime::new(
+        NaiveDate::from_ymd_opt(year, month, day)?,
+        NaiveTime::from_hms_opt(hour, minute, second)?,
+    );
+
+    let offset = match cap.name("tz") {
+        Some(tz) => timezone_offset(tz.as_str(), cap.name("offset").map(|m| m.as_str()))?,
+        None => default_offset,
+    };
+
+    offset.from_local_datetime(&naive)
+        .single()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// Offset of a named timezone with an optional explicit offset such as "+8" or "+05:30"
+fn timezone_offset(name: &str, offset: Option<&str>) -> Option<FixedOffset> {
+    let base_hours = match name.to_uppercase().as_str() {
+        "KST" => 9,
+        "SGT" | "HKT" => 8,
+        _ => 0,
+    };
+
+    let extra_seconds = match offset {
+        Some(offset) => {
+            let offset: String = offset.chars().filter(|c| !c.is_whitespace()).collect();
+            let (sign, digits) = offset.split_at(1);
+            let sign = if sign == "-" { -1 } else { 1 };
+            let (hours, minutes) = match digits.split_once(':') {
+                Some((hours, minutes)) => (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?),
+                None if digits.len() > 2 => (digits[..digits.len() - 2].parse().ok()?, digits[digits.len() - 2..].parse().ok()?),
+                None => (digits.parse::<i32>().ok()?, 0),
+            };
+            sign * (hours * 3600 + minutes * 60)
+        }
+        None => 0,
+    };
+
+    FixedOffset::east_opt(base_hours * 3600 + extra_seconds)
+}
+
+fn month_from_name(name: &str) -> Option<u32> {
+    let month = match name.to_lowercase().get(..3)? {
+        "jan" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" => 4,
+        "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "aug" => 8,
+        "sep" => 9,
+        "oct" => 10,
+        "nov" => 11,
+        "dec" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+/// Strip HTML tags and decode entities, announcement bodies are often raw HTML
+fn html_to_text(content: &str) -> String {
+    let without_tags = HTML_TAG.replace_all(content, " ");
+    html_escape::decode_html_entities(&without_tags).into_owned()
+}
+
+/// Largest char boundary not above `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(datetime: &str) -> Option<DateTime<Utc>> {
+        Some(DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc))
+    }
+
+    fn extract(content: &str) -> Schedule {
+        extract_schedule(content, default_offset("Binance"))
+    }
+
+    #[test]
+    fn parses_numeric_and_textual_dates() {
+        let cases = [
+            ("Trading: 2024-05-01 10:00 (UTC)", "2024-05-01T10:00:00Z"),
+            ("Trading: 2024/05/01 10:00:30", "2024-05-01T10:00:30Z"),
+            ("Trading: 2024.05.01, 10:00 PM", "2024-05-01T22:00:00Z"),
+            ("Trading: 2024-05-01T10:00 UTC+8", "2024-05-01T02:00:00Z"),
+            ("Trading: 2024-05-01 10:00 (UTC+05:30)", "2024-05-01T04:30:00Z"),
+            ("Trading: 2024-05-01 10:00 KST", "2024-05-01T01:00:00Z"),
+            ("Trading: May 1, 2024, 10:00 AM (UTC)", "2024-05-01T10:00:00Z"),
+            ("Trading: May 1st, 2024 at 10PM HKT", "2024-05-01T14:00:00Z"),
+            ("Trading: December 31, 2024 12:00 AM", "2024-12-31T00:00:00Z"),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(extract(content).trading_open_at, utc(expected), "{}", content);
+        }
+    }
+
+    #[test]
+    fn attributes_times_to_the_nearest_keyword() {
+        let content = "<p>Binance will open deposits for ABC at 2024-05-01 08:00 (UTC).</p>\
+            <p>Trading for ABC/USDT opens at 2024-05-01 10:00 (UTC).</p>\
+            <p>Withdrawals open at May 2, 2024, 10:00 AM (UTC).</p>";
+        let schedule = extract(content);
+
+        assert_eq!(schedule.deposit_open_at, utc("2024-05-01T08:00:00Z"));
+        assert_eq!(schedule.trading_open_at, utc("2024-05-01T10:00:00Z"));
+        assert_eq!(schedule.withdrawal_open_at, utc("2024-05-02T10:00:00Z"));
+    }
+
+    #[test]
+    fn ignores_dates_without_a_time_or_keyword() {
+        assert_eq!(extract("Trading opens on 2024-05-01"), Schedule::default());
+        assert_eq!(extract("Published 2024-05-01 10:00 (UTC)"), Schedule::default());
+        assert_eq!(extract("Trading: 2024-13-01 10:00"), Schedule::default());
+    }
+
+    #[test]
+    fn handles_non_ascii_content() {
+        // Lowercasing "ẞ" and "İ" changes their byte lengths, the keyword search must
+        // still slice the text the dates were found in
+        let schedule = extract("ẞ 2024-05-01 10:00한 trading İ");
+        assert_eq!(schedule.trading_open_at, utc("2024-05-01T10:00:00Z"));
+
+        let schedule = extract("TRADING İSTANBUL ẞ: 2024-05-01 10:00 (UTC)");
+        assert_eq!(schedule.trading_open_at, utc("2024-05-01T10:00:00Z"));
+    }
+
+    #[test]
+    fn uses_the_exchange_timezone_for_korean_notices() {
+        let content = "입금 시작: 2024-05-01 17:00\n거래 지원 개시 시점: 2024-05-01 18:00";
+        let schedule = extract_schedule(content, default_offset("Upbit"));
+
+        assert_eq!(schedule.deposit_open_at, utc("2024-05-01T08:00:00Z"));
+        assert_eq!(schedule.trading_open_at, utc("2024-05-01T09:00:00Z"));
+
+        let content = "充值开放时间：2024-05-01 10:00 (UTC+8)，交易开放时间：2024-05-01 12:00 (UTC+8)";
+        let schedule = extract(content);
+        assert_eq!(schedule.deposit_open_at, utc("2024-05-01T02:00:00Z"));
+        assert_eq!(schedule.trading_open_at, utc("2024-05-01T04:00:00Z"));
+    }
+}
@@ -0,0 +1,273 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::classifier::RuleSet;
+use crate::models::announcement::{AnnouncementKind, ListedPair, MarketType};
+
+/// Quote currencies, longest first so that "FDUSD" is not read as "FD" + "USD"
+pub const QUOTE_CURRENCIES: &[&str] = &[
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "USDE", "EURI", "DAI", "USD", "EUR", "TRY", "BRL",
+    "KRW", "JPY", "BTC", "ETH", "BNB",
+];
+
+/// Upper-case words that look like symbols but are not tokens
+pub const DEFAULT_STOPWORDS: &[&str] = &[
+    "UTC", "GMT", "KST", "SGT", "HKT", "AM", "PM", "API", "FAQ", "KYC", "AML", "NFT", "APR", "APY",
+    "ETF", "CEO", "VIP", "TVL", "DEX", "CEX", "AMA", "BETA", "NEW", "THE", "AND", "FOR", "ALL",
+    "SPOT", "MARGIN", "FUTURES", "PERP", "SWAP", "USDⓈ", "ID", "TBA", "TBD", "Q1", "Q2", "Q3", "Q4",
+    "EN", "US", "UK", "EU", "HTTP", "HTTPS", "WWW", "HTML", "DIV", "SPAN", "BR",
+];
+
+lazy_static! {
+    static ref QUOTE_ALTERNATION: String = QUOTE_CURRENCIES.join("|");
+
+    /// "ABC/USDT" or "ABC / USDT"
+    static ref SLASH_PAIR: Regex = Regex::new(&format!(
+        r"\b([A-Z0-9]{{2,15}})\s?/\s?({})\b", *QUOTE_ALTERNATION
+    )).unwrap();
+
+    /// "ABC-USDT", "ABC-USDT-SWAP" or "ABC-USD-240628" (OKX instrument ids)
+    static ref DASH_PAIR: Regex = Regex::new(&format!(
+        r"\b([A-Z0-9]{{2,15}})-({})(?:-(SWAP|\d{{6}}))?\b", *QUOTE_ALTERNATION
+    )).unwrap();
+
+    /// "ABCUSDT", the base is matched lazily so the longest known quote wins
+    static ref CONCATENATED_PAIR: Regex = Regex::new(&format!(
+        r"\b([A-Z0-9]{{2,15}}?)({})\b", *QUOTE_ALTERNATION
+    )).unwrap();
+
+    /// "ABC Perpetual" or "ABCUSDT Perpetual"
+    static ref PERPETUAL: Regex = Regex::new(&format!(
+        r"\b([A-Z0-9]{{2,15}}?)({})?\s+(?i:perpetual)", *QUOTE_ALTERNATION
+    )).unwrap();
+
+    /// Upbit market codes put the quote first: "KRW-ABC", "BTC-ABC", "USDT-ABC"
+    static ref MARKET_CODE: Regex = Regex::new(&format!(
+        r"\b({})-([A-Z0-9]{{2,15}})\b", KOREAN_MARKETS.join("|")
+    )).unwrap();
+
+    /// Korean market markers, "KRW 마켓" or "KRW, BTC, USDT 마켓" (also "KRW Market")
+    static ref MARKET_MARKER: Regex = Regex::new(&format!(
+        r"(?i)\b((?:(?:{0})\s*(?:,|/|및|and)\s*)*(?:{0}))\s*(?:마켓|market)", KOREAN_MARKETS.join("|")
+    )).unwrap();
+
+    static ref MARKET_SEPARATOR: Regex = Regex::new(r"(?i)\s*(?:,|/|및|and)\s*").unwrap();
+}
+
+/// Markets of Korean exchanges, named after their quote currency
+const KOREAN_MARKETS: &[&str] = &["KRW", "BTC", "USDT"];
+
+/// Extract the trading pairs mentioned in an announcement.
+///
+/// Explicit pairs (`ABC/USDT`, `ABC-USDT-SWAP`, `ABCUSDT`, `ABC Perpetual`) are
+/// collected first, since they carry the quote currency and often the market type.
+/// Bare symbols matched by the rule set's symbol patterns, e.g. "(ABC)", are then
+/// added for bases not already covered by a pair. Quote currencies and stopwords
+/// are never reported as bare symbols.
+///
+/// Korean announcements name the markets instead of the pairs ("ABC(ABC) KRW, USDT
+/// 마켓 디지털 자산 추가"), bare symbols are then paired with every market mentioned.
+pub fn extract_pairs(rules: &RuleSet, title: &str, content: &str, kind: AnnouncementKind) -> Vec<ListedPair> {
+    let default_market = default_market_type(kind);
+    let mut pairs: Vec<ListedPair> = Vec::new();
+
+    // Markers in the title are the most precise, the content often lists every market
+    let markets = match market_markers(title) {
+        markets if !markets.is_empty() => markets,
+        _ => market_markers(content),
+    };
+
+    for text in [title, content] {
+        // Slash and dash pairs are explicit enough to skip the stopword check, "NEW/USDT"
+        // is a real pair even though "NEW" alone is noise
+        for cap in SLASH_PAIR.captures_iter(text) {
+            if has_letter(&cap[1]) {
+                push_unique(&mut pairs, pair(&cap[1], Some(&cap[2]), default_market));
+            }
+        }
+
+        for cap in DASH_PAIR.captures_iter(text) {
+            // "KRW-BTC" is an Upbit market code for BTC, handled below
+            if &cap[1] == "KRW" {
+                continue;
+            }
+            // A SWAP or expiry suffix is a derivatives instrument whatever the kind
+            let market_type = if cap.get(3).is_some() { MarketType::Futures } else { default_market };
+            if has_letter(&cap[1]) {
+                push_unique(&mut pairs, pair(&cap[1], Some(&cap[2]), market_type));
+            }
+        }
+
+        for cap in MARKET_CODE.captures_iter(text) {
+            // "BTC-USDT" is a regular dash pair, only KRW is never the base
+            if &cap[1] != "KRW" && QUOTE_CURRENCIES.contains(&&cap[2]) {
+                continue;
+            }
+            if is_valid_symbol(rules, &cap[2]) {
+                push_unique(&mut pairs, pair(&cap[2], Some(&cap[1]), default_market));
+            }
+        }
+
+        for cap in CONCATENATED_PAIR.captures_iter(text) {
+            // "FDUSD" would otherwise be read as "FD" + "USD"
+            if QUOTE_CURRENCIES.contains(&cap.get(0).map_or("", |m| m.as_str())) {
+                continue;
+            }
+            if is_valid_symbol(rules, &cap[1]) {
+                push_unique(&mut pairs, pair(&cap[1], Some(&cap[2]), default_market));
+            }
+        }
+
+        for cap in PERPETUAL.captures_iter(text) {
+            if is_valid_symbol(rules, &cap[1]) {
+                let quote = cap.get(2).map(|m| m.as_str());
+                // "ABC Perpetual" without a quote is already covered if we saw "ABCUSDT Perpetual"
+                if quote.is_none() && pairs.iter().any(|p| p.base == cap[1] && p.market_type == MarketType::Futures) {
+                    continue;
+                }
+                push_unique(&mut pairs, pair(&cap[1], quote, MarketType::Futures));
+            }
+        }
+    }
+
+    for text in [title, content] {
+        for symbol_pattern in rules.symbol_patterns() {
+            for cap in symbol_pattern.captures_iter(text) {
+                let Some(symbol) = cap.get(1) else { continue };
+                let symbol = symbol.as_str().to_uppercase();
+
+                if !is_valid_symbol(rules, &symbol) || QUOTE_CURRENCIES.contains(&symbol.as_str()) {
+                    continue;
+                }
+                if !markets.is_empty() {
+                    for market in &markets {
+                        push_unique(&mut pairs, pair(&symbol, Some(market), default_market));
+                    }
+                } else if !pairs.iter().any(|p| p.base == symbol) {
+                    push_unique(&mut pairs, pair(&symbol, None, default_market));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Quote currencies of the markets named in Korean market markers, in order
+fn market_markers(text: &str) -> Vec<String> {
+    let mut markets = Vec::new();
+    for cap in MARKET_MARKER.captures_iter(text) {
+        for market in MARKET_SEPARATOR.split(&cap[1]) {
+            let market = market.to_uppercase();
+            if !markets.contains(&market) {
+                markets.push(market);
+            }
+        }
+    }
+    markets
+}
+
+fn push_unique(pairs: &mut Vec<ListedPair>, pair: ListedPair) {
+    if !pairs.contains(&pair) {
+        pairs.push(pair);
+    }
+}
+
+fn pair(base: &str, quote: Option<&str>, market_type: MarketType) -> ListedPair {
+    ListedPair {
+        base: base.to_uppercase(),
+        quote: quote.map(|q| q.to_uppercase()),
+        market_type,
+    }
+}
+
+/// Market type implied by the announcement kind when the pair itself does not say
+fn default_market_type(kind: AnnouncementKind) -> MarketType {
+    match kind {
+        AnnouncementKind::FuturesListing => MarketType::Futures,
+        AnnouncementKind::MarginListing => MarketType::Margin,
+        _ => MarketType::Spot,
+    }
+}
+
+/// Symbols need at least one letter, "2024" is a year and not a token
+fn has_letter(symbol: &str) -> bool {
+    symbol.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Symbols outside of explicit pairs must also not be a stopword
+fn is_valid_symbol(rules: &RuleSet, symbol: &str) -> bool {
+    has_letter(symbol) && !rules.is_symbol_stopword(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Extract with the built-in rules, rendered as "BASE/QUOTE market"
+    fn pairs(title: &str, content: &str, kind: AnnouncementKind) -> Vec<String> {
+        extract_pairs(&RuleSet::builtin().unwrap(), title, content, kind)
+            .into_iter()
+            .map(|pair| format!("{}/{} {}", pair.base, pair.quote.as_deref().unwrap_or("-"), pair.market_type))
+            .collect()
+    }
+
+    #[test]
+    fn reads_slash_dash_and_concatenated_pairs() {
+        assert_eq!(
+            pairs("Binance Will Add NOT/USDT and NEW / FDUSD Margin Pairs", "", AnnouncementKind::MarginListing),
+            ["NOT/USDT margin", "NEW/FDUSD margin"]
+        );
+        assert_eq!(
+            pairs("OKX to list ZRO-USDT-SWAP and BTC-USD-240628", "Spot pair ZRO-USDT opens later", AnnouncementKind::SpotListing),
+            ["ZRO/USDT futures", "BTC/USD futures", "ZRO/USDT spot"]
+        );
+        assert_eq!(
+            pairs("Binance Futures Will Launch USDⓈ-M NOTUSDT Perpetual Contract", "", AnnouncementKind::FuturesListing),
+            ["NOT/USDT futures"]
+        );
+        assert_eq!(pairs("Bybit Will List the ZRO Perpetual", "", AnnouncementKind::FuturesListing), ["ZRO/- futures"]);
+    }
+
+    #[test]
+    fn does_not_split_quote_currencies() {
+        assert!(pairs("Binance Will Open Trading for FDUSD and USDC Pairs", "", AnnouncementKind::SpotListing).is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_bare_symbols_without_noise() {
+        assert_eq!(
+            pairs("Binance Will List Notcoin (NOT) with Seed Tag Applied", "", AnnouncementKind::SpotListing),
+            ["NOT/- spot"]
+        );
+        assert!(pairs(
+            "New Listing (2024)",
+            "Trading opens at 12:00 (UTC) after (KYC) checks, quote (USDT)",
+            AnnouncementKind::SpotListing,
+        ).is_empty());
+    }
+
+    #[test]
+    fn prefers_pairs_over_bare_symbols() {
+        assert_eq!(
+            pairs("Binance Will List Notcoin (NOT)", "Trading pairs: NOT/USDT, NOT/FDUSD", AnnouncementKind::SpotListing),
+            ["NOT/USDT spot", "NOT/FDUSD spot"]
+        );
+    }
+
+    #[test]
+    fn pairs_korean_listings_with_every_market_named() {
+        assert_eq!(
+            pairs("노트코인(NOT) KRW, USDT 마켓 디지털 자산 추가", "", AnnouncementKind::SpotListing),
+            ["NOT/KRW spot", "NOT/USDT spot"]
+        );
+        assert_eq!(
+            pairs("[거래] 지토(JTO) 신규 거래지원 안내", "KRW 마켓 및 BTC 마켓 거래 지원", AnnouncementKind::SpotListing),
+            ["JTO/KRW spot", "JTO/BTC spot"]
+        );
+        assert_eq!(
+            pairs("레이어제로 마켓 추가", "거래 가능 마켓: KRW-ZRO, BTC-ZRO", AnnouncementKind::SpotListing),
+            ["ZRO/KRW spot", "ZRO/BTC spot"]
+        );
+    }
+}
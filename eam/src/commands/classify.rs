@@ -0,0 +1,63 @@
+use anyhow::Result;
+
+use crate::classifier;
+use crate::config::ClassifyArgs;
+use crate::models::announcement::Announcement;
+
+/// Classify a title with the active rules and print which rule fired.
+///
+/// This is meant for tuning a rules file: it shows the exact rule, pattern and
+/// location that decided the kind, instead of only the end result.
+pub fn run(args: &ClassifyArgs) -> Result<()> {
+    let rules = classifier::active_rules();
+    let classification = rules.classify(&args.exchange, &args.title, &args.content);
+
+    // Run the regular analysis as well to show the extracted symbols and pairs
+    let mut announcement = Announcement::new(
+        String::new(),
+        args.title.clone(),
+        args.content.clone(),
+        String::new(),
+        args.exchange.clone(),
+        chrono::Utc::now(),
+    );
+    announcement.analyze_for_new_listing();
+
+    println!("Rules:       {}", rules.source());
+    println!("Kind:        {}", classification.kind);
+    println!("Confidence:  {:.2}", classification.confidence);
+    match &classification.rule {
+        Some(rule) => {
+            println!("Rule:        {} (priority {})", rule, classification.priority);
+            if let Some(matched_in) = classification.matched_in {
+                println!("Matched in:  {}", matched_in);
+            }
+            if let Some(pattern) = &classification.pattern {
+                println!("Pattern:     {}", pattern);
+            }
+        }
+        None => println!("Rule:        none matched"),
+    }
+    println!("New listing: {}", announcement.is_new_listing);
+    if !announcement.token_symbols.is_empty() {
+        println!("Symbols:     {}", announcement.token_symbols.join(", "));
+    }
+    for pair in &announcement.listed_pairs {
+        match &pair.quote {
+            Some(quote) => println!("Pair:        {}/{} ({})", pair.base, quote, pair.market_type),
+            None => println!("Pair:        {} ({})", pair.base, pair.market_type),
+        }
+    }
+    let schedule = [
+        ("Deposits:", announcement.deposit_open_at),
+        ("Trading:", announcement.trading_open_at),
+        ("Withdrawals:", announcement.withdrawal_open_at),
+    ];
+    for (label, time) in schedule {
+        if let Some(time) = time {
+            println!("{:<13}{}", label, time.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+    }
+
+    Ok(())
+}
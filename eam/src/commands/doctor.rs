@@ -0,0 +1,462 @@
+use anyhow::{Context, Result};
+use futures::future;
+use serde::Serialize;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use url::Url;
+
+use crate::config::{self, Config, DoctorArgs, OutputFormat};
+use crate::exchanges::{self, monitor::ExchangeMonitor};
+use crate::metrics::{self, diagnostics::{self, BodyFormat, FetchDiagnostics}};
+use crate::utils::{self, ProxyPolicy};
+
+/// Time allowed for the DNS lookup and the TCP connection of the endpoint probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Diagnosis of one exchange
+#[derive(Debug, Serialize)]
+struct Diagnosis {
+    exchange: String,
+    endpoint: String,
+    checks: Vec<Check>,
+}
+
+/// Outcome of one check, e.g. the DNS lookup
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: CheckStatus,
+    detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+    /// Not run because an earlier check failed or it does not apply
+    Skipped,
+}
+
+impl CheckStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Failed => "failed",
+            CheckStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into() }
+    }
+}
+
+/// Diagnose the endpoint of every selected exchange and print the results.
+///
+/// Each exchange gets two passes: a probe of its endpoint that checks DNS, the TCP
+/// connection, TLS and the HTTP response one after the other, and a regular fetch
+/// observed through `metrics::diagnostics`. Fails after printing if any check failed.
+pub async fn run(args: &DoctorArgs, config: &Config) -> Result<()> {
+    let selected = if args.exchanges.is_empty() {
+        config.enabled_exchanges.clone()
+    } else {
+        config::parse_exchanges(&args.exchanges, "--exchanges")?
+    };
+
+    for (exchange, settings) in &config.exchange_settings {
+        utils::set_proxy_policy(exchange, settings.proxy);
+    }
+
+    let monitors: Vec<Box<dyn ExchangeMonitor>> = exchanges::create_monitors(config, &utils::create_shared_client())
+        .into_iter()
+        .filter(|monitor| selected.iter().any(|name| name == monitor.exchange_name()))
+        .collect();
+    let diagnoses = future::join_all(monitors.iter().map(|monitor| diagnose(monitor.as_ref()))).await;
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match args.format {
+        OutputFormat::Table => write_table(&mut out, &diagnoses)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &diagnoses)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_csv(&mut out, &diagnoses)?,
+    }
+
+    let failed: Vec<&str> = diagnoses.iter()
+        .filter(|diagnosis| diagnosis.checks.iter().any(|check| check.status == CheckStatus::Failed))
+        .map(|diagnosis| diagnosis.exchange.as_str())
+        .collect();
+    if !failed.is_empty() {
+        anyhow::bail!("Checks failed for {} of {} exchanges: {}", failed.len(), diagnoses.len(), failed.join(", "));
+    }
+
+    Ok(())
+}
+
+async fn diagnose(monitor: &dyn ExchangeMonitor) -> Diagnosis {
+    let exchange = monitor.exchange_name();
+    let endpoint = monitor.endpoint();
+
+    let (mut checks, probe) = diagnostics::collect(metrics::with_exchange(exchange, probe_endpoint(endpoint))).await;
+
+    let started = Instant::now();
+    let (result, fetch) = diagnostics::collect(metrics::with_exchange(exchange, monitor.fetch_announcements())).await;
+    let elapsed = started.elapsed();
+
+    let statuses = if fetch.statuses.is_empty() {
+        String::new()
+    } else {
+        let statuses: Vec<String> = fetch.statuses.iter().map(u16::to_string).collect();
+        format!(", HTTP {}", statuses.join(", "))
+    };
+    match &result {
+        Ok(announcements) if announcements.is_empty() => checks.push(Check::new(
+            "fetch",
+            CheckStatus::Warning,
+            format!("no announcements in {:.2}s{}", elapsed.as_secs_f64(), statuses),
+        )),
+        Ok(announcements) => checks.push(Check::new(
+            "fetch",
+            CheckStatus::Ok,
+            format!("{} announcements in {:.2}s{}", announcements.len(), elapsed.as_secs_f64(), statuses),
+        )),
+        Err(e) => checks.push(Check::new("fetch", CheckStatus::Failed, format!("{:#}{}", e, statuses))),
+    }
+
+    let content_type = fetch.content_type.as_deref().filter(|t| !t.is_empty()).unwrap_or("no content type");
+    match fetch.body_format {
+        Some(BodyFormat::Json) => {
+            checks.push(Check::new("format", CheckStatus::Ok, format!("JSON parsed ({})", content_type)));
+        }
+        Some(BodyFormat::HtmlFallback) => checks.push(Check::new(
+            "format",
+            CheckStatus::Warning,
+            format!("JSON expected, fell back to the HTML extractor ({})", content_type),
+        )),
+        // The monitor reads its responses itself
+        None => {}
+    }
+
+    if let Ok(announcements) = &result {
+        checks.push(match &fetch.unparsed_date {
+            None => Check::new("dates", CheckStatus::Ok, format!("{} dates parsed", announcements.len())),
+            Some(example) => Check::new(
+                "dates",
+                CheckStatus::Warning,
+                format!(
+                    "{} dates failed to parse and were set to the current time, e.g. '{}'",
+                    fetch.date_parse_failures, example
+                ),
+            ),
+        });
+    }
+
+    checks.push(proxy_check(exchange, &probe, &fetch));
+
+    Diagnosis {
+        exchange: exchange.to_string(),
+        endpoint: endpoint.to_string(),
+        checks,
+    }
+}
+
+/// Check DNS, TCP, TLS and HTTP for the endpoint, in that order.
+///
+/// DNS and TCP are checked directly from this host, the HTTP request goes through
+/// the exchange's proxy like the monitor's requests do, so a failing direct
+/// connection with a working request points at a blocked network rather than the
+/// exchange. Behind a proxy, direct failures are only warnings.
+async fn probe_endpoint(endpoint: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let url = match Url::parse(endpoint) {
+        Ok(url) => url,
+        Err(e) => {
+            checks.push(Check::new("dns", CheckStatus::Failed, format!("invalid endpoint URL: {}", e)));
+            return checks;
+        }
+    };
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let https = url.scheme() == "https";
+
+    // Built inside the exchange's scope, so it uses the exchange's proxy policy
+    let client = utils::create_browser_client();
+    let proxied = diagnostics::current().is_some_and(|diagnostics| diagnostics.proxy.is_some());
+    let unreachable = if proxied { CheckStatus::Warning } else { CheckStatus::Failed };
+
+    let started = Instant::now();
+    let addresses: Vec<SocketAddr> = match tokio::time::timeout(PROBE_TIMEOUT, lookup_host((host, port))).await {
+        Ok(Ok(addresses)) => addresses.collect(),
+        Ok(Err(e)) => {
+            checks.push(Check::new("dns", unreachable, format!("{}: {}", host, e)));
+            Vec::new()
+        }
+        Err(_) => {
+            checks.push(Check::new("dns", unreachable, format!("{}: timed out", host)));
+            Vec::new()
+        }
+    };
+    if !addresses.is_empty() {
+        let shown: Vec<String> = addresses.iter().take(3).map(|address| address.ip().to_string()).collect();
+        let more = addresses.len().saturating_sub(shown.len());
+        checks.push(Check::new(
+            "dns",
+            CheckStatus::Ok,
+            format!(
+                "{} resolves to {}{} in {} ms",
+                host,
+                shown.join(", "),
+                if more > 0 { format!(" and {} more", more) } else { String::new() },
+                started.elapsed().as_millis()
+            ),
+        ));
+    }
+
+    let tcp_connected = match addresses.first() {
+        Some(address) => {
+            let started = Instant::now();
+            match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(address)).await {
+                Ok(Ok(_)) => {
+                    checks.push(Check::new(
+                        "tcp",
+                        CheckStatus::Ok,
+                        format!("connected to {} in {} ms", address, started.elapsed().as_millis()),
+                    ));
+                    true
+                }
+                Ok(Err(e)) => {
+                    checks.push(Check::new("tcp", unreachable, format!("{}: {}", address, e)));
+                    false
+                }
+                Err(_) => {
+                    checks.push(Check::new("tcp", unreachable, format!("{}: timed out", address)));
+                    false
+                }
+            }
+        }
+        None => {
+            checks.push(Check::new("tcp", CheckStatus::Skipped, "no address to connect to"));
+            false
+        }
+    };
+
+    let started = Instant::now();
+    match client.get(url.as_str()).send().await {
+        Ok(response) => {
+            if https {
+                checks.push(Check::new("tls", CheckStatus::Ok, "handshake completed"));
+            } else {
+                checks.push(Check::new("tls", CheckStatus::Skipped, "plain HTTP endpoint"));
+            }
+
+            let status = response.status();
+            let content_type = response.headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("no content type")
+                .to_string();
+            checks.push(Check::new(
+                "http",
+                if status.is_success() { CheckStatus::Ok } else { CheckStatus::Warning },
+                format!("GET returned {}, {}, in {} ms", status, content_type, started.elapsed().as_millis()),
+            ));
+        }
+        Err(e) if e.is_connect() && https && tcp_connected && !proxied => {
+            checks.push(Check::new("tls", CheckStatus::Failed, format!("{:#}", anyhow::Error::new(e))));
+            checks.push(Check::new("http", CheckStatus::Skipped, "no TLS connection"));
+        }
+        Err(e) => {
+            checks.push(Check::new(
+                "tls",
+                CheckStatus::Skipped,
+                if proxied { "request through a proxy failed" } else { "no connection" },
+            ));
+            checks.push(Check::new("http", CheckStatus::Failed, format!("{:#}", anyhow::Error::new(e))));
+        }
+    }
+
+    checks
+}
+
+/// Whether the probe and the fetch used the proxy configured with `PROXY` and
+/// `PORT_RANGE` or `SYSTEM_PROXY`
+fn proxy_check(exchange: &str, probe: &FetchDiagnostics, fetch: &FetchDiagnostics) -> Check {
+    if !utils::proxy_configured() {
+        return Check::new("proxy", CheckStatus::Ok, "no proxy configured, connecting directly");
+    }
+    if utils::proxy_policy(exchange) == ProxyPolicy::Direct {
+        return Check::new("proxy", CheckStatus::Ok, "proxy = \"direct\" in the config file, connecting directly");
+    }
+
+    let probe = match &probe.proxy {
+        Some(proxy) => format!("probe through {}", proxy),
+        None => "probe connected directly".to_string(),
+    };
+    let fetch = match &fetch.proxy {
+        Some(proxy) => format!("fetch through {}", proxy),
+        None => "fetch connected directly, this monitor does not use the proxy".to_string(),
+    };
+    Check::new("proxy", CheckStatus::Ok, format!("{}, {}", probe, fetch))
+}
+
+/// One block per exchange with a line per check
+fn write_table(out: &mut impl Write, diagnoses: &[Diagnosis]) -> Result<()> {
+    for (index, diagnosis) in diagnoses.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}  {}", diagnosis.exchange, diagnosis.endpoint)?;
+        for check in &diagnosis.checks {
+            writeln!(out, "  {:<7} {:<8} {}", check.name, check.status.as_str(), check.detail)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// CSV with a row per check
+fn write_csv(out: &mut impl Write, diagnoses: &[Diagnosis]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(["exchange", "endpoint", "check", "status", "detail"])?;
+
+    for diagnosis in diagnoses {
+        for check in &diagnosis.checks {
+            writer.write_record([
+                diagnosis.exchange.as_str(),
+                diagnosis.endpoint.as_str(),
+                check.name,
+                check.status.as_str(),
+                check.detail.as_str(),
+            ])?;
+        }
+    }
+
+    writer.flush().context("Failed to write CSV output")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use reqwest::Client;
+
+    use crate::exchanges::bitget::BitgetMonitor;
+    use crate::exchanges::monitor::MonitorOptions;
+    use crate::transport::{self, MonitorHttp};
+    use crate::utils;
+
+    fn statuses(diagnosis: &Diagnosis) -> Vec<(&'static str, CheckStatus)> {
+        diagnosis.checks.iter().map(|check| (check.name, check.status)).collect()
+    }
+
+    #[tokio::test]
+    async fn diagnoses_a_reachable_mirror() {
+        let router = Router::new()
+            .route("/api/v2/spot/public/support/notice/list", get(|| async {
+                include_str!("../../tests/fixtures/bitget/01-list.json")
+            }))
+            .route("/api/v2/spot/public/support/notice/detail", get(|| async {
+                include_str!("../../tests/fixtures/bitget/02-detail.json")
+            }));
+        let base_url = utils::serve(router).await;
+        let options = MonitorOptions {
+            base_url: Some(format!("{}/", base_url)),
+            ..MonitorOptions::default()
+        };
+        let http = MonitorHttp {
+            client: Some(Client::new()),
+            transport: transport::live(),
+        };
+        let monitor = BitgetMonitor::with_options(&options, http);
+
+        let diagnosis = diagnose(&monitor).await;
+        assert_eq!(diagnosis.exchange, "Bitget");
+        assert_eq!(diagnosis.endpoint, format!("{}/api/v2/spot/public/support/notice/list", base_url));
+        assert_eq!(statuses(&diagnosis), vec![
+            ("dns", CheckStatus::Ok),
+            ("tcp", CheckStatus::Ok),
+            ("tls", CheckStatus::Skipped),
+            ("http", CheckStatus::Ok),
+            ("fetch", CheckStatus::Ok),
+            // Bitget reads its responses itself, so there is no format check
+            ("dates", CheckStatus::Ok),
+            ("proxy", CheckStatus::Ok),
+        ]);
+        assert!(diagnosis.checks[4].detail.starts_with("2 announcements in "));
+    }
+
+    #[tokio::test]
+    async fn probe_stops_at_an_invalid_endpoint() {
+        let checks = probe_endpoint("not a url").await;
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].name, "dns");
+        assert_eq!(checks[0].status, CheckStatus::Failed);
+        assert!(checks[0].detail.starts_with("invalid endpoint URL: "));
+    }
+
+    #[tokio::test]
+    async fn probe_reports_a_refused_connection() {
+        // Bind and drop a listener to get a port nothing listens on
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let checks = probe_endpoint(&format!("http://{}/", address)).await;
+        let statuses: Vec<_> = checks.iter().map(|check| (check.name, check.status)).collect();
+        assert_eq!(statuses, vec![
+            ("dns", CheckStatus::Ok),
+            ("tcp", CheckStatus::Failed),
+            ("tls", CheckStatus::Skipped),
+            ("http", CheckStatus::Failed),
+        ]);
+    }
+
+    #[test]
+    fn writes_tables_and_csv() {
+        let diagnoses = vec![
+            Diagnosis {
+                exchange: "OKX".to_string(),
+                endpoint: "https://www.okx.com/api".to_string(),
+                checks: vec![
+                    Check::new("dns", CheckStatus::Ok, "resolves"),
+                    Check::new("fetch", CheckStatus::Warning, "no announcements, HTTP 200"),
+                ],
+            },
+            Diagnosis {
+                exchange: "Bybit".to_string(),
+                endpoint: "https://api.bybit.com".to_string(),
+                checks: vec![Check::new("http", CheckStatus::Failed, "timed out")],
+            },
+        ];
+
+        let mut table = Vec::new();
+        write_table(&mut table, &diagnoses).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "OKX  https://www.okx.com/api\n\
+             \x20 dns     ok       resolves\n\
+             \x20 fetch   warning  no announcements, HTTP 200\n\
+             \n\
+             Bybit  https://api.bybit.com\n\
+             \x20 http    failed   timed out\n"
+        );
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &diagnoses).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "exchange,endpoint,check,status,detail\n\
+             OKX,https://www.okx.com/api,dns,ok,resolves\n\
+             OKX,https://www.okx.com/api,fetch,warning,\"no announcements, HTTP 200\"\n\
+             Bybit,https://api.bybit.com,http,failed,timed out\n"
+        );
+    }
+}
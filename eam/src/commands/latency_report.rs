@@ -0,0 +1,230 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::config::{Config, LatencyReportArgs, OutputFormat};
+use crate::metrics::latency::{latency_seconds, LatencySummary};
+use crate::store::archive::{AnnouncementArchive, ArchiveQuery, ArchivedAnnouncement};
+
+/// One line of the report: the percentiles of one measure for one exchange
+#[derive(Debug, Serialize)]
+struct ReportRow {
+    exchange: String,
+    measure: &'static str,
+    #[serde(flatten)]
+    summary: LatencySummary,
+}
+
+/// Latency of one announcement in seconds, None if the measure does not apply
+type Measure = fn(&ArchivedAnnouncement) -> Option<f64>;
+
+/// The latencies reported, all measured from `published_at`
+const MEASURES: &[(&str, Measure)] = &[
+    // First appearance in the exchange's list endpoint
+    ("list", |archived| {
+        archived.announcement.listed_at
+            .map(|listed_at| latency_seconds(archived.announcement.published_at, listed_at))
+    }),
+    // Content retrieved from the detail endpoint, for exchanges that have one
+    ("detail", |archived| {
+        archived.announcement.content_fetched_at
+            .map(|fetched_at| latency_seconds(archived.announcement.published_at, fetched_at))
+    }),
+    // Reported as new
+    ("alert", |archived| {
+        archived.announcement.detected_at
+            .map(|detected_at| latency_seconds(archived.announcement.published_at, detected_at))
+    }),
+];
+
+/// Print detection latency percentiles per exchange, computed over the archive.
+///
+/// Only announcements this process reported as new are included, announcements
+/// recorded by a baseline poll or fetched before the archive existed were not
+/// detected live and would distort the numbers.
+pub async fn run(args: &LatencyReportArgs, config: &Config) -> Result<()> {
+    if !config.archive_path.exists() {
+        anyhow::bail!(
+            "Archive {} does not exist, run the monitors first or pass --archive",
+            config.archive_path.display()
+        );
+    }
+
+    let archive = AnnouncementArchive::open(&config.archive_path).await?;
+    let query = ArchiveQuery {
+        exchange: args.exchange.clone(),
+        kind: args.kind,
+        since: args.since,
+        until: args.until,
+        detected_only: true,
+        ..Default::default()
+    };
+    let results = archive.query(&query).await?;
+
+    let mut by_exchange: BTreeMap<&str, Vec<&ArchivedAnnouncement>> = BTreeMap::new();
+    for archived in &results {
+        by_exchange.entry(archived.announcement.exchange.as_str()).or_default().push(archived);
+    }
+
+    let mut rows = Vec::new();
+    for (exchange, announcements) in &by_exchange {
+        rows.extend(summarize(exchange, announcements));
+    }
+    if by_exchange.len() > 1 {
+        let all: Vec<&ArchivedAnnouncement> = results.iter().collect();
+        rows.extend(summarize("all", &all));
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match args.format {
+        OutputFormat::Table => write_table(&mut out, &rows)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &rows)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_csv(&mut out, &rows)?,
+    }
+
+    Ok(())
+}
+
+/// One row per measure that has samples
+fn summarize(exchange: &str, announcements: &[&ArchivedAnnouncement]) -> Vec<ReportRow> {
+    MEASURES.iter()
+        .filter_map(|(measure, latency)| {
+            let samples: Vec<f64> = announcements.iter().filter_map(|a| latency(a)).collect();
+            LatencySummary::from_samples(&samples).map(|summary| ReportRow {
+                exchange: exchange.to_string(),
+                measure,
+                summary,
+            })
+        })
+        .collect()
+}
+
+fn write_table(out: &mut impl Write, rows: &[ReportRow]) -> Result<()> {
+    if rows.is_empty() {
+        writeln!(out, "No detected announcements found")?;
+        return Ok(());
+    }
+
+    let exchange_width = rows.iter()
+        .map(|row| row.exchange.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("EXCHANGE".len());
+
+    writeln!(
+        out,
+        "{:<exchange_width$}  {:<7}  {:>5}  {:>8}  {:>8}  {:>8}  {:>8}",
+        "EXCHANGE", "MEASURE", "COUNT", "P50", "P90", "P99", "MAX",
+        exchange_width = exchange_width
+    )?;
+    for row in rows {
+        writeln!(
+            out,
+            "{:<exchange_width$}  {:<7}  {:>5}  {:>8}  {:>8}  {:>8}  {:>8}",
+            row.exchange,
+            row.measure,
+            row.summary.count,
+            format_duration(row.summary.p50),
+            format_duration(row.summary.p90),
+            format_duration(row.summary.p99),
+            format_duration(row.summary.max),
+            exchange_width = exchange_width
+        )?;
+    }
+
+    Ok(())
+}
+
+/// CSV with a header row, latencies in seconds
+fn write_csv(out: &mut impl Write, rows: &[ReportRow]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(["exchange", "measure", "count", "p50", "p90", "p99", "max"])?;
+    for row in rows {
+        writer.write_record([
+            row.exchange.clone(),
+            row.measure.to_string(),
+            row.summary.count.to_string(),
+            format!("{:.3}", row.summary.p50),
+            format!("{:.3}", row.summary.p90),
+            format!("{:.3}", row.summary.p99),
+            format!("{:.3}", row.summary.max),
+        ])?;
+    }
+    writer.flush().context("Failed to write CSV output")?;
+    Ok(())
+}
+
+/// Compact human-readable duration: "850ms", "12.3s", "4m05s", "2h03m", "3d04h"
+fn format_duration(seconds: f64) -> String {
+    if seconds < 1.0 {
+        return format!("{:.0}ms", seconds * 1000.0);
+    }
+    if seconds < 60.0 {
+        return format!("{:.1}s", seconds);
+    }
+
+    let seconds = seconds.round() as u64;
+    if seconds < 3600 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else if seconds < 86400 {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}d{:02}h", seconds / 86400, seconds % 86400 / 3600)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::announcement::Announcement;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn archived(listed: i64, fetched: Option<i64>, detected: Option<i64>) -> ArchivedAnnouncement {
+        let published_at = Utc.with_ymd_and_hms(2024, 6, 20, 8, 0, 0).unwrap();
+        let at = |seconds: i64| published_at + Duration::seconds(seconds);
+        let mut announcement = Announcement::new(
+            "1".to_string(),
+            "Binance Will List ABC".to_string(),
+            String::new(),
+            "https://www.binance.com/en/support/announcement/1".to_string(),
+            "Binance".to_string(),
+            published_at,
+        );
+        announcement.listed_at = Some(at(listed));
+        announcement.content_fetched_at = fetched.map(at);
+        announcement.detected_at = detected.map(at);
+
+        ArchivedAnnouncement {
+            announcement,
+            // The archiver stores announcements after the whole poll, long after listing
+            first_seen_at: at(3600),
+            last_seen_at: at(3600),
+            content_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn measures_list_detail_and_alert_latencies() {
+        let announcements = [archived(10, Some(12), Some(13)), archived(20, None, Some(21))];
+        let rows = summarize("Binance", &announcements.iter().collect::<Vec<_>>());
+
+        let measures: Vec<(&str, usize, f64)> = rows.iter()
+            .map(|row| (row.measure, row.summary.count, row.summary.max))
+            .collect();
+        assert_eq!(measures, [("list", 2, 20.0), ("detail", 1, 12.0), ("alert", 2, 21.0)]);
+    }
+
+    #[test]
+    fn formats_durations_compactly() {
+        assert_eq!(format_duration(0.85), "850ms");
+        assert_eq!(format_duration(12.34), "12.3s");
+        assert_eq!(format_duration(245.0), "4m05s");
+        assert_eq!(format_duration(7380.0), "2h03m");
+        assert_eq!(format_duration(273600.0), "3d04h");
+    }
+}
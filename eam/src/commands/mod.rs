@@ -0,0 +1,20 @@
+use anyhow::Result;
+
+use crate::config::{Command, Config};
+
+pub mod classify;
+pub mod doctor;
+pub mod latency_report;
+pub mod once;
+pub mod query;
+
+/// Run a subcommand instead of the monitors
+pub async fn run(command: &Command, config: &Config) -> Result<()> {
+    match command {
+        Command::Classify(args) => classify::run(args),
+        Command::Query(args) => query::run(args, config).await,
+        Command::LatencyReport(args) => latency_report::run(args, config).await,
+        Command::Once(args) => once::run(args, config).await,
+        Command::Doctor(args) => doctor::run(args, config).await,
+    }
+}
@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use futures::future;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::commands::query::{announcement_record, write_table, ANNOUNCEMENT_COLUMNS};
+use crate::config::{self, Config, OnceArgs, OutputFormat};
+use crate::exchanges::{self, monitor::ExchangeMonitor};
+use crate::metrics;
+use crate::models::announcement::Announcement;
+use crate::transport::{record::RecordingTransport, replay::ReplayTransport, HttpTransport, MonitorHttp};
+use crate::utils;
+
+/// Outcome of fetching one exchange
+#[derive(Debug, Serialize)]
+struct FetchResult {
+    exchange: String,
+    duration_seconds: f64,
+    /// Error chain of a failed fetch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    announcements: Vec<Announcement>,
+}
+
+/// Fetch announcements from the selected exchanges once, concurrently, and print them.
+///
+/// Every exchange is fetched even if another one fails. Fails after printing if any
+/// exchange did, so that scripts can tell from the exit status.
+pub async fn run(args: &OnceArgs, config: &Config) -> Result<()> {
+    let selected = if args.exchanges.is_empty() {
+        config.enabled_exchanges.clone()
+    } else {
+        config::parse_exchanges(&args.exchanges, "--exchanges")?
+    };
+
+    for (exchange, settings) in &config.exchange_settings {
+        utils::set_proxy_policy(exchange, settings.proxy);
+    }
+
+    let transports = fixture_transports(args, &selected).await?;
+    let client = utils::create_shared_client();
+    let http_for = |exchange: &str| {
+        let http = exchanges::monitor_http(exchange, &config.monitor_options(exchange), &client);
+        match transports.get(exchange) {
+            Some(transport) => MonitorHttp { transport: transport.clone(), ..http },
+            None => http,
+        }
+    };
+    let monitors: Vec<Box<dyn ExchangeMonitor>> = exchanges::create_monitors_with(config, http_for)
+        .into_iter()
+        .filter(|monitor| selected.iter().any(|name| name == monitor.exchange_name()))
+        .collect();
+    let results = future::join_all(monitors.iter().map(|monitor| fetch(monitor.as_ref()))).await;
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match args.format {
+        OutputFormat::Table => write_results(&mut out, &results)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &results)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            write_csv(&mut out, &results)?;
+            for result in &results {
+                if let Some(error) = &result.error {
+                    eprintln!("{}: {}", result.exchange, error);
+                }
+            }
+        }
+    }
+
+    let failed: Vec<&str> = results.iter()
+        .filter(|result| result.error.is_some())
+        .map(|result| result.exchange.as_str())
+        .collect();
+    if !failed.is_empty() {
+        anyhow::bail!("Failed to fetch {} of {} exchanges: {}", failed.len(), results.len(), failed.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Transports of the selected exchanges when recording or replaying fixtures, each
+/// exchange has its own fixture subdirectory, e.g. "gateio"
+async fn fixture_transports(args: &OnceArgs, selected: &[String]) -> Result<HashMap<String, Arc<dyn HttpTransport>>> {
+    let mut transports: HashMap<String, Arc<dyn HttpTransport>> = HashMap::new();
+    for exchange in selected {
+        if let Some(dir) = &args.record {
+            let dir = dir.join(exchanges::name_key(exchange));
+            tracing::info!(exchange = exchange, dir = %dir.display(), "Recording responses");
+            transports.insert(exchange.clone(), Arc::new(RecordingTransport::create(&dir).await?));
+        } else if let Some(dir) = &args.replay {
+            let dir = dir.join(exchanges::name_key(exchange));
+            transports.insert(exchange.clone(), Arc::new(ReplayTransport::load(&dir)?));
+        }
+    }
+    Ok(transports)
+}
+
+async fn fetch(monitor: &dyn ExchangeMonitor) -> FetchResult {
+    let exchange = monitor.exchange_name();
+    let started = Instant::now();
+    let result = metrics::with_exchange(exchange, monitor.fetch_announcements()).await;
+    let duration_seconds = started.elapsed().as_secs_f64();
+
+    match result {
+        Ok(announcements) => FetchResult {
+            exchange: exchange.to_string(),
+            duration_seconds,
+            error: None,
+            announcements,
+        },
+        Err(e) => FetchResult {
+            exchange: exchange.to_string(),
+            duration_seconds,
+            error: Some(format!("{:#}", e)),
+            announcements: Vec::new(),
+        },
+    }
+}
+
+/// One section per exchange: a summary line followed by its announcements
+fn write_results(out: &mut impl Write, results: &[FetchResult]) -> Result<()> {
+    for (index, result) in results.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        match &result.error {
+            Some(error) => {
+                writeln!(out, "{}: failed after {:.2}s: {}", result.exchange, result.duration_seconds, error)?;
+            }
+            None => {
+                writeln!(
+                    out,
+                    "{}: {} announcements in {:.2}s",
+                    result.exchange, result.announcements.len(), result.duration_seconds
+                )?;
+                let announcements: Vec<&Announcement> = result.announcements.iter().collect();
+                write_table(out, &announcements)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// CSV of the announcements of every exchange that succeeded
+fn write_csv(out: &mut impl Write, results: &[FetchResult]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(ANNOUNCEMENT_COLUMNS)?;
+
+    for announcement in results.iter().flat_map(|result| &result.announcements) {
+        writer.write_record(announcement_record(announcement))?;
+    }
+
+    writer.flush().context("Failed to write CSV output")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::exchanges::bitget::BitgetMonitor;
+    use crate::exchanges::monitor::MonitorOptions;
+
+    fn replay_args(dir: PathBuf) -> OnceArgs {
+        OnceArgs {
+            exchanges: Vec::new(),
+            format: OutputFormat::Table,
+            record: None,
+            replay: Some(dir),
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_each_exchange_from_its_subdirectory() {
+        let args = replay_args(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"));
+        let mut transports = fixture_transports(&args, &["Bitget".to_string()]).await.unwrap();
+        let transport = transports.remove("Bitget").unwrap();
+        assert!(transports.is_empty());
+
+        let monitor = BitgetMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(transport));
+        let result = fetch(&monitor).await;
+        assert_eq!(result.exchange, "Bitget");
+        assert!(result.error.is_none());
+        assert_eq!(result.announcements.len(), 2);
+
+        let missing = replay_args(std::env::temp_dir().join("eam-once-test-missing"));
+        assert!(fixture_transports(&missing, &["Bitget".to_string()]).await.is_err());
+    }
+
+    #[test]
+    fn writes_failures_and_announcements() {
+        let failed = FetchResult {
+            exchange: "OKX".to_string(),
+            duration_seconds: 1.25,
+            error: Some("HTTP 503".to_string()),
+            announcements: Vec::new(),
+        };
+        let binance = FetchResult {
+            exchange: "Binance".to_string(),
+            duration_seconds: 0.5,
+            error: None,
+            announcements: vec![
+                Announcement::sample("195342", "Binance Will List Notcoin (NOT)"),
+                Announcement::sample("195343", "Binance Will Delist MobileCoin (MOB)"),
+            ],
+        };
+        let results = [failed, binance];
+
+        let mut table = Vec::new();
+        write_results(&mut table, &results).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.starts_with("OKX: failed after 1.25s: HTTP 503\n\nBinance: 2 announcements in 0.50s\n"));
+        assert!(table.contains("PUBLISHED (UTC)"));
+        assert!(table.contains("2024-05-16 06:00"));
+
+        // Failed exchanges have no rows
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &results).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], ANNOUNCEMENT_COLUMNS.join(","));
+        assert!(lines[1..].iter().all(|line| line.starts_with("Binance,")));
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+
+use crate::config::{Config, OutputFormat, QueryArgs};
+use crate::models::announcement::Announcement;
+use crate::notifications::truncate_chars;
+use crate::store::archive::{AnnouncementArchive, ArchiveQuery, ArchivedAnnouncement};
+
+/// Maximum width of the title column in table output
+const TITLE_WIDTH: usize = 80;
+
+/// CSV columns describing the announcement itself, see `announcement_record`
+pub(crate) const ANNOUNCEMENT_COLUMNS: [&str; 10] = [
+    "exchange", "id", "published_at", "kind", "confidence", "symbols", "pairs", "title", "url",
+    "trading_open_at",
+];
+
+/// Search the announcement archive and print the results
+pub async fn run(args: &QueryArgs, config: &Config) -> Result<()> {
+    if !config.archive_path.exists() {
+        anyhow::bail!(
+            "Archive {} does not exist, run the monitors first or pass --archive",
+            config.archive_path.display()
+        );
+    }
+
+    let archive = AnnouncementArchive::open(&config.archive_path).await?;
+    let query = ArchiveQuery {
+        exchange: args.exchange.clone(),
+        symbol: args.symbol.clone(),
+        kind: args.kind,
+        since: args.since,
+        until: args.until,
+        detected_only: false,
+        limit: Some(args.limit),
+    };
+    let results = archive.query(&query).await?;
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match args.format {
+        OutputFormat::Table => {
+            let announcements: Vec<&Announcement> = results.iter().map(|archived| &archived.announcement).collect();
+            write_table(&mut out, &announcements)?
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &results)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_csv(&mut out, &results)?,
+    }
+
+    Ok(())
+}
+
+/// Human-readable table, one announcement per line
+pub(crate) fn write_table(out: &mut impl Write, announcements: &[&Announcement]) -> Result<()> {
+    if announcements.is_empty() {
+        writeln!(out, "No announcements found")?;
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 5]> = announcements.iter()
+        .map(|announcement| {
+            [
+                announcement.published_at.format("%Y-%m-%d %H:%M").to_string(),
+                announcement.exchange.clone(),
+                announcement.kind.to_string(),
+                announcement.token_symbols.join(","),
+                truncate_chars(&announcement.title, TITLE_WIDTH),
+            ]
+        })
+        .collect();
+
+    let headers = ["PUBLISHED (UTC)", "EXCHANGE", "KIND", "SYMBOLS", "TITLE"];
+    let mut widths = headers.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: [&str; 5]| {
+        cells.iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    writeln!(out, "{}", format_row(headers))?;
+    for row in &rows {
+        writeln!(out, "{}", format_row(row.each_ref().map(|cell| cell.as_str())))?;
+    }
+
+    Ok(())
+}
+
+/// CSV with a header row, lists are joined with spaces
+fn write_csv(out: &mut impl Write, results: &[ArchivedAnnouncement]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(
+        ANNOUNCEMENT_COLUMNS.iter().chain(&["first_seen_at", "last_seen_at", "content_hash"]),
+    )?;
+
+    for archived in results {
+        writer.write_record(announcement_record(&archived.announcement).into_iter().chain([
+            archived.first_seen_at.to_rfc3339(),
+            archived.last_seen_at.to_rfc3339(),
+            archived.content_hash.clone(),
+        ]))?;
+    }
+
+    writer.flush().context("Failed to write CSV output")?;
+    Ok(())
+}
+
+/// CSV fields of an announcement, in the order of `ANNOUNCEMENT_COLUMNS`
+pub(crate) fn announcement_record(announcement: &Announcement) -> [String; 10] {
+    let pairs = announcement.listed_pairs.iter()
+        .map(|pair| match &pair.quote {
+            Some(quote) => format!("{}/{}:{}", pair.base, quote, pair.market_type),
+            None => format!("{}:{}", pair.base, pair.market_type),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    [
+        announcement.exchange.clone(),
+        announcement.id.clone(),
+        announcement.published_at.to_rfc3339(),
+        announcement.kind.to_string(),
+        format!("{:.2}", announcement.confidence),
+        announcement.token_symbols.join(" "),
+        pairs,
+        announcement.title.clone(),
+        announcement.url.clone(),
+        announcement.trading_open_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+    ]
+}
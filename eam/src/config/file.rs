@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::UpdateNotifications;
+use crate::utils::ProxyPolicy;
+
+/// Contents of the TOML file given with `--config`.
+///
+/// Unknown keys are rejected rather than ignored, so that a typo such as `intreval`
+/// fails at startup instead of silently keeping the default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Default polling interval in seconds
+    pub interval: Option<u64>,
+    /// Exchanges to monitor, all if absent
+    pub enabled_exchanges: Option<Vec<String>>,
+    pub log_level: Option<String>,
+    /// Classification rules file
+    pub rules: Option<PathBuf>,
+    /// Which edited announcements are sent to the notification sinks
+    pub notify_updates: Option<UpdateNotifications>,
+    /// `[exchanges.<name>]` sections, keyed by the name as written in the file
+    #[serde(default)]
+    pub exchanges: BTreeMap<String, ExchangeSection>,
+}
+
+/// Settings of one exchange, every key is optional
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExchangeSection {
+    /// Turn the exchange on or off, unless `--exchanges` or `ENABLED_EXCHANGES` is set
+    pub enabled: Option<bool>,
+    /// Polling interval in seconds
+    pub interval: Option<u64>,
+    pub language: Option<String>,
+    pub page_size: Option<u32>,
+    pub catalog_ids: Option<Vec<String>>,
+    /// Base URL replacing the exchange's own API, e.g. a mirror or a mock server
+    pub base_url: Option<String>,
+    pub proxy: Option<ProxyPolicy>,
+    /// Names of the sinks that receive this exchange's notifications, all if absent
+    pub sinks: Option<Vec<String>>,
+}
+
+impl ConfigFile {
+    /// Read and parse a config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+}
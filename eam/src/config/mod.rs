@@ -1,50 +1,385 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::exchanges::{self, find_exchange, SUPPORTED_EXCHANGES};
+use crate::exchanges::monitor::{MonitorOptions, RequestSetting};
+use crate::models::announcement::{Announcement, AnnouncementKind};
+use crate::notifications::SINK_NAMES;
+use crate::notifications::discord::DiscordSinkConfig;
+use crate::notifications::slack::SlackSinkConfig;
+use crate::notifications::telegram::{TelegramChat, TelegramSinkConfig};
+use crate::notifications::webhook::WebhookSinkConfig;
+use crate::utils::ProxyPolicy;
+
+mod file;
+pub mod reload;
+
+use self::file::{ConfigFile, ExchangeSection};
+
+/// Polling interval used when none is configured
+const DEFAULT_POLLING_INTERVAL: u64 = 300;
+
+/// Log level used when none is configured
+const DEFAULT_LOG_LEVEL: &str = "info";
 
 /// Configuration for the exchange announcement monitoring application
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Config file given with `--config`, watched for changes
+    pub config_path: Option<PathBuf>,
     /// Default interval in seconds between polling exchanges for new announcements
     pub default_polling_interval: u64,
-    /// Exchange-specific polling intervals (if specified)
+    /// Exchange-specific polling intervals (if specified), keyed by exchange name
     pub exchange_intervals: HashMap<String, u64>,
-    /// Enable monitoring for specific exchanges, or all if empty
+    /// Names of the exchanges to monitor
     pub enabled_exchanges: Vec<String>,
+    /// Settings from the `[exchanges.<name>]` sections of the config file, keyed by
+    /// exchange name
+    pub exchange_settings: HashMap<String, ExchangeSettings>,
     /// Log level
     pub log_level: String,
+    /// Path of the file-backed seen-announcement store
+    pub seen_store_path: PathBuf,
+    /// Record the first poll of every exchange as seen without reporting it
+    pub baseline_first_poll: bool,
+    /// Path of the SQLite archive of every fetched announcement
+    pub archive_path: PathBuf,
+    /// Address of the embedded HTTP server, disabled if absent
+    pub http_addr: Option<SocketAddr>,
+    /// Generic JSON webhook sink, if configured
+    pub webhook: Option<WebhookSinkConfig>,
+    /// Telegram bot sink, if configured
+    pub telegram: Option<TelegramSinkConfig>,
+    /// Discord incoming-webhook sink, if configured
+    pub discord: Option<DiscordSinkConfig>,
+    /// Slack incoming-webhook sink, if configured
+    pub slack: Option<SlackSinkConfig>,
+    /// Classification rules file, the built-in rules are used if absent
+    pub rules_path: Option<PathBuf>,
+    /// Which edited announcements are sent to the notification sinks
+    pub notify_updates: UpdateNotifications,
+    /// Seconds allowed for a graceful shutdown before the process exits anyway
+    pub shutdown_timeout: u64,
+    /// Subcommand to run instead of the monitors
+    pub command: Option<Command>,
+    /// Environment variables that override a setting of the config file, logged once
+    /// the logger is set up since edits of that setting in the file have no effect
+    pub warnings: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
 #[command(name = "exchange-announcement-monitoring")]
 #[command(about = "Monitor cryptocurrency exchange announcements for new token listings")]
 pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    
+    /// Path to a TOML config file with [exchanges.<name>] sections
+    /// Command-line arguments and environment variables take precedence over it
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    
     /// Exchanges to monitor (comma-separated list)
     /// Leave empty to monitor all available exchanges
     #[arg(short, long, value_delimiter = ',')]
     pub exchanges: Vec<String>,
     
     /// Default interval in seconds between polling exchanges for announcements
-    #[arg(short, long, default_value = "300")]
-    pub interval: u64,
+    /// Defaults to 300
+    #[arg(short, long)]
+    pub interval: Option<u64>,
     
     /// Exchange-specific polling intervals in the format exchange:seconds
     /// Example: binance:60,okx:120
     #[arg(long, value_delimiter = ',')]
     pub exchange_intervals: Vec<String>,
     
-    /// Log level (trace, debug, info, warn, error)
-    #[arg(long, default_value = "info")]
-    pub log_level: String,
+    /// Log level (trace, debug, info, warn, error), RUST_LOG overrides it
+    /// Defaults to info
+    #[arg(long)]
+    pub log_level: Option<String>,
     
     /// Path to dotenv file for configuration
     #[arg(long)]
     pub env_file: Option<PathBuf>,
+    
+    /// Path to the seen-announcement store used to report each announcement only once
+    #[arg(long, default_value = "seen_announcements.jsonl")]
+    pub seen_store: PathBuf,
+    
+    /// Treat the first poll after startup as a silent baseline
+    /// Announcements already online at startup are recorded but not reported
+    #[arg(long)]
+    pub baseline_first_poll: bool,
+    
+    /// Path to the SQLite archive that keeps every fetched announcement
+    #[arg(long, global = true, default_value = "announcements.db")]
+    pub archive: PathBuf,
+    
+    /// Address of the embedded HTTP server serving /metrics and the API, e.g. 127.0.0.1:9898
+    /// The server is disabled unless this is set
+    #[arg(long)]
+    pub http_addr: Option<SocketAddr>,
+    
+    /// Seconds to wait for polls, notifications and storage to finish on SIGINT or SIGTERM
+    #[arg(long, default_value = "30")]
+    pub shutdown_timeout: u64,
+    
+    /// Notify the sinks when an announcement is edited after it was first fetched
+    /// Defaults to off
+    #[arg(long, value_enum)]
+    pub notify_updates: Option<UpdateNotifications>,
+    
+    /// URL of a webhook that receives a JSON POST for every new listing
+    #[arg(long)]
+    pub webhook_url: Option<String>,
+    
+    /// Extra header sent with webhook requests in the format "Name: value"
+    /// Can be given multiple times
+    #[arg(long = "webhook-header")]
+    pub webhook_headers: Vec<String>,
+    
+    /// Path to a file with the webhook body template
+    /// Defaults to the serialized announcement
+    #[arg(long)]
+    pub webhook_template: Option<PathBuf>,
+    
+    /// Maximum number of attempts for each webhook delivery
+    /// Applies to the generic, Discord and Slack webhooks
+    #[arg(long, default_value = "3")]
+    pub webhook_retries: usize,
+    
+    /// Telegram bot token used to send new listing messages
+    #[arg(long)]
+    pub telegram_bot_token: Option<String>,
+    
+    /// Telegram chat to notify in the format chat_id[:exchange,exchange]
+    /// Without an exchange list the chat receives all exchanges. Can be given multiple times
+    #[arg(long = "telegram-chat")]
+    pub telegram_chats: Vec<String>,
+    
+    /// Base URL of the Telegram Bot API
+    #[arg(long, default_value = "https://api.telegram.org")]
+    pub telegram_api_url: String,
+    
+    /// Maximum number of attempts for each Telegram message
+    #[arg(long, default_value = "3")]
+    pub telegram_retries: usize,
+    
+    /// Discord incoming webhook URL that receives new listing embeds
+    #[arg(long)]
+    pub discord_webhook_url: Option<String>,
+    
+    /// Exchanges forwarded to Discord (comma-separated list)
+    /// Leave empty to forward all exchanges
+    #[arg(long, value_delimiter = ',')]
+    pub discord_exchanges: Vec<String>,
+    
+    /// Slack incoming webhook URL that receives new listing messages
+    #[arg(long)]
+    pub slack_webhook_url: Option<String>,
+    
+    /// Exchanges forwarded to Slack (comma-separated list)
+    /// Leave empty to forward all exchanges
+    #[arg(long, value_delimiter = ',')]
+    pub slack_exchanges: Vec<String>,
+    
+    /// Path to a TOML or YAML file with classification rules
+    /// The file is reloaded automatically when it changes
+    #[arg(long, global = true)]
+    pub rules: Option<PathBuf>,
+}
+
+/// Settings of one exchange from its `[exchanges.<name>]` section of the config file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExchangeSettings {
+    /// Request settings passed to the exchange's monitor
+    pub options: MonitorOptions,
+    pub proxy: ProxyPolicy,
+    /// Names of the sinks receiving the exchange's notifications, all sinks if None
+    pub sinks: Option<Vec<String>>,
+}
+
+/// Subcommands that run instead of the monitors
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Classify an announcement title and show which rule fired
+    Classify(ClassifyArgs),
+    /// Search the announcement archive
+    Query(QueryArgs),
+    /// Report detection latency percentiles per exchange from the archive
+    LatencyReport(LatencyReportArgs),
+    /// Fetch announcements from each exchange once, print them and exit
+    Once(OnceArgs),
+    /// Check DNS, TLS, HTTP, parsing and proxy use of every exchange endpoint
+    Doctor(DoctorArgs),
+}
+
+/// Output format of the commands printing announcements
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Which edited announcements are sent to the notification sinks
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateNotifications {
+    /// Edits are only logged and archived
+    Off,
+    /// Edits of listing announcements, which often add the trading time or networks
+    Listings,
+    /// Edits of every announcement
+    All,
+}
+
+impl UpdateNotifications {
+    /// Check if an edit of the given announcement should be notified
+    pub fn accepts(self, announcement: &Announcement) -> bool {
+        match self {
+            UpdateNotifications::Off => false,
+            UpdateNotifications::Listings => announcement.is_new_listing,
+            UpdateNotifications::All => true,
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ClassifyArgs {
+    /// Announcement title to classify
+    pub title: String,
+    
+    /// Announcement content, if any
+    #[arg(long, default_value = "")]
+    pub content: String,
+    
+    /// Exchange the announcement belongs to, selects exchange-specific rules
+    #[arg(long, default_value = "")]
+    pub exchange: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct QueryArgs {
+    /// Only announcements from this exchange
+    #[arg(long)]
+    pub exchange: Option<String>,
+    
+    /// Only announcements mentioning this token symbol
+    #[arg(long)]
+    pub symbol: Option<String>,
+    
+    /// Only announcements of this kind, e.g. spot_listing or delisting
+    #[arg(long)]
+    pub kind: Option<AnnouncementKind>,
+    
+    /// Only announcements published at or after this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<DateTime<Utc>>,
+    
+    /// Only announcements published up to this date (YYYY-MM-DD, inclusive, or RFC 3339)
+    #[arg(long, value_parser = parse_until)]
+    pub until: Option<DateTime<Utc>>,
+    
+    /// Maximum number of announcements, newest first
+    #[arg(long, default_value = "50")]
+    pub limit: usize,
+    
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LatencyReportArgs {
+    /// Only announcements from this exchange
+    #[arg(long)]
+    pub exchange: Option<String>,
+    
+    /// Only announcements of this kind, e.g. spot_listing
+    #[arg(long)]
+    pub kind: Option<AnnouncementKind>,
+    
+    /// Only announcements published at or after this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<DateTime<Utc>>,
+    
+    /// Only announcements published up to this date (YYYY-MM-DD, inclusive, or RFC 3339)
+    #[arg(long, value_parser = parse_until)]
+    pub until: Option<DateTime<Utc>>,
+    
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct OnceArgs {
+    /// Exchanges to fetch (comma-separated list)
+    /// Defaults to the enabled exchanges
+    #[arg(long, value_delimiter = ',')]
+    pub exchanges: Vec<String>,
+    
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+    
+    /// Save every response as a fixture under this directory, one subdirectory per
+    /// exchange, replacing the fixtures already there
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    
+    /// Answer the requests from the fixtures under this directory instead of the
+    /// network
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DoctorArgs {
+    /// Exchanges to diagnose (comma-separated list)
+    /// Defaults to the enabled exchanges
+    #[arg(long, value_delimiter = ',')]
+    pub exchanges: Vec<String>,
+    
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+/// Parse a date given as YYYY-MM-DD or RFC 3339, a date means the start of that day in UTC
+pub(crate) fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    Ok(DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("Invalid date '{}', expected YYYY-MM-DD or RFC 3339", value))?
+        .with_timezone(&Utc))
+}
+
+/// Like `parse_since`, but a date means the end of that day so that it is inclusive
+pub(crate) fn parse_until(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let next_day = date.succ_opt().context("Date out of range")?;
+        return Ok(next_day.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    parse_since(value)
 }
 
 impl Config {
-    /// Create a new configuration from command line arguments and environment variables
+    /// Create a new configuration from the command line arguments, environment variables
+    /// and config file.
+    ///
+    /// A setting given on the command line wins over the same setting in an environment
+    /// variable, which wins over the config file. An exchange's own polling interval
+    /// wins over the default interval, wherever either comes from.
     pub fn new() -> Result<Self> {
         let args = CliArgs::parse();
         
@@ -56,25 +391,327 @@ impl Config {
             let _ = dotenv::dotenv();
         }
         
+        Self::from_args(args)
+    }
+    
+    /// Build the configuration from parsed command line arguments and the environment
+    /// variables already loaded, see `new`
+    fn from_args(args: CliArgs) -> Result<Self> {
+        let file = match &args.config {
+            Some(path) => ConfigFile::load(path)?,
+            None => ConfigFile::default(),
+        };
+        
+        let mut warnings = Vec::new();
+        
+        let env_log_level = env_var("LOG_LEVEL");
+        if args.log_level.is_none() && env_log_level.is_some() && file.log_level.is_some() {
+            warnings.push(shadowed("LOG_LEVEL", "log_level in the config file"));
+        }
+        let log_level = args.log_level.clone()
+            .or(env_log_level)
+            .or_else(|| file.log_level.clone())
+            .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string());
+        tracing::Level::from_str(&log_level)
+            .map_err(|_| anyhow::anyhow!("Invalid log level '{}', expected trace, debug, info, warn or error", log_level))?;
+        
+        let default_polling_interval = resolve_interval([
+            ("--interval", args.interval),
+            ("DEFAULT_POLLING_INTERVAL", env_parse("DEFAULT_POLLING_INTERVAL")?),
+            ("interval in the config file", file.interval),
+        ], &mut warnings)?.unwrap_or(DEFAULT_POLLING_INTERVAL);
+        
+        let webhook = Self::parse_webhook_config(&args)?;
+        let telegram = Self::parse_telegram_config(&args)?;
+        let discord = args.discord_webhook_url.clone().map(|webhook_url| DiscordSinkConfig {
+            webhook_url,
+            exchanges: args.discord_exchanges.clone(),
+            max_retries: args.webhook_retries.max(1),
+        });
+        let slack = args.slack_webhook_url.clone().map(|webhook_url| SlackSinkConfig {
+            webhook_url,
+            exchanges: args.slack_exchanges.clone(),
+            max_retries: args.webhook_retries.max(1),
+        });
+        let configured_sinks: Vec<&str> = [
+            ("webhook", webhook.is_some()),
+            ("telegram", telegram.is_some()),
+            ("discord", discord.is_some()),
+            ("slack", slack.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, configured)| configured.then_some(name))
+        .collect();
+        
+        // Config file sections, keyed by exchange name
+        let mut sections: HashMap<&str, (&str, &ExchangeSection)> = HashMap::new();
+        for (key, section) in &file.exchanges {
+            let exchange = parse_exchange(key, "the [exchanges] sections of the config file")?;
+            if let Some((other, _)) = sections.insert(exchange, (key, section)) {
+                anyhow::bail!("[exchanges.{}] and [exchanges.{}] both configure {}", other, key, exchange);
+            }
+        }
+        
         // Parse exchange-specific intervals
-        let mut exchange_intervals = HashMap::new();
+        let mut cli_intervals = HashMap::new();
         for interval_str in &args.exchange_intervals {
-            let parts: Vec<&str> = interval_str.split(':').collect();
-            if parts.len() == 2 {
-                if let Ok(seconds) = parts[1].parse::<u64>() {
-                    exchange_intervals.insert(parts[0].to_string(), seconds);
-                }
+            let (name, seconds) = interval_str
+                .split_once(':')
+                .with_context(|| format!("Invalid --exchange-intervals entry '{}', expected exchange:seconds", interval_str))?;
+            let exchange = parse_exchange(name, "--exchange-intervals")?;
+            let seconds = seconds.trim().parse::<u64>()
+                .with_context(|| format!("Invalid interval '{}' for {} in --exchange-intervals", seconds, name))?;
+            cli_intervals.insert(exchange, seconds);
+        }
+        
+        let mut exchange_intervals = HashMap::new();
+        let mut exchange_settings = HashMap::new();
+        for &(exchange, _) in SUPPORTED_EXCHANGES {
+            let section = sections.get(exchange);
+            let env_name = format!("{}_POLLING_INTERVAL", exchanges::name_key(exchange).to_uppercase());
+            let file_source = format!("[exchanges.{}] interval", section.map_or(exchange, |(key, _)| key));
+            
+            let interval = resolve_interval([
+                ("--exchange-intervals", cli_intervals.get(exchange).copied()),
+                (&env_name, env_parse(&env_name)?),
+                (&file_source, section.and_then(|(_, section)| section.interval)),
+            ], &mut warnings)?;
+            if let Some(interval) = interval {
+                exchange_intervals.insert(exchange.to_string(), interval);
+            }
+            
+            if let Some((key, section)) = section {
+                let settings = Self::parse_exchange_section(exchange, key, section, &configured_sinks)?;
+                exchange_settings.insert(exchange.to_string(), settings);
             }
         }
         
+        let enabled_exchanges = Self::resolve_enabled_exchanges(&args, &file, &sections, &mut warnings)?;
+        
         Ok(Self {
-            default_polling_interval: args.interval,
+            config_path: args.config,
+            default_polling_interval,
             exchange_intervals,
-            enabled_exchanges: args.exchanges,
-            log_level: args.log_level,
+            enabled_exchanges,
+            exchange_settings,
+            log_level,
+            seen_store_path: args.seen_store,
+            baseline_first_poll: args.baseline_first_poll,
+            archive_path: args.archive,
+            http_addr: args.http_addr,
+            webhook,
+            telegram,
+            discord,
+            slack,
+            rules_path: args.rules.or(file.rules),
+            notify_updates: args.notify_updates.or(file.notify_updates).unwrap_or(UpdateNotifications::Off),
+            shutdown_timeout: args.shutdown_timeout,
+            command: args.command,
+            warnings,
+        })
+    }
+    
+    /// Decide which exchanges to monitor.
+    ///
+    /// `--exchanges` or else `ENABLED_EXCHANGES` is taken as is. Otherwise the config
+    /// file's `enabled_exchanges` (all exchanges if absent) is adjusted by the `enabled`
+    /// key of the exchange sections.
+    fn resolve_enabled_exchanges(
+        args: &CliArgs,
+        file: &ConfigFile,
+        sections: &HashMap<&str, (&str, &ExchangeSection)>,
+        warnings: &mut Vec<String>,
+    ) -> Result<Vec<String>> {
+        let env_exchanges = env_var("ENABLED_EXCHANGES").map(|value| {
+            value.split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<_>>()
+        });
+        
+        let mut enabled: Vec<&str> = if !args.exchanges.is_empty() {
+            return parse_exchanges(&args.exchanges, "--exchanges");
+        } else if let Some(names) = env_exchanges {
+            let in_file = file.enabled_exchanges.is_some()
+                || sections.values().any(|(_, section)| section.enabled.is_some());
+            if in_file {
+                warnings.push(shadowed("ENABLED_EXCHANGES", "the exchanges enabled in the config file"));
+            }
+            return parse_exchanges(&names, "ENABLED_EXCHANGES");
+        } else if let Some(names) = &file.enabled_exchanges {
+            names.iter()
+                .map(|name| parse_exchange(name, "enabled_exchanges in the config file"))
+                .collect::<Result<_>>()?
+        } else {
+            SUPPORTED_EXCHANGES.iter().map(|(exchange, _)| *exchange).collect()
+        };
+        
+        for (exchange, (_, section)) in sections {
+            match section.enabled {
+                Some(true) if !enabled.contains(exchange) => enabled.push(exchange),
+                Some(false) => enabled.retain(|e| e != exchange),
+                _ => {}
+            }
+        }
+        
+        // Keep the usual order of the exchanges
+        Ok(SUPPORTED_EXCHANGES.iter()
+            .map(|(exchange, _)| *exchange)
+            .filter(|exchange| enabled.contains(exchange))
+            .map(str::to_string)
+            .collect())
+    }
+    
+    /// Check an `[exchanges.<name>]` section against what the exchange's monitor supports
+    /// and the sinks that are configured
+    fn parse_exchange_section(
+        exchange: &str,
+        key: &str,
+        section: &ExchangeSection,
+        configured_sinks: &[&str],
+    ) -> Result<ExchangeSettings> {
+        let supported = exchanges::supported_settings(exchange);
+        let ensure_supported = |setting: RequestSetting| {
+            if supported.contains(&setting) {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "[exchanges.{}] {} is not supported by the {} monitor",
+                    key, setting.name(), exchange
+                ))
+            }
+        };
+        
+        if let Some(language) = &section.language {
+            ensure_supported(RequestSetting::Language)?;
+            if language.trim().is_empty() {
+                anyhow::bail!("[exchanges.{}] language must not be empty", key);
+            }
+        }
+        
+        if let Some(page_size) = section.page_size {
+            ensure_supported(RequestSetting::PageSize)?;
+            if page_size == 0 {
+                anyhow::bail!("[exchanges.{}] page_size must be at least 1", key);
+            }
+        }
+        
+        if let Some(catalog_ids) = &section.catalog_ids {
+            ensure_supported(RequestSetting::CatalogIds)?;
+            if catalog_ids.is_empty() || catalog_ids.iter().any(|id| id.trim().is_empty()) {
+                anyhow::bail!("[exchanges.{}] catalog_ids must list at least one non-empty ID", key);
+            }
+        }
+        
+        if let Some(base_url) = &section.base_url {
+            let valid = url::Url::parse(base_url)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host() && url.query().is_none());
+            if !valid {
+                anyhow::bail!("[exchanges.{}] base_url must be an http or https URL without a query, got '{}'", key, base_url);
+            }
+        }
+        
+        if let Some(sinks) = &section.sinks {
+            for sink in sinks {
+                if !SINK_NAMES.contains(&sink.as_str()) {
+                    anyhow::bail!(
+                        "[exchanges.{}] sinks: unknown sink '{}', expected one of {}",
+                        key, sink, SINK_NAMES.join(", ")
+                    );
+                }
+                if !configured_sinks.contains(&sink.as_str()) {
+                    anyhow::bail!("[exchanges.{}] sinks: the {} sink is not configured", key, sink);
+                }
+            }
+        }
+        
+        Ok(ExchangeSettings {
+            options: MonitorOptions {
+                language: section.language.clone(),
+                page_size: section.page_size,
+                catalog_ids: section.catalog_ids.clone().unwrap_or_default(),
+                base_url: section.base_url.clone(),
+            },
+            proxy: section.proxy.unwrap_or_default(),
+            sinks: section.sinks.clone(),
         })
     }
     
+    /// Build the webhook sink configuration from the command line arguments
+    fn parse_webhook_config(args: &CliArgs) -> Result<Option<WebhookSinkConfig>> {
+        let Some(url) = &args.webhook_url else {
+            return Ok(None);
+        };
+        
+        let mut headers = Vec::new();
+        for header in &args.webhook_headers {
+            let (name, value) = header
+                .split_once(':')
+                .with_context(|| format!("Invalid webhook header '{}', expected 'Name: value'", header))?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        
+        let body_template = match &args.webhook_template {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read webhook template {}", path.display()))?,
+            ),
+            None => None,
+        };
+        
+        Ok(Some(WebhookSinkConfig {
+            url: url.clone(),
+            headers,
+            body_template,
+            max_retries: args.webhook_retries.max(1),
+        }))
+    }
+    
+    /// Build the Telegram sink configuration from the command line arguments
+    fn parse_telegram_config(args: &CliArgs) -> Result<Option<TelegramSinkConfig>> {
+        let Some(bot_token) = &args.telegram_bot_token else {
+            if !args.telegram_chats.is_empty() {
+                anyhow::bail!("--telegram-chat requires --telegram-bot-token");
+            }
+            return Ok(None);
+        };
+        
+        if args.telegram_chats.is_empty() {
+            anyhow::bail!("--telegram-bot-token requires at least one --telegram-chat");
+        }
+        
+        let chats = args.telegram_chats.iter()
+            .map(|chat| {
+                let (chat_id, exchanges) = match chat.split_once(':') {
+                    Some((chat_id, exchanges)) => (
+                        chat_id,
+                        exchanges.split(',')
+                            .map(|e| e.trim().to_string())
+                            .filter(|e| !e.is_empty())
+                            .collect(),
+                    ),
+                    None => (chat.as_str(), Vec::new()),
+                };
+                
+                if chat_id.trim().is_empty() {
+                    anyhow::bail!("Invalid Telegram chat '{}', expected chat_id[:exchange,exchange]", chat);
+                }
+                
+                Ok(TelegramChat {
+                    chat_id: chat_id.trim().to_string(),
+                    exchanges,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Some(TelegramSinkConfig {
+            bot_token: bot_token.clone(),
+            api_base_url: args.telegram_api_url.clone(),
+            chats,
+            max_retries: args.telegram_retries.max(1),
+        }))
+    }
+    
     /// Get the polling interval for a specific exchange
     pub fn get_polling_interval(&self, exchange_name: &str) -> u64 {
         self.exchange_intervals
@@ -85,6 +722,242 @@ impl Config {
     
     /// Check if an exchange should be monitored
     pub fn should_monitor_exchange(&self, exchange_name: &str) -> bool {
-        self.enabled_exchanges.is_empty() || self.enabled_exchanges.contains(&exchange_name.to_string())
+        self.enabled_exchanges.iter().any(|e| e.eq_ignore_ascii_case(exchange_name))
+    }
+    
+    /// Request settings for an exchange's monitor
+    pub fn monitor_options(&self, exchange_name: &str) -> MonitorOptions {
+        self.exchange_settings
+            .get(exchange_name)
+            .map(|settings| settings.options.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Read an environment variable, an empty value counts as unset
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// Read and parse an environment variable, failing on values that do not parse
+fn env_parse<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    env_var(name)
+        .map(|value| {
+            value.trim().parse::<T>()
+                .map_err(|e| anyhow::anyhow!("Invalid value '{}' for {}: {}", value, name, e))
+        })
+        .transpose()
+}
+
+/// Take the first polling interval that is set, in order of precedence, and check it.
+/// The candidates are the command line, an environment variable and the config file.
+fn resolve_interval(candidates: [(&str, Option<u64>); 3], warnings: &mut Vec<String>) -> Result<Option<u64>> {
+    if let [(_, None), (env_name, Some(_)), (file_source, Some(_))] = candidates {
+        warnings.push(shadowed(env_name, file_source));
+    }
+    
+    for (source, seconds) in candidates {
+        if let Some(seconds) = seconds {
+            if seconds == 0 {
+                anyhow::bail!("Invalid polling interval 0 in {}, expected at least 1 second", source);
+            }
+            return Ok(Some(seconds));
+        }
+    }
+    Ok(None)
+}
+
+/// Warning about an environment variable that overrides a setting of the config file
+fn shadowed(env_name: &str, file_source: &str) -> String {
+    format!("{} is set and overrides {}, changing it in the file has no effect", env_name, file_source)
+}
+
+/// Look up an exchange named in `source`, failing if it is not supported
+fn parse_exchange(name: &str, source: &str) -> Result<&'static str> {
+    find_exchange(name).with_context(|| {
+        let supported: Vec<&str> = SUPPORTED_EXCHANGES.iter().map(|(exchange, _)| *exchange).collect();
+        format!(
+            "Unknown exchange '{}' in {}, expected one of {}",
+            name.trim(), source, supported.join(", ")
+        )
+    })
+}
+
+/// Look up a list of exchanges, see `parse_exchange`
+pub(crate) fn parse_exchanges(names: &[String], source: &str) -> Result<Vec<String>> {
+    names.iter()
+        .map(|name| parse_exchange(name, source).map(str::to_string))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Environment variables are process-wide, tests reading them take turns
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    pub(super) fn lock_env() -> MutexGuard<'static, ()> {
+        ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Build a configuration from command line arguments and, if given, a config file
+    pub(super) fn config(file: Option<&str>, args: &[&str]) -> Result<Config> {
+        static FILES: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "eam-config-{}-{}.toml",
+            std::process::id(),
+            FILES.fetch_add(1, Ordering::SeqCst)
+        ));
+        let mut argv = vec!["exchange-announcement-monitoring".to_string()];
+        if let Some(contents) = file {
+            std::fs::write(&path, contents).unwrap();
+            argv.push("--config".to_string());
+            argv.push(path.display().to_string());
+        }
+        argv.extend(args.iter().map(|arg| arg.to_string()));
+
+        let config = CliArgs::try_parse_from(argv)
+            .map_err(anyhow::Error::from)
+            .and_then(Config::from_args);
+        let _ = std::fs::remove_file(&path);
+        config
+    }
+
+    fn error(file: &str, args: &[&str]) -> String {
+        format!("{:#}", config(Some(file), args).unwrap_err())
+    }
+
+    #[test]
+    fn command_line_wins_over_environment_over_file() {
+        let _env = lock_env();
+        let file = r#"
+            interval = 600
+            log_level = "warn"
+            notify_updates = "listings"
+
+            [exchanges.binance]
+            interval = 60
+        "#;
+
+        let from_file = config(Some(file), &[]).unwrap();
+        assert!(from_file.warnings.is_empty());
+        assert_eq!(from_file.default_polling_interval, 600);
+        assert_eq!(from_file.get_polling_interval("Binance"), 60);
+        assert_eq!(from_file.get_polling_interval("OKX"), 600);
+        assert_eq!(from_file.log_level, "warn");
+        assert_eq!(from_file.notify_updates, UpdateNotifications::Listings);
+
+        std::env::set_var("DEFAULT_POLLING_INTERVAL", "120");
+        std::env::set_var("BINANCE_POLLING_INTERVAL", "20");
+        std::env::set_var("LOG_LEVEL", "debug");
+        let from_env = config(Some(file), &[]);
+        let from_args = config(Some(file), &[
+            "--interval", "30",
+            "--exchange-intervals", "binance:10",
+            "--log-level", "error",
+            "--notify-updates", "all",
+        ]);
+        std::env::remove_var("DEFAULT_POLLING_INTERVAL");
+        std::env::remove_var("BINANCE_POLLING_INTERVAL");
+        std::env::remove_var("LOG_LEVEL");
+
+        let from_env = from_env.unwrap();
+        assert_eq!(from_env.default_polling_interval, 120);
+        assert_eq!(from_env.get_polling_interval("Binance"), 20);
+        assert_eq!(from_env.log_level, "debug");
+        assert_eq!(from_env.warnings, [
+            "LOG_LEVEL is set and overrides log_level in the config file, changing it in the file has no effect",
+            "DEFAULT_POLLING_INTERVAL is set and overrides interval in the config file, changing it in the file has no effect",
+            "BINANCE_POLLING_INTERVAL is set and overrides [exchanges.binance] interval, changing it in the file has no effect",
+        ]);
+
+        let from_args = from_args.unwrap();
+        assert_eq!(from_args.default_polling_interval, 30);
+        assert_eq!(from_args.get_polling_interval("Binance"), 10);
+        assert_eq!(from_args.get_polling_interval("OKX"), 30);
+        assert_eq!(from_args.log_level, "error");
+        assert_eq!(from_args.notify_updates, UpdateNotifications::All);
+        // The command line overrides the environment as well, on purpose
+        assert!(from_args.warnings.is_empty());
+    }
+
+    #[test]
+    fn exchange_sections_turn_exchanges_on_and_off() {
+        let _env = lock_env();
+        let file = r#"
+            enabled_exchanges = ["binance", "okx"]
+
+            [exchanges.okx]
+            enabled = false
+
+            [exchanges.kraken]
+            enabled = true
+        "#;
+
+        assert_eq!(config(Some(file), &[]).unwrap().enabled_exchanges, ["Binance", "Kraken"]);
+        assert_eq!(config(Some(file), &["--exchanges", "okx,bybit"]).unwrap().enabled_exchanges, ["OKX", "Bybit"]);
+        assert_eq!(config(None, &[]).unwrap().enabled_exchanges.len(), SUPPORTED_EXCHANGES.len());
+    }
+
+    #[test]
+    fn passes_section_settings_to_the_monitors() {
+        let _env = lock_env();
+        let config = config(Some(r#"
+            [exchanges."gate.io"]
+            language = "en"
+            page_size = 20
+            base_url = "http://127.0.0.1:8080"
+            sinks = ["slack"]
+        "#), &["--slack-webhook-url", "https://hooks.slack.com/services/T/B/X"]).unwrap();
+
+        let settings = &config.exchange_settings["Gate.io"];
+        assert_eq!(settings.options, MonitorOptions {
+            language: Some("en".to_string()),
+            page_size: Some(20),
+            catalog_ids: Vec::new(),
+            base_url: Some("http://127.0.0.1:8080".to_string()),
+        });
+        assert_eq!(settings.sinks, Some(vec!["slack".to_string()]));
+        assert_eq!(config.monitor_options("Binance"), MonitorOptions::default());
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let _env = lock_env();
+
+        assert!(error("intreval = 5", &[]).contains("unknown field `intreval`"));
+        assert!(error("interval = 0", &[]).contains("Invalid polling interval 0 in interval in the config file"));
+        assert!(error("[exchanges.foo]", &[]).contains("Unknown exchange 'foo' in the [exchanges] sections"));
+        assert!(error("[exchanges.binance]\n[exchanges.Binance]", &[]).contains("both configure Binance"));
+        assert!(error("[exchanges.okx]\npage_size = 10", &[]).contains("page_size is not supported by the OKX monitor"));
+        assert!(error("[exchanges.binance]\npage_size = 0", &[]).contains("page_size must be at least 1"));
+        assert!(error("[exchanges.bybit]\nsinks = ['email']", &[]).contains("unknown sink 'email'"));
+        assert!(error("[exchanges.bybit]\nsinks = ['slack']", &[]).contains("the slack sink is not configured"));
+        assert!(error("", &["--exchange-intervals", "binance=60"]).contains("expected exchange:seconds"));
+        assert!(error("", &["--telegram-chat=-100123"]).contains("requires --telegram-bot-token"));
+
+        for base_url in ["ftp://mirror.example.com", "https://mirror.example.com/?key=1", "mirror.example.com"] {
+            let file = format!("[exchanges.binance]\nbase_url = '{}'", base_url);
+            assert!(error(&file, &[]).contains("base_url must be an http or https URL"), "{}", base_url);
+        }
+    }
+
+    #[test]
+    fn parses_inclusive_date_ranges() {
+        let day = Utc.with_ymd_and_hms(2024, 6, 20, 0, 0, 0).unwrap();
+
+        assert_eq!(parse_since("2024-06-20").unwrap(), day);
+        assert_eq!(parse_until("2024-06-20").unwrap(), day + chrono::Duration::days(1));
+        assert_eq!(parse_until("2024-06-20T08:00:00+08:00").unwrap(), day);
+        assert!(parse_since("20/06/2024").is_err());
     }
 }
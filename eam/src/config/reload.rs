@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+
+use crate::config::Config;
+use crate::exchanges::SUPPORTED_EXCHANGES;
+
+/// Spawn a task that asks for a configuration reload whenever the config file changes
+/// or, on Unix, the process receives SIGHUP. Every request carries its reason.
+///
+/// Like the rules reloader, the file's modification time is polled instead of relying
+/// on filesystem notifications. Requests arriving while one is still queued are merged
+/// into it.
+pub fn spawn_reload_watcher(path: Option<PathBuf>, poll_interval: Duration) -> mpsc::Receiver<&'static str> {
+    let (sender, receiver) = mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let mut last_modified = match &path {
+            Some(path) => modified_time(path).await,
+            None => None,
+        };
+        let mut interval = tokio::time::interval(poll_interval);
+        let mut hangup = Hangup::new();
+
+        loop {
+            let reason = tokio::select! {
+                _ = interval.tick() => {
+                    let Some(path) = &path else { continue };
+                    let modified = modified_time(path).await;
+                    if modified.is_none() || modified == last_modified {
+                        continue;
+                    }
+                    last_modified = modified;
+                    "config file changed"
+                }
+                _ = hangup.recv() => "SIGHUP",
+            };
+
+            if let Err(mpsc::error::TrySendError::Closed(_)) = sender.try_send(reason) {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// SIGHUP listener, never fires where the signal does not exist or cannot be handled
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let signal = signal(SignalKind::hangup())
+                .map_err(|e| tracing::warn!(error = %e, "Failed to listen for SIGHUP, only config file changes trigger a reload"))
+                .ok();
+            Self { signal }
+        }
+
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            if signal.recv().await.is_some() {
+                return;
+            }
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+impl Config {
+    /// Describe every setting that differs in `new`, one line per setting, e.g.
+    /// "Binance interval: 300s -> 60s"
+    pub fn changes(&self, new: &Config) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        for &(exchange, _) in SUPPORTED_EXCHANGES {
+            let (old_enabled, new_enabled) = (self.should_monitor_exchange(exchange), new.should_monitor_exchange(exchange));
+            if old_enabled != new_enabled {
+                changes.push(format!("{}: {}", exchange, if new_enabled { "enabled" } else { "disabled" }));
+            }
+            // Settings of an exchange that is not monitored make no difference
+            if !new_enabled {
+                continue;
+            }
+
+            let name = |setting: &str| format!("{} {}", exchange, setting);
+            let old = self.exchange_settings.get(exchange).cloned().unwrap_or_default();
+            let updated = new.exchange_settings.get(exchange).cloned().unwrap_or_default();
+            push_change(
+                &mut changes,
+                name("interval"),
+                format!("{}s", self.get_polling_interval(exchange)),
+                format!("{}s", new.get_polling_interval(exchange)),
+            );
+            push_change(
+                &mut changes,
+                name("language"),
+                or_default(old.options.language),
+                or_default(updated.options.language),
+            );
+            push_change(
+                &mut changes,
+                name("page_size"),
+                or_default(old.options.page_size),
+                or_default(updated.options.page_size),
+            );
+            push_change(
+                &mut changes,
+                name("catalog_ids"),
+                list_or(&old.options.catalog_ids, "default"),
+                list_or(&updated.options.catalog_ids, "default"),
+            );
+            push_change(
+                &mut changes,
+                name("base_url"),
+                or_default(old.options.base_url),
+                or_default(updated.options.base_url),
+            );
+            push_change(
+                &mut changes,
+                name("proxy"),
+                format!("{:?}", old.proxy).to_lowercase(),
+                format!("{:?}", updated.proxy).to_lowercase(),
+            );
+            push_change(
+                &mut changes,
+                name("sinks"),
+                list_or(old.sinks.as_deref().unwrap_or_default(), "all"),
+                list_or(updated.sinks.as_deref().unwrap_or_default(), "all"),
+            );
+        }
+
+        push_change(
+            &mut changes,
+            "notify_updates".to_string(),
+            format!("{:?}", self.notify_updates).to_lowercase(),
+            format!("{:?}", new.notify_updates).to_lowercase(),
+        );
+        push_change(
+            &mut changes,
+            "rules".to_string(),
+            self.rules_path.as_ref().map_or("built-in".to_string(), |path| path.display().to_string()),
+            new.rules_path.as_ref().map_or("built-in".to_string(), |path| path.display().to_string()),
+        );
+        push_change(
+            &mut changes,
+            "log_level (applies after a restart)".to_string(),
+            self.log_level.clone(),
+            new.log_level.clone(),
+        );
+
+        changes
+    }
+}
+
+fn push_change(changes: &mut Vec<String>, setting: String, old: String, new: String) {
+    if old != new {
+        changes.push(format!("{}: {} -> {}", setting, old, new));
+    }
+}
+
+fn or_default(value: Option<impl ToString>) -> String {
+    value.map_or("default".to_string(), |value| value.to_string())
+}
+
+fn list_or(values: &[String], empty: &str) -> String {
+    if values.is_empty() {
+        empty.to_string()
+    } else {
+        values.join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::{config, lock_env};
+
+    #[test]
+    fn describes_every_changed_setting() {
+        let _env = lock_env();
+        let old = config(Some(r#"
+            [exchanges.binance]
+            interval = 60
+            page_size = 20
+        "#), &["--exchanges", "binance,okx"]).unwrap();
+        let new = config(Some(r#"
+            notify_updates = "listings"
+            rules = "rules.toml"
+
+            [exchanges.binance]
+            interval = 30
+            catalog_ids = ["48", "161"]
+            base_url = "http://127.0.0.1:8080"
+            proxy = "direct"
+
+            [exchanges.bybit]
+            language = "en-US"
+        "#), &["--exchanges", "binance,bybit", "--log-level", "debug"]).unwrap();
+
+        assert_eq!(new.changes(&new), Vec::<String>::new());
+        assert_eq!(old.changes(&new), [
+            "Binance interval: 60s -> 30s",
+            "Binance page_size: 20 -> default",
+            "Binance catalog_ids: default -> 48,161",
+            "Binance base_url: default -> http://127.0.0.1:8080",
+            "Binance proxy: auto -> direct",
+            "OKX: disabled",
+            "Bybit: enabled",
+            "Bybit language: default -> en-US",
+            "notify_updates: off -> listings",
+            "rules: built-in -> rules.toml",
+            "log_level (applies after a restart): info -> debug",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn asks_for_a_reload_when_the_file_changes() {
+        let dir = std::env::temp_dir().join(format!("eam-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "interval = 300").unwrap();
+
+        let mut reloads = spawn_reload_watcher(Some(path.clone()), Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(reloads.try_recv().is_err());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        let reason = tokio::time::timeout(Duration::from_secs(5), reloads.recv()).await.unwrap();
+        assert_eq!(reason, Some("config file changed"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,220 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+
+use crate::models::announcement::Announcement;
+use crate::models::update::AnnouncementUpdate;
+
+/// Something that happened in a monitor's run loop.
+///
+/// Announcements are shared behind an `Arc` since every subscriber receives its own
+/// copy of each event.
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    /// A monitor started polling
+    MonitorStarted {
+        exchange: String,
+        interval_seconds: u64,
+    },
+    /// A monitor's run loop exited, with the error that stopped it
+    MonitorStopped {
+        exchange: String,
+        error: Option<String>,
+    },
+    /// The supervisor restarts a crashed monitor after `delay`
+    MonitorRestarting {
+        exchange: String,
+        delay: Duration,
+    },
+    /// A monitor was disabled by a configuration reload and has stopped
+    MonitorRemoved {
+        exchange: String,
+    },
+    /// A configuration reload changed a running monitor's polling interval
+    IntervalChanged {
+        exchange: String,
+        interval_seconds: u64,
+    },
+    /// A poll succeeded. Sent after the poll's `NewAnnouncement` events, with every
+    /// announcement the exchange returned, new or not.
+    AnnouncementsFetched {
+        exchange: String,
+        announcements: Arc<[Announcement]>,
+        /// Number of announcements that were not seen before
+        new_announcements: usize,
+        /// The poll only seeded the seen store, see `MonitorContext::baseline_first_poll`
+        baseline: bool,
+        duration: Duration,
+    },
+    /// An announcement that was not reported before, with `detected_at` set
+    NewAnnouncement(Arc<Announcement>),
+    /// An archived announcement whose title or content changed since it was archived
+    AnnouncementUpdated(Arc<AnnouncementUpdate>),
+    /// A poll failed
+    FetchFailed {
+        exchange: String,
+        /// The error with its causes, as formatted by `{:#}`
+        error: String,
+        /// See `metrics::error_class`
+        class: &'static str,
+        duration: Duration,
+    },
+}
+
+impl MonitorEvent {
+    /// Exchange the event is about
+    pub fn exchange(&self) -> &str {
+        match self {
+            MonitorEvent::MonitorStarted { exchange, .. }
+            | MonitorEvent::MonitorStopped { exchange, .. }
+            | MonitorEvent::MonitorRestarting { exchange, .. }
+            | MonitorEvent::MonitorRemoved { exchange }
+            | MonitorEvent::IntervalChanged { exchange, .. }
+            | MonitorEvent::AnnouncementsFetched { exchange, .. }
+            | MonitorEvent::FetchFailed { exchange, .. } => exchange,
+            MonitorEvent::NewAnnouncement(announcement) => &announcement.exchange,
+            MonitorEvent::AnnouncementUpdated(update) => &update.announcement.exchange,
+        }
+    }
+}
+
+/// Broadcast channel between the monitors and everything that consumes their results.
+///
+/// The run loops only fetch and detect new announcements; notification sinks, the
+/// archive, metrics, the status registry and the HTTP streams each subscribe on their
+/// own, so a slow consumer never delays a poll or another consumer.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<MonitorEvent>,
+}
+
+impl EventBus {
+    /// Create a bus buffering up to `capacity` events for each subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Send an event to every current subscriber
+    pub fn publish(&self, event: MonitorEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(event);
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<MonitorEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Handle for consumers that publish events themselves.
+    ///
+    /// Consumers exit once every `EventBus` is dropped and they have handled the events
+    /// still queued, which is how shutdown drains them. A consumer holding an `EventBus`
+    /// would keep the bus open, so it publishes through a weak handle instead.
+    pub fn downgrade(&self) -> WeakEventBus {
+        WeakEventBus {
+            sender: self.sender.downgrade(),
+        }
+    }
+}
+
+/// Publishing handle that does not keep the bus open, see `EventBus::downgrade`
+#[derive(Clone)]
+pub struct WeakEventBus {
+    sender: broadcast::WeakSender<MonitorEvent>,
+}
+
+impl WeakEventBus {
+    /// Send an event to every current subscriber, dropped if the bus is closed
+    pub fn publish(&self, event: MonitorEvent) {
+        if let Some(sender) = self.sender.upgrade() {
+            let _ = sender.send(event);
+        }
+    }
+}
+
+/// Spawn a task that passes every event published on the bus to `handler`, one at a time.
+///
+/// The subscription is made before this returns, so no event published afterwards is
+/// missed. A consumer that falls more than the bus capacity behind skips the oldest
+/// events and logs how many it lost. The task ends once the bus is closed and every
+/// queued event has been handled.
+pub fn spawn_consumer<F, Fut>(bus: &EventBus, name: &'static str, mut handler: F) -> JoinHandle<()>
+where
+    F: FnMut(MonitorEvent) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let mut receiver = bus.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => handler(event).await,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        consumer = name,
+                        skipped = skipped,
+                        "Event consumer {} fell behind and skipped {} events",
+                        name, skipped
+                    );
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn stopped(exchange: &str) -> MonitorEvent {
+        MonitorEvent::MonitorStopped { exchange: exchange.to_string(), error: None }
+    }
+
+    /// Spawn a consumer that records the exchange of every event it handles
+    fn recorder(bus: &EventBus) -> (JoinHandle<()>, Arc<Mutex<Vec<String>>>) {
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let consumer = spawn_consumer(bus, "test", {
+            let handled = handled.clone();
+            move |event| {
+                handled.lock().unwrap().push(event.exchange().to_string());
+                async {}
+            }
+        });
+        (consumer, handled)
+    }
+
+    #[tokio::test]
+    async fn consumers_drain_queued_events_once_the_bus_closes() {
+        let bus = EventBus::new(16);
+        let (first, first_handled) = recorder(&bus);
+        let (second, second_handled) = recorder(&bus);
+
+        bus.publish(stopped("Binance"));
+        bus.publish(stopped("OKX"));
+        drop(bus);
+
+        first.await.unwrap();
+        second.await.unwrap();
+        assert_eq!(*first_handled.lock().unwrap(), ["Binance", "OKX"]);
+        assert_eq!(*second_handled.lock().unwrap(), ["Binance", "OKX"]);
+    }
+
+    #[tokio::test]
+    async fn weak_handles_do_not_keep_the_bus_open() {
+        let bus = EventBus::new(16);
+        let weak = bus.downgrade();
+        let (consumer, handled) = recorder(&bus);
+
+        weak.publish(stopped("Bybit"));
+        drop(bus);
+        consumer.await.unwrap();
+        // Dropped silently, nobody is left to receive it
+        weak.publish(stopped("Kraken"));
+
+        assert_eq!(*handled.lock().unwrap(), ["Bybit"]);
+    }
+}
@@ -1,67 +1,90 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{dedup_announcements, ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
-use crate::utils::{create_browser_headers, create_browser_client, retry_request, create_new_proxy_client};
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::MonitorHttp;
+use crate::utils::{create_browser_headers, retry_request, create_new_proxy_client};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use reqwest::Client;
+use serde::Deserialize;
+use url::Url;
 
 /// Binance announcement monitor
 pub struct BinanceMonitor {
-    client: Client,
+    http: MonitorHttp,
     base_url: String,
+    endpoint: String,
+    /// Host header of the requests, the host of the base URL
+    host: String,
+    page_size: u32,
+    catalog_ids: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct BinanceAnnouncementResponse {
-    code: String,
     message: Option<String>,
     data: Vec<BinanceAnnouncement>,
-    total: usize,
     success: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct BinanceAnnouncement {
     id: String,
-    code: String,
     title: String,
-    #[serde(rename = "type")]
-    announcement_type: usize,
     #[serde(rename = "releaseDate")]
     release_date: i64,
     url: Option<String>,
 }
 
 impl BinanceMonitor {
-    /// Create a new Binance monitor
-    pub fn new() -> Self {
+    /// Create a new Binance monitor with the request settings from the config file.
+    /// Unlike the other monitors it only uses a shared client when one is injected,
+    /// which `exchanges::monitor_http` does for mirrors. Otherwise each request goes
+    /// through a new proxy of the pool.
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
+        let base_url = options.base_url_or("https://www.binance.com");
+        let host = Url::parse(&base_url)
+            .ok()
+            .and_then(|url| {
+                let host = url.host_str()?;
+                Some(match url.port() {
+                    Some(port) => format!("{}:{}", host, port),
+                    None => host.to_string(),
+                })
+            })
+            .unwrap_or_else(|| "www.binance.com".to_string());
         Self {
-            client: create_browser_client(),
-            base_url: "https://www.binance.com/bapi/composite/v1/public/cms/article/catalog/list/query".to_string(),
+            http,
+            endpoint: format!("{}/bapi/composite/v1/public/cms/article/catalog/list/query", base_url),
+            base_url,
+            host,
+            page_size: options.page_size_or(20),
+            catalog_ids: options.catalog_ids_or(&["48"]), // 48 is "New Crypto Listings"
         }
     }
 
     /// Fetch announcement content for a specific announcement ID
     async fn fetch_announcement_content(&self, url: &str) -> Result<String> {
-        if let Some(url) = url.strip_prefix("https://www.binance.com") {
-            let full_url = format!("https://www.binance.com{}", url);
+        if let Some(path) = url.strip_prefix("https://www.binance.com") {
+            // The page is fetched from the base URL, the announcement keeps its public link
+            let full_url = format!("{}{}", self.base_url, path);
             
             // Use retry mechanism for fetching content with proxy rotation
-            let headers = create_browser_headers(None, Some("www.binance.com"));
+            let headers = create_browser_headers(None, Some(&self.host));
             let full_url_clone = full_url.clone();
+            let http = self.http.clone();
             
             let response = retry_request(
                 move || {
                     // Create a new client with different proxy for each retry attempt
-                    let client = create_new_proxy_client();
+                    let client = http.client_or(create_new_proxy_client);
                     let url = full_url_clone.clone();
                     let headers = headers.clone();
+                    let transport = http.transport.clone();
                     async move {
-                        client.get(&url)
-                            .headers(headers)
-                            .send()
+                        let request = client.get(&url)
+                            .headers(headers);
+                        transport.send(request)
                             .await
                             .context("Failed to request Binance announcement content")
                     }
@@ -92,43 +115,39 @@ impl BinanceMonitor {
             Ok(String::new())
         }
     }
-}
 
-#[async_trait]
-impl ExchangeMonitor for BinanceMonitor {
-    fn exchange_name(&self) -> &str {
-        "Binance"
-    }
-    
-    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+    /// Fetch the announcements of one catalog
+    async fn fetch_catalog(&self, catalog_id: &str) -> Result<Vec<Announcement>> {
         // First, check if the site is accessible
-        tracing::info!("Attempting to fetch Binance announcements");
+        tracing::info!(catalog_id = catalog_id, "Attempting to fetch Binance announcements");
         
         // Request parameters for the Binance announcement API
         let params = serde_json::json!({
-            "catalogId": "48",  // 48 is "New Crypto Listings"
+            "catalogId": catalog_id,
             "pageNo": 1,
-            "pageSize": 20,
+            "pageSize": self.page_size,
         });
         
         // Prepare for retry logic with proxy rotation
-        let headers = create_browser_headers(Some("application/json"), Some("www.binance.com"));
-        let base_url_clone = self.base_url.clone();
+        let headers = create_browser_headers(Some("application/json"), Some(&self.host));
+        let endpoint_clone = self.endpoint.clone();
         let params_clone = params.clone();
+        let http = self.http.clone();
         
         // Use retry mechanism for the main request with proxy rotation
         match retry_request(
             move || {
                 // Create a new client with different proxy for each retry attempt
-                let client = create_new_proxy_client();
-                let url = base_url_clone.clone();
+                let client = http.client_or(create_new_proxy_client);
+                let url = endpoint_clone.clone();
                 let headers = headers.clone();
                 let params = params_clone.clone();
+                let transport = http.transport.clone();
                 async move {
-                    client.post(&url)
+                    let request = client.post(&url)
                         .headers(headers)
-                        .json(&params)
-                        .send()
+                        .json(&params);
+                    transport.send(request)
                         .await
                         .context("Failed to request Binance announcements")
                 }
@@ -163,6 +182,7 @@ impl ExchangeMonitor for BinanceMonitor {
                         return Err(anyhow::anyhow!("Failed to parse Binance announcement response: {}", e));
                     }
                 };
+                let listed_at = Utc::now();
                 
                 // Check if the request was successful
                 if !binance_response.success {
@@ -181,7 +201,7 @@ impl ExchangeMonitor for BinanceMonitor {
                         let published_at = DateTime::<Utc>::from_timestamp(
                             binance_announcement.release_date / 1000, // Convert milliseconds to seconds
                             0,
-                        ).unwrap_or_else(|| Utc::now());
+                        ).unwrap_or_else(|| unparsed_date(binance_announcement.release_date));
                         
                         // Clone the ID for use in error logging
                         let announcement_id = binance_announcement.id.clone();
@@ -195,11 +215,13 @@ impl ExchangeMonitor for BinanceMonitor {
                             self.exchange_name().to_string(),
                             published_at,
                         );
+                        announcement.listed_at = Some(listed_at);
                         
                         // Fetch the full content
                         match self.fetch_announcement_content(url).await {
                             Ok(content) => {
                                 announcement.content = content;
+                                announcement.content_fetched_at = Some(Utc::now());
                                 // Analyze if this is a new listing
                                 announcement.analyze_for_new_listing();
                                 announcements.push(announcement);
@@ -247,3 +269,53 @@ impl ExchangeMonitor for BinanceMonitor {
         }
     }
 }
+
+#[async_trait]
+impl ExchangeMonitor for BinanceMonitor {
+    fn exchange_name(&self) -> &str {
+        "Binance"
+    }
+    
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
+    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+        let mut announcements = Vec::new();
+        for catalog_id in &self.catalog_ids {
+            announcements.extend(self.fetch_catalog(catalog_id).await?);
+        }
+        Ok(dedup_announcements(announcements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_catalog_and_content() {
+        let monitor = BinanceMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("binance")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        // The announcement without a URL is skipped
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.statuses, [200, 200, 200]);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "195342");
+        assert_eq!(listing.title, "Binance Will List Notcoin (NOT) with Seed Tag Applied");
+        assert_eq!(listing.published_at, DateTime::parse_from_rfc3339("2024-05-16T06:00:00Z").unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["NOT"]);
+        assert!(listing.content.starts_with("<p>Binance will list Notcoin (NOT)"));
+        assert!(listing.content_fetched_at.is_some());
+
+        assert_eq!(announcements[1].kind, AnnouncementKind::FuturesListing);
+    }
+}
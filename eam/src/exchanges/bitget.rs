@@ -1,15 +1,23 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{dedup_announcements, ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc, TimeZone};
-use serde::{Deserialize, Serialize};
+use chrono::{Utc, TimeZone};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// Bitget announcement monitor
 pub struct BitgetMonitor {
     client: Client,
+    transport: Arc<dyn HttpTransport>,
     base_url: String,
+    endpoint: String,
+    language: String,
+    page_size: u32,
+    catalog_ids: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,7 +30,6 @@ struct BitgetAnnouncementResponse {
 #[derive(Debug, Deserialize)]
 struct BitgetAnnouncementData {
     list: Vec<BitgetAnnouncement>,
-    total: usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,20 +43,26 @@ struct BitgetAnnouncement {
 }
 
 impl BitgetMonitor {
-    /// Create a new Bitget monitor
-    pub fn new() -> Self {
+    /// Create a new Bitget monitor with the request settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
+        let base_url = options.base_url_or("https://api.bitget.com");
         Self {
-            client: Client::new(),
-            base_url: "https://api.bitget.com/api/v2/spot/public/support/notice/list".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/api/v2/spot/public/support/notice/list", base_url),
+            base_url,
+            language: options.language_or("en"),
+            page_size: options.page_size_or(20),
+            catalog_ids: options.catalog_ids_or(&["6"]), // 6 is the listings category
         }
     }
 
     /// Fetch full content for an announcement
     async fn fetch_announcement_content(&self, id: &str) -> Result<String> {
-        let url = format!("https://api.bitget.com/api/v2/spot/public/support/notice/detail?id={}", id);
+        let url = format!("{}/api/v2/spot/public/support/notice/detail?id={}", self.base_url, id);
         
-        let response = self.client.get(&url)
-            .send()
+        let request = self.client.get(&url);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Bitget announcement detail")?;
         
@@ -62,8 +75,6 @@ impl BitgetMonitor {
         
         #[derive(Debug, Deserialize)]
         struct BitgetDetail {
-            id: String,
-            title: String,
             content: String,
         }
         
@@ -79,27 +90,22 @@ impl BitgetMonitor {
         
         Ok(detail_response.data.content)
     }
-}
 
-#[async_trait]
-impl ExchangeMonitor for BitgetMonitor {
-    fn exchange_name(&self) -> &str {
-        "Bitget"
-    }
-    
-    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+    /// Fetch the announcements of one catalog
+    async fn fetch_catalog(&self, catalog_id: &str) -> Result<Vec<Announcement>> {
         // Request parameters for the Bitget API
+        let page_size = self.page_size.to_string();
         let params = [
-            ("language", "en"),
-            ("catalogId", "6"), // Listings category
+            ("language", self.language.as_str()),
+            ("catalogId", catalog_id),
             ("page", "1"),
-            ("pageSize", "20"),
+            ("pageSize", page_size.as_str()),
         ];
         
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .query(&params)
-            .send()
+        let request = self.client.get(&self.endpoint)
+            .query(&params);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Bitget announcements")?;
         
@@ -107,6 +113,7 @@ impl ExchangeMonitor for BitgetMonitor {
         let bitget_response: BitgetAnnouncementResponse = response.json()
             .await
             .context("Failed to parse Bitget announcement response")?;
+        let listed_at = Utc::now();
         
         // Check if the request was successful
         if bitget_response.code != "00000" {
@@ -121,13 +128,17 @@ impl ExchangeMonitor for BitgetMonitor {
             // Convert timestamp to DateTime<Utc>
             let published_at = Utc.timestamp_opt(bitget_announcement.release_time / 1000, 0)
                 .single()
-                .unwrap_or_else(|| Utc::now());
+                .unwrap_or_else(|| unparsed_date(bitget_announcement.release_time));
             
             // Get content from the announcement or fetch it if not available
+            let mut content_fetched_at = None;
             let content = match bitget_announcement.content {
                 Some(content) if !content.is_empty() => content,
                 _ => match self.fetch_announcement_content(&bitget_announcement.id).await {
-                    Ok(content) => content,
+                    Ok(content) => {
+                        content_fetched_at = Some(Utc::now());
+                        content
+                    }
                     Err(e) => {
                         tracing::warn!(
                             exchange = self.exchange_name(),
@@ -149,6 +160,8 @@ impl ExchangeMonitor for BitgetMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
+            announcement.content_fetched_at = content_fetched_at;
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -158,3 +171,86 @@ impl ExchangeMonitor for BitgetMonitor {
         Ok(announcements)
     }
 }
+
+#[async_trait]
+impl ExchangeMonitor for BitgetMonitor {
+    fn exchange_name(&self) -> &str {
+        "Bitget"
+    }
+    
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
+    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+        let mut announcements = Vec::new();
+        for catalog_id in &self.catalog_ids {
+            announcements.extend(self.fetch_catalog(catalog_id).await?);
+        }
+        Ok(dedup_announcements(announcements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::{self, replay::fixtures};
+    use axum::routing::get;
+    use axum::Router;
+
+    #[tokio::test]
+    async fn parses_recorded_notices_and_details() {
+        let monitor = BitgetMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("bitget")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "12560604121011");
+        assert_eq!(listing.url, "https://www.bitget.com/support/articles/12560604121011");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 5, 0, 0).unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert!(listing.content_fetched_at.is_none());
+
+        // Notices without content get it from the detail endpoint
+        let futures = &announcements[1];
+        assert_eq!(futures.content, "Bitget futures launched the ZROUSDT perpetual contract with up to 50x leverage.");
+        assert!(futures.content_fetched_at.is_some());
+        // Listed before the detail request
+        assert!(futures.listed_at <= futures.content_fetched_at);
+        assert_eq!(futures.kind, AnnouncementKind::FuturesListing);
+    }
+
+    #[tokio::test]
+    async fn base_url_points_requests_at_a_mirror() {
+        let router = Router::new()
+            .route("/api/v2/spot/public/support/notice/list", get(|| async {
+                include_str!("../../tests/fixtures/bitget/01-list.json")
+            }))
+            .route("/api/v2/spot/public/support/notice/detail", get(|| async {
+                include_str!("../../tests/fixtures/bitget/02-detail.json")
+            }));
+        let base_url = crate::utils::serve(router).await;
+
+        let options = MonitorOptions {
+            base_url: Some(format!("{}/", base_url)),
+            ..MonitorOptions::default()
+        };
+        let http = MonitorHttp {
+            client: Some(Client::new()),
+            transport: transport::live(),
+        };
+        let monitor = BitgetMonitor::with_options(&options, http);
+        assert_eq!(monitor.endpoint(), format!("{}/api/v2/spot/public/support/notice/list", base_url));
+
+        let announcements = monitor.fetch_announcements().await.unwrap();
+        assert_eq!(announcements.len(), 2);
+        // Links still lead to the exchange
+        assert_eq!(announcements[0].url, "https://www.bitget.com/support/articles/12560604121011");
+        assert!(announcements[1].content.starts_with("Bitget futures launched the ZROUSDT"));
+    }
+}
@@ -1,21 +1,19 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// BitMEX announcement monitor
 pub struct BitmexMonitor {
     client: Client,
-    base_url: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct BitmexAnnouncementResponse {
-    data: Vec<BitmexAnnouncement>,
-    success: bool,
+    transport: Arc<dyn HttpTransport>,
+    endpoint: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,11 +26,12 @@ struct BitmexAnnouncement {
 }
 
 impl BitmexMonitor {
-    /// Create a new BitMEX monitor
-    pub fn new() -> Self {
+    /// Create a new BitMEX monitor with the settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: Client::new(),
-            base_url: "https://www.bitmex.com/api/v1/announcement".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/api/v1/announcement", options.base_url_or("https://www.bitmex.com")),
         }
     }
 }
@@ -43,10 +42,14 @@ impl ExchangeMonitor for BitmexMonitor {
         "BitMEX"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .send()
+        let request = self.client.get(&self.endpoint);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request BitMEX announcements")?;
         
@@ -54,13 +57,14 @@ impl ExchangeMonitor for BitmexMonitor {
         let bitmex_response: Vec<BitmexAnnouncement> = response.json()
             .await
             .context("Failed to parse BitMEX announcement response")?;
+        let listed_at = Utc::now();
         
         // Convert BitMEX announcements to our standard format
         let mut announcements = Vec::new();
         for bitmex_announcement in bitmex_response {
             // Parse publish time - BitMEX uses ISO 8601 format
             let published_at = DateTime::parse_from_rfc3339(&bitmex_announcement.date)
-                .unwrap_or_else(|_| Utc::now().into())
+                .unwrap_or_else(|_| unparsed_date(&bitmex_announcement.date).into())
                 .with_timezone(&Utc);
             
             // Create the announcement
@@ -78,6 +82,7 @@ impl ExchangeMonitor for BitmexMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -87,3 +92,32 @@ impl ExchangeMonitor for BitmexMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_announcements() {
+        let monitor = BitmexMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("bitmex")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "1542");
+        // Relative links are made absolute, absolute ones are kept
+        assert_eq!(listing.url, "https://www.bitmex.com/blog/zrousdt-perpetual-swap-listing");
+        assert_eq!(announcements[1].url, "https://www.bitmex.com/blog/maintenance-2024-06-19");
+        assert_eq!(listing.published_at, DateTime::parse_from_rfc3339("2024-06-20T08:00:00Z").unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::FuturesListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+
+        assert_eq!(announcements[1].kind, AnnouncementKind::Maintenance);
+    }
+}
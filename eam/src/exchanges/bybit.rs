@@ -1,15 +1,21 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc, TimeZone};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// Bybit announcement monitor
 pub struct BybitMonitor {
     client: Client,
-    base_url: String,
+    transport: Arc<dyn HttpTransport>,
+    endpoint: String,
+    language: String,
+    page_size: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,15 +28,12 @@ struct BybitAnnouncementResponse {
 #[derive(Debug, Deserialize)]
 struct BybitAnnouncementResult {
     list: Vec<BybitAnnouncement>,
-    total: usize,
 }
 
 #[derive(Debug, Deserialize)]
 struct BybitAnnouncement {
     id: usize,
     title: String,
-    #[serde(rename = "type")]
-    announcement_type: String,
     #[serde(rename = "releaseDate")]
     release_date: String,
     description: String,
@@ -38,11 +41,14 @@ struct BybitAnnouncement {
 }
 
 impl BybitMonitor {
-    /// Create a new Bybit monitor
-    pub fn new() -> Self {
+    /// Create a new Bybit monitor with the request settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: Client::new(),
-            base_url: "https://api2.bybit.com/announcement/api/v1/announcement/list".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/announcement/api/v1/announcement/list", options.base_url_or("https://api2.bybit.com")),
+            language: options.language_or("en-US"),
+            page_size: options.page_size_or(20),
         }
     }
     
@@ -57,7 +63,7 @@ impl BybitMonitor {
                 timestamp = timestamp,
                 "Failed to parse Bybit timestamp"
             );
-            Utc::now()
+            unparsed_date(timestamp)
         }
     }
 }
@@ -68,19 +74,23 @@ impl ExchangeMonitor for BybitMonitor {
         "Bybit"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Request parameters for the Bybit API
         let params = [
-            ("locale", "en-US".to_string()),
+            ("locale", self.language.clone()),
             ("page", "1".to_string()),
-            ("limit", "20".to_string()),
+            ("limit", self.page_size.to_string()),
             ("type", "new_crypto".to_string()), // Filter for new crypto listings
         ];
         
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .query(&params)
-            .send()
+        let request = self.client.get(&self.endpoint)
+            .query(&params);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Bybit announcements")?;
         
@@ -88,6 +98,7 @@ impl ExchangeMonitor for BybitMonitor {
         let bybit_response: BybitAnnouncementResponse = response.json()
             .await
             .context("Failed to parse Bybit announcement response")?;
+        let listed_at = Utc::now();
         
         // Check if the request was successful
         if !bybit_response.success {
@@ -111,6 +122,7 @@ impl ExchangeMonitor for BybitMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -120,3 +132,31 @@ impl ExchangeMonitor for BybitMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_announcements() {
+        let monitor = BybitMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("bybit")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "31205");
+        assert_eq!(listing.published_at, DateTime::parse_from_rfc3339("2024-06-20T06:00:00Z").unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+        assert!(listing.url.starts_with("https://announcements.bybit.com/en-US/article/new-listing-zro-usdt"));
+
+        assert_eq!(announcements[1].kind, AnnouncementKind::Delisting);
+        assert_eq!(announcements[1].token_symbols, ["HNT"]);
+    }
+}
@@ -1,15 +1,19 @@
-use crate::exchanges::monitor::ExchangeMonitor;
-use crate::models::announcement::Announcement;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
+use crate::models::announcement::{Announcement, AnnouncementKind};
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// Coinbase announcement monitor
 pub struct CoinbaseMonitor {
     client: Client,
-    base_url: String,
+    transport: Arc<dyn HttpTransport>,
+    endpoint: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -31,12 +35,13 @@ struct CoinbaseBlogPost {
 }
 
 impl CoinbaseMonitor {
-    /// Create a new Coinbase monitor
-    pub fn new() -> Self {
+    /// Create a new Coinbase monitor with the settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: Client::new(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
             // Using a RSS to JSON converter service for Coinbase blog
-            base_url: "https://api.rss2json.com/v1/api.json?rss_url=https://blog.coinbase.com/feed".to_string(),
+            endpoint: format!("{}/v1/api.json?rss_url=https://blog.coinbase.com/feed", options.base_url_or("https://api.rss2json.com")),
         }
     }
 }
@@ -47,10 +52,14 @@ impl ExchangeMonitor for CoinbaseMonitor {
         "Coinbase"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .send()
+        let request = self.client.get(&self.endpoint);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Coinbase blog RSS")?;
         
@@ -58,13 +67,14 @@ impl ExchangeMonitor for CoinbaseMonitor {
         let blog_response: CoinbaseBlogResponse = response.json()
             .await
             .context("Failed to parse Coinbase blog response")?;
+        let listed_at = Utc::now();
         
         // Convert blog posts to our standard format
         let mut announcements = Vec::new();
         for blog_post in blog_response.items {
             // Parse publish time
             let published_at = DateTime::parse_from_rfc3339(&blog_post.pub_date)
-                .unwrap_or_else(|_| Utc::now().into())
+                .unwrap_or_else(|_| unparsed_date(&blog_post.pub_date).into())
                 .with_timezone(&Utc);
             
             // Get content from either full content or snippet
@@ -83,12 +93,13 @@ impl ExchangeMonitor for CoinbaseMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
             
             // If we have categories and they contain "listings" or similar keywords,
-            // explicitly mark this as a new listing
+            // explicitly mark this as a spot listing unless the text says otherwise
             if let Some(categories) = blog_post.categories {
                 let has_listing_category = categories.iter().any(|cat| {
                     let cat_lower = cat.to_lowercase();
@@ -97,10 +108,8 @@ impl ExchangeMonitor for CoinbaseMonitor {
                     cat_lower.contains("new crypto")
                 });
                 
-                if has_listing_category && !announcement.is_new_listing {
-                    announcement.is_new_listing = true;
-                    // Re-analyze for token symbols
-                    announcement.analyze_for_new_listing();
+                if has_listing_category && announcement.kind == AnnouncementKind::Other {
+                    announcement.set_kind(AnnouncementKind::SpotListing, 0.8);
                 }
             }
             
@@ -110,3 +119,31 @@ impl ExchangeMonitor for CoinbaseMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_feed() {
+        let monitor = CoinbaseMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("coinbase")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        // Posts in a listing category are spot listings even without listing words
+        let listing = &announcements[0];
+        assert_eq!(listing.title, "ZRO is launching on Coinbase");
+        assert_eq!(listing.url, "https://blog.coinbase.com/zro-is-launching-on-coinbase-7b1c2d3e4f50");
+        assert_eq!(listing.published_at, DateTime::parse_from_rfc3339("2024-06-20T16:00:00Z").unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+
+        // The snippet stands in for an empty content
+        assert_eq!(announcements[1].content, "Highlights from our annual summit in New York.");
+        assert_eq!(announcements[1].kind, AnnouncementKind::Other);
+    }
+}
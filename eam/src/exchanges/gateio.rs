@@ -1,15 +1,21 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc, TimeZone};
-use serde::{Deserialize, Serialize};
+use chrono::{Utc, TimeZone};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// Gate.io announcement monitor
 pub struct GateioMonitor {
     client: Client,
-    base_url: String,
+    transport: Arc<dyn HttpTransport>,
+    endpoint: String,
+    language: String,
+    page_size: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,7 +28,6 @@ struct GateioAnnouncementResponse {
 #[derive(Debug, Deserialize)]
 struct GateioData {
     list: Vec<GateioAnnouncement>,
-    total: usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,11 +41,14 @@ struct GateioAnnouncement {
 }
 
 impl GateioMonitor {
-    /// Create a new Gate.io monitor
-    pub fn new() -> Self {
+    /// Create a new Gate.io monitor with the request settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: Client::new(),
-            base_url: "https://www.gate.io/api/v1/announcement/list".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/api/v1/announcement/list", options.base_url_or("https://www.gate.io")),
+            language: options.language_or("en"),
+            page_size: options.page_size_or(20),
         }
     }
 }
@@ -51,19 +59,24 @@ impl ExchangeMonitor for GateioMonitor {
         "Gate.io"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Request parameters for the Gate.io API
+        let page_size = self.page_size.to_string();
         let params = [
             ("page", "1"),
-            ("limit", "20"),
-            ("lang", "en"),
+            ("limit", page_size.as_str()),
+            ("lang", self.language.as_str()),
             ("category", "listing"), // Focus on listing announcements
         ];
         
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .query(&params)
-            .send()
+        let request = self.client.get(&self.endpoint)
+            .query(&params);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Gate.io announcements")?;
         
@@ -71,6 +84,7 @@ impl ExchangeMonitor for GateioMonitor {
         let gateio_response: GateioAnnouncementResponse = response.json()
             .await
             .context("Failed to parse Gate.io announcement response")?;
+        let listed_at = Utc::now();
         
         // Check if the request was successful
         if gateio_response.code != 0 {
@@ -85,7 +99,7 @@ impl ExchangeMonitor for GateioMonitor {
             // Convert timestamp to DateTime<Utc>
             let published_at = Utc.timestamp_opt(gateio_announcement.publish_time as i64, 0)
                 .single()
-                .unwrap_or_else(|| Utc::now());
+                .unwrap_or_else(|| unparsed_date(gateio_announcement.publish_time));
             
             // Create the announcement
             let mut announcement = Announcement::new(
@@ -96,6 +110,7 @@ impl ExchangeMonitor for GateioMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -105,3 +120,30 @@ impl ExchangeMonitor for GateioMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_announcements() {
+        let monitor = GateioMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("gateio")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "38012");
+        assert_eq!(listing.url, "https://www.gate.io/article/38012");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 3, 0, 0).unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+
+        assert_eq!(announcements[1].kind, AnnouncementKind::Launchpad);
+    }
+}
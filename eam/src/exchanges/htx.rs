@@ -1,30 +1,31 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::MonitorHttp;
 use crate::utils::{create_browser_client, retry_request, extract_response_data};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc, TimeZone, NaiveDateTime};
+use chrono::{Utc, TimeZone};
 use serde::Deserialize;
 use reqwest::header;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 
 /// HTX announcement monitor (formerly Huobi)
 pub struct HtxMonitor {
-    client: reqwest::Client,
+    http: MonitorHttp,
     api_url: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct HtxResponse {
     success: bool,
-    code: i32,
     message: Option<String>,
     data: HtxData,
 }
 
 #[derive(Debug, Deserialize)]
 struct HtxData {
-    total: i32,
     list: Vec<HtxItem>,
 }
 
@@ -34,91 +35,34 @@ struct HtxItem {
     title: String,
     content: String,
     created_at: i64,
-    lang: String,
+    /// Link of the notice, only known when it was scraped from the HTML page
+    #[serde(skip)]
+    link: Option<String>,
 }
 
 impl HtxMonitor {
-    /// Create a new HTX monitor
-    pub fn new() -> Self {
+    /// Create a new HTX monitor with the settings from the config file. Unless a
+    /// shared client is injected, which `exchanges::monitor_http` does for mirrors,
+    /// each poll builds its own client with the next proxy of the pool.
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: match create_browser_client() {
-                Ok(client) => client,
-                Err(_) => reqwest::Client::new(),
-            },
-            api_url: "https://www.htx.com/api/v1/notice/get_notice_list".to_string(),
-        }
-    }
-    
-    /// Fetch the content for a specific announcement
-    async fn fetch_announcement_content(&self, id: i64) -> Result<String> {
-        let url = format!("https://www.htx.com/api/v1/notice/get_notice_by_id?id={}", id);
-        let url_clone = url.clone();
-        
-        let headers = header::HeaderMap::new();
-        headers.insert(header::USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36".parse().unwrap());
-        
-        // Use retry mechanism with proxy rotation
-        let response = retry_request(
-            move || {
-                let client = create_browser_client().unwrap();
-                let url = url_clone.clone();
-                let headers = headers.clone();
-                
-                async move {
-                    client.get(&url)
-                        .headers(headers)
-                        .send()
-                        .await
-                        .context("Failed to request HTX announcement content")
-                }
-            },
-            3, // max retries
-            500, // initial delay in ms
-        ).await.context("Failed to fetch HTX announcement content after retries")?;
-        
-        // Use our new extract_response_data function with HTML fallback
-        let content_response = extract_response_data::<HtxContentResponse>(
-            response, 
-            Some(|html| extract_htx_html_content(html))
-        )
-        .await
-        .context("Failed to parse HTX announcement content")?;
-        
-        if !content_response.success {
-            return Err(anyhow::anyhow!("HTX API returned error: {:?}", content_response.message));
+            http,
+            api_url: format!("{}/api/v1/notice/get_notice_list", options.base_url_or("https://www.htx.com")),
         }
-        
-        Ok(content_response.data.content)
-    }
-    
-    /// Parse date string to DateTime
-    fn parse_date(&self, date_str: &str) -> DateTime<Utc> {
-        // Try standard format first
-        if let Ok(dt) = DateTime::parse_from_str(&format!("{} +0000", date_str), "%Y-%m-%d %H:%M:%S %z") {
-            return dt.into();
-        }
-        
-        // Fallback to just date
-        if let Ok(date) = NaiveDateTime::parse_from_str(&format!("{} 00:00:00", date_str), "%Y-%m-%d %H:%M:%S") {
-            return Utc.from_utc_datetime(&date);
-        }
-        
-        // Return current time if parsing fails
-        Utc::now()
     }
     
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
-        let client = create_browser_client()?;
+        let client = self.http.client_or(create_browser_client);
         
         let response = retry_request(
             || async {
-                client
+                let request = client
                     .get(&self.api_url)
                     .header(
                         header::USER_AGENT,
                         "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36",
-                    )
-                    .send()
+                    );
+                self.http.transport.send(request)
                     .await
                     .context("Failed to request HTX announcements")
             },
@@ -131,10 +75,11 @@ impl HtxMonitor {
         // Use our new extract_response_data function with HTML fallback
         let htx_response = extract_response_data::<HtxResponse>(
             response, 
-            Some(|html| extract_htx_html(html))
+            Some(extract_htx_html)
         )
         .await
         .context("Failed to parse HTX announcement response")?;
+        let listed_at = Utc::now();
         
         if !htx_response.success {
             return Err(anyhow::anyhow!("HTX API returned error: {:?}", htx_response.message));
@@ -147,26 +92,34 @@ impl HtxMonitor {
                 let datetime = if item.created_at > 9999999999 {
                     // If the timestamp is in milliseconds
                     Utc.timestamp_millis_opt(item.created_at).single()
-                        .unwrap_or_else(|| Utc::now())
+                        .unwrap_or_else(|| unparsed_date(item.created_at))
                 } else {
                     // If the timestamp is in seconds
                     Utc.timestamp_opt(item.created_at, 0).single()
-                        .unwrap_or_else(|| Utc::now())
+                        .unwrap_or_else(|| unparsed_date(item.created_at))
                 };
                 
-                // Generate a UUID-like ID if none exists
-                let id = item.id.unwrap_or_else(|| format!("htx-{}", chrono::Utc::now().timestamp()));
+                // Notices without an ID link to the support center unless a link was scraped
+                let url = match (&item.link, &item.id) {
+                    (Some(link), _) => link.clone(),
+                    (None, Some(id)) => format!("https://www.htx.com/support/en-us/detail/{}", id),
+                    (None, None) => "https://www.htx.com/support/en-us/".to_string(),
+                };
+                let id = item.id.unwrap_or_else(|| fallback_id(&item.title, item.created_at));
                 
-                Announcement {
+                let mut announcement = Announcement::new(
                     id,
-                    title: item.title,
-                    content: item.content,
-                    url: format!("https://www.htx.com/support/en-us/detail/{}", item.id.unwrap_or_default()),
-                    exchange: "HTX".to_string(),
-                    published_at: datetime,
-                    is_new_listing: false, // Will be analyzed later
-                    token_symbols: Vec::new(),
-                }
+                    item.title,
+                    item.content,
+                    url,
+                    "HTX".to_string(),
+                    datetime,
+                );
+                announcement.listed_at = Some(listed_at);
+                
+                // Analyze if this is a new listing
+                announcement.analyze_for_new_listing();
+                announcement
             })
             .collect();
         
@@ -174,6 +127,22 @@ impl HtxMonitor {
     }
 }
 
+/// Stable ID of a notice without one, from its title and publish time, so that the seen
+/// store recognises it on the next poll and notices of the same day stay apart
+fn fallback_id(title: &str, created_at: i64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(title.as_bytes());
+    hasher.update([0]);
+    hasher.update(created_at.to_string().as_bytes());
+    let hash: String = hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("htx-{}", hash)
+}
+
 /// Extract HTX announcements from HTML when API returns HTML instead of JSON
 fn extract_htx_html(html: &str) -> Result<HtxResponse> {
     tracing::info!("Attempting to extract HTX announcements from HTML");
@@ -190,6 +159,10 @@ fn extract_htx_html(html: &str) -> Result<HtxResponse> {
     let date_re = Regex::new(r#"<div[^>]*class="article-date[^>]*>(.*?)</div>"#)
         .context("Failed to compile HTX date regex")?;
     
+    // Link extractor regex
+    let link_re = Regex::new(r#"<a[^>]*href="([^"]+)""#)
+        .context("Failed to compile HTX link regex")?;
+    
     // Collect announcements from HTML
     let mut announcements = Vec::new();
     
@@ -210,16 +183,23 @@ fn extract_htx_html(html: &str) -> Result<HtxResponse> {
                 .unwrap_or("2025-01-01");
             
             // Try to parse the date
-            let created_at = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d")
-                .map(|d| d.and_hms_opt(0, 0, 0).unwrap().timestamp())
-                .unwrap_or_else(|_| chrono::Utc::now().timestamp());
+            let created_at = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+                .unwrap_or_else(|_| unparsed_date(date_str).timestamp());
+            
+            // Extract the link, its last path segment is the notice ID
+            let link = link_re.captures(article_text)
+                .and_then(|c| c.get(1))
+                .and_then(|m| url::Url::parse("https://www.htx.com/").ok()?.join(m.as_str()).ok());
+            let id = link.as_ref()
+                .and_then(|link| link.path_segments()?.rfind(|s| !s.is_empty()).map(str::to_string));
             
             announcements.push(HtxItem {
-                id: None,
+                id,
                 title,
                 content: "".to_string(),
                 created_at,
-                lang: "en_US".to_string(),
+                link: link.map(String::from),
             });
         }
     }
@@ -230,10 +210,8 @@ fn extract_htx_html(html: &str) -> Result<HtxResponse> {
         // Return empty successful response
         return Ok(HtxResponse {
             success: true,
-            code: 200,
             message: None,
             data: HtxData {
-                total: 0,
                 list: Vec::new(),
             },
         });
@@ -242,80 +220,93 @@ fn extract_htx_html(html: &str) -> Result<HtxResponse> {
     // Return found announcements
     Ok(HtxResponse {
         success: true,
-        code: 200,
         message: None,
         data: HtxData {
-            total: announcements.len() as i32,
             list: announcements,
         },
     })
 }
 
-/// Extract HTX announcement content from HTML when API returns HTML instead of JSON
-fn extract_htx_html_content(html: &str) -> Result<HtxContentResponse> {
-    tracing::info!("Attempting to extract HTX announcement content from HTML");
-    
-    // Simple regex to find content data in the HTML
-    let re_pattern = r#"<div\s+class="article-content[^>]*>(.*?)</div>"#;
-    let re = Regex::new(re_pattern).context("Failed to compile HTX HTML regex")?;
-    
-    // Collect content from HTML
-    let mut content = "";
-    
-    for cap in re.captures_iter(html) {
-        if let Some(article_html) = cap.get(1) {
-            let article_text = article_html.as_str();
-            
-            // Extract content
-            content = article_text.trim();
-        }
+#[async_trait]
+impl ExchangeMonitor for HtxMonitor {
+    fn exchange_name(&self) -> &str {
+        "HTX"
     }
     
-    // If extraction failed or found no content
-    if content.is_empty() {
-        tracing::warn!("No content found in HTX HTML content");
-        // Return empty successful response
-        return Ok(HtxContentResponse {
-            success: true,
-            code: 200,
-            message: None,
-            data: HtxContentData {
-                content: "".to_string(),
-            },
-        });
+    fn endpoint(&self) -> &str {
+        &self.api_url
     }
     
-    // Return found content
-    Ok(HtxContentResponse {
-        success: true,
-        code: 200,
-        message: None,
-        data: HtxContentData {
-            content: content.to_string(),
-        },
-    })
+    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+        self.fetch_announcements().await
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct HtxContentResponse {
-    success: bool,
-    code: i32,
-    message: Option<String>,
-    data: HtxContentData,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics::{self, BodyFormat};
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
 
-#[derive(Debug, Deserialize)]
-struct HtxContentData {
-    content: String,
-}
+    #[tokio::test]
+    async fn parses_recorded_notices() {
+        let monitor = HtxMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("htx")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
 
-#[async_trait]
-impl ExchangeMonitor for HtxMonitor {
-    fn exchange_name(&self) -> &str {
-        "HTX"
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.body_format, Some(BodyFormat::Json));
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        // Timestamps come in milliseconds or seconds
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "44963582");
+        assert_eq!(listing.url, "https://www.htx.com/support/en-us/detail/44963582");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 4, 0, 0).unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+
+        // Notices without an ID get one derived from the title and publish time
+        let maintenance = &announcements[1];
+        assert_eq!(maintenance.id, "htx-48225b8ed238f670");
+        assert_eq!(maintenance.url, "https://www.htx.com/support/en-us/");
+        assert_eq!(maintenance.published_at, Utc.with_ymd_and_hms(2024, 6, 18, 2, 0, 0).unwrap());
+        assert_eq!(maintenance.kind, AnnouncementKind::Maintenance);
     }
-    
-    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
-        self.fetch_announcements().await
+
+    #[tokio::test]
+    async fn falls_back_to_html_page() {
+        let monitor = HtxMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("htx-html")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(diagnostics.body_format, Some(BodyFormat::HtmlFallback));
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let titles: Vec<&str> = announcements.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, ["HTX Will List LayerZero (ZRO)", "HTX Will List Notcoin (NOT)", "HTX Will Delist BTT/USDT"]);
+        assert_eq!(announcements[0].published_at, Utc.with_ymd_and_hms(2024, 6, 20, 0, 0, 0).unwrap());
+        assert_eq!(announcements[0].kind, AnnouncementKind::SpotListing);
+        assert_eq!(announcements[2].kind, AnnouncementKind::Delisting);
+    }
+
+    #[tokio::test]
+    async fn html_notices_of_the_same_day_keep_their_own_ids() {
+        let monitor = HtxMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("htx-html")));
+        let announcements = monitor.fetch_announcements().await.unwrap();
+
+        // Both listings were published on 2024-06-20, their IDs come from their links
+        assert_eq!(announcements[0].published_at, announcements[1].published_at);
+        assert_eq!(announcements[0].id, "44963582");
+        assert_eq!(announcements[0].url, "https://www.htx.com/support/en-us/detail/44963582");
+        assert_eq!(announcements[1].id, "44963590");
+        assert_eq!(announcements[1].url, "https://www.htx.com/support/en-us/detail/44963590");
+
+        // Without a link the ID is derived from the title and date, and must not
+        // change between releases or the seen store would report the notice again
+        let delisting = &announcements[2];
+        assert_eq!(delisting.id, "htx-0ec972556f27b913");
+        assert_eq!(delisting.url, "https://www.htx.com/support/en-us/");
     }
 }
@@ -1,23 +1,28 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc, NaiveDateTime, TimeZone};
 use reqwest::Client;
 use scraper::{Html, Selector};
+use std::sync::Arc;
 
 /// Kraken announcement monitor
 pub struct KrakenMonitor {
     client: Client,
-    base_url: String,
+    transport: Arc<dyn HttpTransport>,
+    endpoint: String,
 }
 
 impl KrakenMonitor {
-    /// Create a new Kraken monitor
-    pub fn new() -> Self {
+    /// Create a new Kraken monitor with the settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: Client::new(),
-            base_url: "https://blog.kraken.com/product-updates".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/product-updates", options.base_url_or("https://blog.kraken.com")),
         }
     }
     
@@ -32,7 +37,7 @@ impl KrakenMonitor {
                 date_str = date_str,
                 "Failed to parse Kraken date string"
             );
-            Utc::now()
+            unparsed_date(date_str)
         }
     }
 }
@@ -43,10 +48,14 @@ impl ExchangeMonitor for KrakenMonitor {
         "Kraken"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Make the request to the Kraken blog
-        let response = self.client.get(&self.base_url)
-            .send()
+        let request = self.client.get(&self.endpoint);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Kraken blog")?;
         
@@ -56,6 +65,7 @@ impl ExchangeMonitor for KrakenMonitor {
         
         // Parse the HTML
         let document = Html::parse_document(&html);
+        let listed_at = Utc::now();
         
         // Define selectors for blog posts
         let post_selector = Selector::parse("article.blog-post").unwrap();
@@ -108,6 +118,7 @@ impl ExchangeMonitor for KrakenMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -117,3 +128,33 @@ impl ExchangeMonitor for KrakenMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_blog_page() {
+        let monitor = KrakenMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("kraken")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        // The post without a title is skipped
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.title, "ZRO is now available for trading!");
+        assert_eq!(listing.url, "https://blog.kraken.com/product/asset-listings/zro-is-available-for-trading");
+        assert_eq!(listing.id, "kraken_https:__blog.kraken.com_product_asset-listings_zro-is-available-for-trading");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 0, 0, 0).unwrap());
+        assert_eq!(listing.content, "Trading for LayerZero (ZRO) starts June 20, 2024. Deposits are open now.");
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+
+        assert_eq!(announcements[1].kind, AnnouncementKind::Other);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{dedup_announcements, ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::MonitorHttp;
 use crate::utils::{create_browser_client, retry_request, extract_response_data};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
@@ -10,8 +12,11 @@ use regex::Regex;
 
 /// KuCoin announcement monitor
 pub struct KucoinMonitor {
-    base_url: String,
+    http: MonitorHttp,
     api_url: String,
+    language: String,
+    page_size: u32,
+    categories: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -23,14 +28,6 @@ struct KucoinAnnouncementResponse {
 #[derive(Debug, Deserialize)]
 struct KucoinAnnouncementData {
     items: Vec<KucoinAnnouncement>,
-    #[serde(rename = "totalPage")]
-    total_page: i32,
-    #[serde(rename = "pageSize")]
-    page_size: i32,
-    #[serde(rename = "currentPage")]
-    current_page: i32,
-    #[serde(rename = "totalNum")]
-    total_num: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,30 +42,41 @@ struct KucoinAnnouncement {
 }
 
 impl KucoinMonitor {
-    /// Create a new KuCoin monitor
-    pub fn new() -> Self {
+    /// Create a new KuCoin monitor with the request settings from the config file, the
+    /// catalog IDs are KuCoin's article categories. Unless a shared client is injected,
+    /// which `exchanges::monitor_http` does for mirrors, each poll builds its own
+    /// client with the next proxy of the pool.
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            base_url: "https://www.kucoin.com/api/v1/news/list".to_string(),
-            api_url: "https://www.kucoin.com/_api/cms/articles?page=1&pageSize=20&category=listing&lang=en_US".to_string(),
+            http,
+            api_url: format!("{}/_api/cms/articles", options.base_url_or("https://www.kucoin.com")),
+            language: options.language_or("en_US"),
+            page_size: options.page_size_or(20),
+            categories: options.catalog_ids_or(&["listing"]),
         }
     }
     
-    /// Fetch KuCoin announcements
-    pub async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
-        let client = match create_browser_client() {
-            Ok(client) => client,
-            Err(_) => reqwest::Client::new(),
-        };
+    /// Fetch the KuCoin announcements of one category
+    async fn fetch_category(&self, category: &str) -> Result<Vec<Announcement>> {
+        let client = self.http.client_or(create_browser_client);
+        let page_size = self.page_size.to_string();
+        let params = [
+            ("page", "1"),
+            ("pageSize", page_size.as_str()),
+            ("category", category),
+            ("lang", self.language.as_str()),
+        ];
         
         let response = retry_request(
             || async {
-                client
+                let request = client
                     .get(&self.api_url)
+                    .query(&params)
                     .header(
                         header::USER_AGENT,
                         "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36",
-                    )
-                    .send()
+                    );
+                self.http.transport.send(request)
                     .await
                     .context("Failed to request KuCoin announcements")
             },
@@ -81,10 +89,11 @@ impl KucoinMonitor {
         // Use our new extract_response_data function with HTML fallback
         let kucoin_response = extract_response_data::<KucoinAnnouncementResponse>(
             response, 
-            Some(|html| extract_kucoin_html(html))
+            Some(extract_kucoin_html)
         )
         .await
         .context("Failed to parse KuCoin announcement response")?;
+        let listed_at = Utc::now();
         
         if kucoin_response.code != "200000" {
             return Err(anyhow::anyhow!("KuCoin API returned error: {:?}", kucoin_response.code));
@@ -97,23 +106,26 @@ impl KucoinMonitor {
                 let datetime = if item.published_at > 9999999999 {
                     // If the timestamp is in milliseconds (more than 10 digits)
                     Utc.timestamp_millis_opt(item.published_at).single()
-                        .unwrap_or_else(|| Utc::now())
+                        .unwrap_or_else(|| unparsed_date(item.published_at))
                 } else {
                     // If the timestamp is in seconds
                     Utc.timestamp_opt(item.published_at, 0).single()
-                        .unwrap_or_else(|| Utc::now())
+                        .unwrap_or_else(|| unparsed_date(item.published_at))
                 };
                 
-                Announcement {
-                    id: item.id,
-                    title: item.title,
-                    content: item.summary.unwrap_or_default(),
-                    url: item.web_path,
-                    exchange: "KuCoin".to_string(),
-                    published_at: datetime,
-                    is_new_listing: false, // Default, can be analyzed later
-                    token_symbols: Vec::new(),
-                }
+                let mut announcement = Announcement::new(
+                    item.id,
+                    item.title,
+                    item.summary.unwrap_or_default(),
+                    item.web_path,
+                    "KuCoin".to_string(),
+                    datetime,
+                );
+                announcement.listed_at = Some(listed_at);
+                
+                // Analyze if this is a new listing
+                announcement.analyze_for_new_listing();
+                announcement
             })
             .collect();
         
@@ -161,18 +173,9 @@ fn extract_kucoin_html(html: &str) -> Result<KucoinAnnouncementResponse> {
                             })
                             .unwrap_or_default();
                         
-                        // Store the length for use in other fields
-                        let items_len = items.len();
-                        
                         return Ok(KucoinAnnouncementResponse {
                             code: "200000".to_string(),
-                            data: KucoinAnnouncementData { 
-                                items,
-                                total_page: 1,
-                                page_size: items_len as i32,
-                                current_page: 1,
-                                total_num: items_len as i32,
-                            },
+                            data: KucoinAnnouncementData { items },
                         });
                     }
                 },
@@ -186,13 +189,7 @@ fn extract_kucoin_html(html: &str) -> Result<KucoinAnnouncementResponse> {
     // If extraction failed, return empty response
     Ok(KucoinAnnouncementResponse {
         code: "200000".to_string(),
-        data: KucoinAnnouncementData { 
-            items: vec![],
-            total_page: 1,
-            page_size: 0,
-            current_page: 1,
-            total_num: 0,
-        },
+        data: KucoinAnnouncementData { items: vec![] },
     })
 }
 
@@ -202,7 +199,70 @@ impl ExchangeMonitor for KucoinMonitor {
         "KuCoin"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.api_url
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
-        self.fetch_announcements().await
+        let mut announcements = Vec::new();
+        for category in &self.categories {
+            announcements.extend(self.fetch_category(category).await?);
+        }
+        Ok(dedup_announcements(announcements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics::{self, BodyFormat};
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_articles() {
+        let monitor = KucoinMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("kucoin")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.body_format, Some(BodyFormat::Json));
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "kucoin-will-list-layerzero-zro");
+        assert_eq!(listing.url, "https://www.kucoin.com/announcement/kucoin-will-list-layerzero-zro");
+        assert_eq!(listing.content, "Trading: 08:00 on June 20, 2024 (UTC)");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 2, 0, 0).unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+
+        // Timestamps in seconds are accepted too
+        let futures = &announcements[1];
+        assert_eq!(futures.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 9, 0, 0).unwrap());
+        assert_eq!(futures.kind, AnnouncementKind::FuturesListing);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_html_page() {
+        let monitor = KucoinMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("kucoin-html")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(diagnostics.body_format, Some(BodyFormat::HtmlFallback));
+
+        // The article without a publish date is skipped
+        let ids: Vec<&str> = announcements.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["kucoin-will-list-layerzero-zro", "kucoin-will-delist-btt"]);
+        assert_eq!(announcements[0].url, "https://www.kucoin.com/news/kucoin-will-list-layerzero-zro");
+        assert_eq!(announcements[0].published_at, Utc.with_ymd_and_hms(2024, 6, 20, 2, 0, 0).unwrap());
+        assert_eq!(announcements[0].kind, AnnouncementKind::SpotListing);
+        assert_eq!(announcements[1].kind, AnnouncementKind::Delisting);
+    }
+
+    #[test]
+    fn html_page_without_state_has_no_articles() {
+        let response = extract_kucoin_html("<html><body>Access denied</body></html>").unwrap();
+        assert_eq!(response.code, "200000");
+        assert!(response.data.items.is_empty());
     }
 }
@@ -1,15 +1,23 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{dedup_announcements, ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc, TimeZone};
-use serde::{Deserialize, Serialize};
+use chrono::{Utc, TimeZone};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// MEXC announcement monitor
 pub struct MexcMonitor {
     client: Client,
+    transport: Arc<dyn HttpTransport>,
     base_url: String,
+    endpoint: String,
+    language: String,
+    page_size: u32,
+    catalog_ids: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,8 +29,8 @@ struct MexcAnnouncementResponse {
 
 #[derive(Debug, Deserialize)]
 struct MexcAnnouncementData {
-    dataList: Vec<MexcAnnouncement>,
-    total: i32,
+    #[serde(rename = "dataList")]
+    data_list: Vec<MexcAnnouncement>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,20 +44,26 @@ struct MexcAnnouncement {
 }
 
 impl MexcMonitor {
-    /// Create a new MEXC monitor
-    pub fn new() -> Self {
+    /// Create a new MEXC monitor with the request settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
+        let base_url = options.base_url_or("https://www.mexc.com");
         Self {
-            client: Client::new(),
-            base_url: "https://www.mexc.com/api/platform/notice/list".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/api/platform/notice/list", base_url),
+            base_url,
+            language: options.language_or("en_US"),
+            page_size: options.page_size_or(20),
+            catalog_ids: options.catalog_ids_or(&["5"]), // 5 is the new token listings category
         }
     }
     
     /// Fetch the content for a specific announcement
     async fn fetch_announcement_content(&self, id: &str) -> Result<String> {
-        let url = format!("https://www.mexc.com/api/platform/notice/detail?id={}", id);
+        let url = format!("{}/api/platform/notice/detail?id={}", self.base_url, id);
         
-        let response = self.client.get(&url)
-            .send()
+        let request = self.client.get(&url);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request MEXC announcement content")?;
         
@@ -62,8 +76,6 @@ impl MexcMonitor {
         
         #[derive(Debug, Deserialize)]
         struct MexcContentData {
-            id: String,
-            title: String,
             content: String,
         }
         
@@ -79,27 +91,22 @@ impl MexcMonitor {
         
         Ok(content_response.data.content)
     }
-}
-
-#[async_trait]
-impl ExchangeMonitor for MexcMonitor {
-    fn exchange_name(&self) -> &str {
-        "MEXC"
-    }
     
-    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+    /// Fetch the announcements of one catalog
+    async fn fetch_catalog(&self, catalog_id: &str) -> Result<Vec<Announcement>> {
         // Request parameters for the MEXC API
+        let page_size = self.page_size.to_string();
         let params = [
             ("pageNum", "1"),
-            ("pageSize", "20"),
-            ("catalogId", "5"), // New token listings category
-            ("lang", "en_US"),
+            ("pageSize", page_size.as_str()),
+            ("catalogId", catalog_id),
+            ("lang", self.language.as_str()),
         ];
         
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .query(&params)
-            .send()
+        let request = self.client.get(&self.endpoint)
+            .query(&params);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request MEXC announcements")?;
         
@@ -107,6 +114,7 @@ impl ExchangeMonitor for MexcMonitor {
         let mexc_response: MexcAnnouncementResponse = response.json()
             .await
             .context("Failed to parse MEXC announcement response")?;
+        let listed_at = Utc::now();
         
         // Check if the request was successful
         if mexc_response.code != 200 {
@@ -117,17 +125,21 @@ impl ExchangeMonitor for MexcMonitor {
         
         // Convert MEXC announcements to our standard format
         let mut announcements = Vec::new();
-        for mexc_announcement in mexc_response.data.dataList {
+        for mexc_announcement in mexc_response.data.data_list {
             // Convert timestamp to DateTime<Utc>
             let published_at = Utc.timestamp_opt(mexc_announcement.create_time / 1000, 0)
                 .single()
-                .unwrap_or_else(|| Utc::now());
+                .unwrap_or_else(|| unparsed_date(mexc_announcement.create_time));
             
             // Get content from the announcement or fetch it if not available
+            let mut content_fetched_at = None;
             let content = match mexc_announcement.content {
                 Some(content) if !content.is_empty() => content,
                 _ => match self.fetch_announcement_content(&mexc_announcement.id).await {
-                    Ok(content) => content,
+                    Ok(content) => {
+                        content_fetched_at = Some(Utc::now());
+                        content
+                    }
                     Err(e) => {
                         tracing::warn!(
                             exchange = self.exchange_name(),
@@ -154,6 +166,8 @@ impl ExchangeMonitor for MexcMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
+            announcement.content_fetched_at = content_fetched_at;
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -163,3 +177,55 @@ impl ExchangeMonitor for MexcMonitor {
         Ok(announcements)
     }
 }
+
+#[async_trait]
+impl ExchangeMonitor for MexcMonitor {
+    fn exchange_name(&self) -> &str {
+        "MEXC"
+    }
+    
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
+    async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+        let mut announcements = Vec::new();
+        for catalog_id in &self.catalog_ids {
+            announcements.extend(self.fetch_catalog(catalog_id).await?);
+        }
+        Ok(dedup_announcements(announcements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_notices_and_details() {
+        let monitor = MexcMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("mexc")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        // Notices without content or URL get them from the detail endpoint and the ID
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "1013456");
+        assert_eq!(listing.url, "https://www.mexc.com/support/notice/detail?id=1013456");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 19, 12, 0, 0).unwrap());
+        assert!(listing.content.starts_with("MEXC will list LayerZero (ZRO)"));
+        assert!(listing.content_fetched_at.is_some());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+
+        let airdrop = &announcements[1];
+        assert_eq!(airdrop.url, "https://www.mexc.com/support/articles/1013402");
+        assert!(airdrop.content_fetched_at.is_none());
+        assert_eq!(airdrop.kind, AnnouncementKind::Airdrop);
+    }
+}
@@ -11,3 +11,135 @@ pub mod htx;
 pub mod mexc;
 pub mod kucoin;
 pub mod monitor;
+pub mod status;
+pub mod supervisor;
+
+use reqwest::Client;
+use crate::config::Config;
+use crate::transport::MonitorHttp;
+use self::monitor::{ExchangeMonitor, MonitorOptions};
+use self::monitor::RequestSetting::{self, CatalogIds, Language, PageSize};
+use self::{
+    binance::BinanceMonitor,
+    okx::OkxMonitor,
+    bybit::BybitMonitor,
+    bitmex::BitmexMonitor,
+    gateio::GateioMonitor,
+    kraken::KrakenMonitor,
+    coinbase::CoinbaseMonitor,
+    upbit::UpbitMonitor,
+    bitget::BitgetMonitor,
+    htx::HtxMonitor,
+    mexc::MexcMonitor,
+    kucoin::KucoinMonitor,
+};
+
+/// Every supported exchange, with the request settings its monitor understands
+pub const SUPPORTED_EXCHANGES: &[(&str, &[RequestSetting])] = &[
+    ("Binance", &[PageSize, CatalogIds]),
+    ("OKX", &[Language]),
+    ("Bybit", &[Language, PageSize]),
+    ("BitMEX", &[]),
+    ("Gate.io", &[Language, PageSize]),
+    ("Kraken", &[]),
+    ("Coinbase", &[]),
+    ("Upbit", &[PageSize]),
+    ("Bitget", &[Language, PageSize, CatalogIds]),
+    ("HTX", &[]),
+    ("MEXC", &[Language, PageSize, CatalogIds]),
+    ("KuCoin", &[Language, PageSize, CatalogIds]),
+];
+
+/// Exchanges whose monitors build a new client per request to rotate through the
+/// proxy pool. They are the exception to the shared client, which they only use
+/// when their `base_url` points them at a mirror.
+pub const PROXY_POOL_EXCHANGES: &[&str] = &["Binance", "HTX", "KuCoin"];
+
+/// Create and return all available exchange monitors, with the request settings
+/// from the config file. The monitors share `client` and its connections, except
+/// for the ones rotating through the proxy pool, see `monitor_http`.
+pub fn create_monitors(config: &Config, client: &Client) -> Vec<Box<dyn ExchangeMonitor>> {
+    create_monitors_with(config, |exchange| {
+        monitor_http(exchange, &config.monitor_options(exchange), client)
+    })
+}
+
+/// How an exchange's monitor sends its requests over the network: with the shared
+/// client, unless it rotates through the proxy pool. The proxies only get around
+/// the exchanges' rate limits, so a monitor pointed at a mirror or a mock server
+/// with `base_url` uses the shared client like any other.
+pub fn monitor_http(exchange: &str, options: &MonitorOptions, client: &Client) -> MonitorHttp {
+    if PROXY_POOL_EXCHANGES.contains(&exchange) && options.base_url.is_none() {
+        MonitorHttp::live()
+    } else {
+        MonitorHttp::shared(client.clone())
+    }
+}
+
+/// Create all available exchange monitors, each one sending its requests with the
+/// client and through the transport returned for its exchange name, e.g. to share one
+/// client between monitors or to record or replay fixtures
+pub fn create_monitors_with(
+    config: &Config,
+    http: impl Fn(&str) -> MonitorHttp,
+) -> Vec<Box<dyn ExchangeMonitor>> {
+    vec![
+        Box::new(BinanceMonitor::with_options(&config.monitor_options("Binance"), http("Binance"))),
+        Box::new(OkxMonitor::with_options(&config.monitor_options("OKX"), http("OKX"))),
+        Box::new(BybitMonitor::with_options(&config.monitor_options("Bybit"), http("Bybit"))),
+        Box::new(BitmexMonitor::with_options(&config.monitor_options("BitMEX"), http("BitMEX"))),
+        Box::new(GateioMonitor::with_options(&config.monitor_options("Gate.io"), http("Gate.io"))),
+        Box::new(KrakenMonitor::with_options(&config.monitor_options("Kraken"), http("Kraken"))),
+        Box::new(CoinbaseMonitor::with_options(&config.monitor_options("Coinbase"), http("Coinbase"))),
+        Box::new(UpbitMonitor::with_options(&config.monitor_options("Upbit"), http("Upbit"))),
+        Box::new(BitgetMonitor::with_options(&config.monitor_options("Bitget"), http("Bitget"))),
+        Box::new(HtxMonitor::with_options(&config.monitor_options("HTX"), http("HTX"))),
+        Box::new(MexcMonitor::with_options(&config.monitor_options("MEXC"), http("MEXC"))),
+        Box::new(KucoinMonitor::with_options(&config.monitor_options("KuCoin"), http("KuCoin"))),
+    ]
+}
+
+/// Find a supported exchange by name, ignoring case and punctuation so that "gateio",
+/// "gate.io" and "Gate.io" all match. Returns the exchange's display name.
+pub fn find_exchange(name: &str) -> Option<&'static str> {
+    let key = name_key(name);
+    SUPPORTED_EXCHANGES.iter()
+        .map(|(exchange, _)| *exchange)
+        .find(|exchange| name_key(exchange) == key)
+}
+
+/// Request settings supported by an exchange's monitor
+pub fn supported_settings(exchange: &str) -> &'static [RequestSetting] {
+    SUPPORTED_EXCHANGES.iter()
+        .find(|(name, _)| *name == exchange)
+        .map(|(_, settings)| *settings)
+        .unwrap_or_default()
+}
+
+/// Name of an exchange reduced to lowercase letters and digits, e.g. "gateio"
+pub fn name_key(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proxy_pool_monitors_use_the_shared_client_for_mirrors() {
+        let client = Client::new();
+        let mirror = MonitorOptions {
+            base_url: Some("http://127.0.0.1:8080".to_string()),
+            ..Default::default()
+        };
+
+        for (exchange, _) in SUPPORTED_EXCHANGES {
+            let rotates = PROXY_POOL_EXCHANGES.contains(exchange);
+            assert_eq!(monitor_http(exchange, &MonitorOptions::default(), &client).client.is_none(), rotates, "{}", exchange);
+            assert!(monitor_http(exchange, &mirror, &client).client.is_some(), "{}", exchange);
+        }
+    }
+}
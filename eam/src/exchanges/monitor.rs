@@ -1,6 +1,84 @@
 use async_trait::async_trait;
 use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use crate::events::{EventBus, MonitorEvent};
+use crate::metrics;
 use crate::models::announcement::Announcement;
+use crate::store::seen::SeenStore;
+
+/// Shared state handed to every monitor's run loop
+#[derive(Clone)]
+pub struct MonitorContext {
+    /// Store used to report every announcement only once, across restarts
+    pub seen_store: Arc<dyn SeenStore>,
+    /// Treat the first poll after startup as a baseline: record everything as seen
+    /// without reporting it
+    pub baseline_first_poll: bool,
+    /// Bus receiving the results of every poll, see `events`
+    pub events: EventBus,
+}
+
+/// Request settings of a monitor from its `[exchanges.<name>]` section of the config
+/// file. Unset values keep the monitor's built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MonitorOptions {
+    /// Language of the announcements, in the exchange's own format (e.g. "en_US")
+    pub language: Option<String>,
+    /// Number of announcements requested per poll
+    pub page_size: Option<u32>,
+    /// Announcement categories to poll, each one with its own request
+    pub catalog_ids: Vec<String>,
+    /// Replaces the scheme and host of the exchange's API, e.g. a regional mirror, a
+    /// caching reverse proxy or a mock server. Links to the announcements are kept.
+    pub base_url: Option<String>,
+}
+
+impl MonitorOptions {
+    pub fn language_or(&self, default: &str) -> String {
+        self.language.clone().unwrap_or_else(|| default.to_string())
+    }
+    
+    pub fn page_size_or(&self, default: u32) -> u32 {
+        self.page_size.unwrap_or(default)
+    }
+    
+    /// Base URL of the API without a trailing slash, so that paths can be appended
+    pub fn base_url_or(&self, default: &str) -> String {
+        self.base_url.as_deref().unwrap_or(default).trim_end_matches('/').to_string()
+    }
+    
+    pub fn catalog_ids_or(&self, default: &[&str]) -> Vec<String> {
+        if self.catalog_ids.is_empty() {
+            default.iter().map(|id| id.to_string()).collect()
+        } else {
+            self.catalog_ids.clone()
+        }
+    }
+}
+
+/// A `MonitorOptions` field, used to check which settings an exchange supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestSetting {
+    Language,
+    PageSize,
+    CatalogIds,
+}
+
+impl RequestSetting {
+    /// Name of the setting in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            RequestSetting::Language => "language",
+            RequestSetting::PageSize => "page_size",
+            RequestSetting::CatalogIds => "catalog_ids",
+        }
+    }
+}
 
 /// ExchangeMonitor trait defines the common interface for all exchange announcement monitors
 #[async_trait]
@@ -8,59 +86,171 @@ pub trait ExchangeMonitor: Send + Sync {
     /// Returns the name of the exchange being monitored
     fn exchange_name(&self) -> &str;
     
+    /// URL of the exchange's announcement list endpoint, checked by the `doctor` command
+    fn endpoint(&self) -> &str;
+    
     /// Asynchronously fetches the latest announcements from the exchange
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>>;
     
-    /// Run the monitoring loop with the specified polling interval in seconds
-    async fn run(&self, interval_seconds: u64) -> Result<()> {
+    /// Run the monitoring loop until `shutdown` is cancelled, polling at the interval in
+    /// seconds held by `interval_seconds`.
+    ///
+    /// The loop only fetches and detects new announcements, everything else happens
+    /// in the consumers of the `MonitorEvent`s it publishes. Cancellation is checked
+    /// between polls, so a fetch in flight always completes and gets published. A new
+    /// interval applies from the last poll on, without restarting the loop.
+    async fn run(
+        &self,
+        mut interval_seconds: watch::Receiver<u64>,
+        context: MonitorContext,
+        shutdown: CancellationToken,
+    ) -> Result<()> {
         let exchange_name = self.exchange_name();
+        let period = Duration::from_secs(*interval_seconds.borrow_and_update());
         
         tracing::info!(exchange = exchange_name, "Starting monitor for {}", exchange_name);
+        context.events.publish(MonitorEvent::MonitorStarted {
+            exchange: exchange_name.to_string(),
+            interval_seconds: period.as_secs(),
+        });
         
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        let mut interval = tokio::time::interval(period);
+        let mut last_poll = tokio::time::Instant::now();
+        let mut is_baseline = context.baseline_first_poll;
         
         loop {
-            interval.tick().await;
+            tokio::select! {
+                tick = interval.tick() => last_poll = tick,
+                Ok(()) = interval_seconds.changed() => {
+                    let period = Duration::from_secs(*interval_seconds.borrow_and_update());
+                    interval = tokio::time::interval_at(last_poll + period, period);
+                    tracing::info!(
+                        exchange = exchange_name,
+                        interval_seconds = period.as_secs(),
+                        "Polling {} every {} seconds",
+                        exchange_name, period.as_secs()
+                    );
+                    context.events.publish(MonitorEvent::IntervalChanged {
+                        exchange: exchange_name.to_string(),
+                        interval_seconds: period.as_secs(),
+                    });
+                    continue;
+                }
+                _ = shutdown.cancelled() => break,
+            }
             
             tracing::info!(exchange = exchange_name, "Fetching announcements from {}", exchange_name);
             
-            match self.fetch_announcements().await {
-                Ok(announcements) => {
+            let started = Instant::now();
+            let result = metrics::with_exchange(exchange_name, self.fetch_announcements()).await;
+            let duration = started.elapsed();
+            
+            match result {
+                Ok(mut announcements) => {
                     let total = announcements.len();
-                    let new_listings = announcements.iter()
-                        .filter(|a| a.is_new_listing)
+                    
+                    // Keep only announcements that have not been reported before
+                    let unseen_indices = filter_unseen(context.seen_store.as_ref(), &announcements).await;
+                    
+                    // Stamp new announcements with their detection time and publish them
+                    // right away. The baseline poll only finds old announcements, so it
+                    // reports nothing and its latencies would be meaningless.
+                    if !is_baseline {
+                        let detected_at = Utc::now();
+                        for &index in &unseen_indices {
+                            let announcement = &mut announcements[index];
+                            announcement.detected_at = Some(detected_at);
+                            context.events.publish(MonitorEvent::NewAnnouncement(Arc::new(announcement.clone())));
+                        }
+                    }
+                    
+                    let new_listings = unseen_indices.iter()
+                        .filter(|&&index| announcements[index].is_new_listing)
                         .count();
                     
                     tracing::info!(
                         exchange = exchange_name,
                         total_announcements = total,
+                        unseen_announcements = unseen_indices.len(),
                         new_listings = new_listings,
-                        "Retrieved {} announcements from {}, {} are new listings",
-                        total, exchange_name, new_listings
+                        "Retrieved {} announcements from {}, {} unseen, {} are new listings",
+                        total, exchange_name, unseen_indices.len(), new_listings
                     );
                     
-                    // Process new listing announcements
-                    for announcement in announcements.iter().filter(|a| a.is_new_listing) {
-                        let token_list = announcement.token_symbols.join(", ");
+                    if is_baseline {
+                        // The first poll only seeds the store, everything in it is old news
                         tracing::info!(
                             exchange = exchange_name,
-                            title = announcement.title,
-                            tokens = token_list,
-                            url = announcement.url,
-                            "New listing announcement: {}",
-                            announcement.title
+                            recorded = unseen_indices.len(),
+                            "Recorded {} announcements from {} as baseline without reporting them",
+                            unseen_indices.len(), exchange_name
                         );
                     }
+                    
+                    context.events.publish(MonitorEvent::AnnouncementsFetched {
+                        exchange: exchange_name.to_string(),
+                        announcements: announcements.into(),
+                        new_announcements: unseen_indices.len(),
+                        baseline: is_baseline,
+                        duration,
+                    });
+                    is_baseline = false;
                 }
                 Err(e) => {
+                    let class = metrics::error_class(&e);
                     tracing::error!(
                         exchange = exchange_name,
                         error = %e,
+                        error_class = class,
                         "Failed to fetch announcements from {}: {}",
                         exchange_name, e
                     );
+                    context.events.publish(MonitorEvent::FetchFailed {
+                        exchange: exchange_name.to_string(),
+                        error: format!("{:#}", e),
+                        class,
+                        duration,
+                    });
                 }
             }
         }
+        
+        tracing::info!(exchange = exchange_name, "Stopped monitor for {}", exchange_name);
+        Ok(())
+    }
+}
+
+/// Record every announcement in the seen store and return the indices of the ones
+/// that were not known before.
+///
+/// If the store fails, the announcement is treated as unseen: reporting a listing
+/// twice is preferable to missing it.
+async fn filter_unseen(seen_store: &dyn SeenStore, announcements: &[Announcement]) -> Vec<usize> {
+    let mut unseen = Vec::new();
+    
+    for (index, announcement) in announcements.iter().enumerate() {
+        match seen_store.mark_seen(announcement).await {
+            Ok(true) => unseen.push(index),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(
+                    exchange = announcement.exchange,
+                    announcement_id = announcement.id,
+                    error = %e,
+                    "Failed to update seen store, treating announcement as unseen"
+                );
+                unseen.push(index);
+            }
+        }
     }
+    
+    unseen
+}
+
+/// Drop announcements returned more than once, e.g. by two catalogs of the same exchange,
+/// keeping the first copy
+pub fn dedup_announcements(mut announcements: Vec<Announcement>) -> Vec<Announcement> {
+    let mut ids = HashSet::new();
+    announcements.retain(|announcement| ids.insert(announcement.id.clone()));
+    announcements
 }
@@ -1,15 +1,20 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc, TimeZone};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// OKX announcement monitor
 pub struct OkxMonitor {
     client: Client,
-    base_url: String,
+    transport: Arc<dyn HttpTransport>,
+    endpoint: String,
+    language: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,16 +34,16 @@ struct OkxAnnouncement {
     url_path: String,
     #[serde(rename = "sContent")]
     content: Option<String>,
-    #[serde(rename = "sCategoryName")]
-    category: Option<String>,
 }
 
 impl OkxMonitor {
-    /// Create a new OKX monitor
-    pub fn new() -> Self {
+    /// Create a new OKX monitor with the request settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
         Self {
-            client: Client::new(),
-            base_url: "https://www.okx.com/v2/support/home/web/announcement/queryList".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/v2/support/home/web/announcement/queryList", options.base_url_or("https://www.okx.com")),
+            language: options.language_or("en_US"),
         }
     }
 
@@ -53,7 +58,7 @@ impl OkxMonitor {
                 timestamp = timestamp,
                 "Failed to parse OKX timestamp"
             );
-            Utc::now()
+            unparsed_date(timestamp)
         }
     }
 }
@@ -64,17 +69,21 @@ impl ExchangeMonitor for OkxMonitor {
         "OKX"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Request parameters for OKX announcement API
         let params = [
             ("t", Utc::now().timestamp_millis().to_string()),
-            ("language", "en_US".to_string()),
+            ("language", self.language.clone()),
         ];
         
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .query(&params)
-            .send()
+        let request = self.client.get(&self.endpoint)
+            .query(&params);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request OKX announcements")?;
         
@@ -82,6 +91,7 @@ impl ExchangeMonitor for OkxMonitor {
         let okx_response: OkxAnnouncementResponse = response.json()
             .await
             .context("Failed to parse OKX announcement response")?;
+        let listed_at = Utc::now();
         
         // Check if the request was successful
         if okx_response.code != "0" {
@@ -114,6 +124,7 @@ impl ExchangeMonitor for OkxMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -123,3 +134,34 @@ impl ExchangeMonitor for OkxMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_announcements() {
+        let monitor = OkxMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("okx")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 3);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.title, "OKX to list ZRO (LayerZero) for spot trading");
+        assert_eq!(listing.url, "https://www.okx.com/help/okx-to-list-zro-layerzero-for-spot-trading");
+        assert_eq!(listing.id, "okx_https:__www.okx.com_help_okx-to-list-zro-layerzero-for-spot-trading");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 7, 0, 0).unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert!(listing.token_symbols.contains(&"ZRO".to_string()));
+
+        assert_eq!(announcements[1].kind, AnnouncementKind::Delisting);
+        assert_eq!(announcements[1].token_symbols, ["BTT", "WIN", "SUN"]);
+        // Announcements without content keep an empty one
+        assert_eq!(announcements[2].content, "");
+    }
+}
@@ -0,0 +1,289 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::events::{self, EventBus, MonitorEvent};
+use crate::exchanges::supervisor::RESTART_WINDOW;
+use crate::utils;
+
+/// A monitor is unhealthy after this many failed polls in a row
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// A monitor is unhealthy if it has not succeeded for this many polling intervals
+const STALE_INTERVALS: i64 = 3;
+
+/// Extra time on top of the stale intervals, so that slow polls on short intervals
+/// do not flap between healthy and unhealthy
+const STALE_GRACE_SECONDS: i64 = 120;
+
+/// Health of one monitor as reported by `/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorStatus {
+    pub exchange: String,
+    /// Current polling interval in seconds
+    pub interval_seconds: u64,
+    pub started_at: DateTime<Utc>,
+    pub last_poll_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Proxy used by the last request, None for direct connections
+    pub proxy: Option<String>,
+    /// False once the monitor's run loop has exited
+    pub running: bool,
+    /// Number of times the supervisor restarted the crashed run loop
+    pub restarts: u32,
+    pub last_restart_at: Option<DateTime<Utc>>,
+    /// When the crashed run loop will be restarted, None while it runs or once the
+    /// supervisor has given up on it
+    pub restarting_at: Option<DateTime<Utc>>,
+    /// Derived from the fields above, see `MonitorStatus::is_healthy`
+    pub healthy: bool,
+    /// Derived from the fields above, see `MonitorStatus::is_degraded`
+    pub degraded: bool,
+}
+
+impl MonitorStatus {
+    /// A monitor is healthy unless it has stopped, keeps failing or has not succeeded
+    /// for several intervals. A monitor that has not finished its first poll yet is healthy.
+    pub fn is_healthy(&self, now: DateTime<Utc>) -> bool {
+        if !self.running || self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            return false;
+        }
+
+        let stale_after = Duration::seconds(self.interval_seconds as i64 * STALE_INTERVALS + STALE_GRACE_SECONDS);
+        let last_progress = self.last_success_at.unwrap_or(self.started_at);
+        now - last_progress <= stale_after
+    }
+
+    /// A monitor is degraded while it waits to be restarted and for the restart window
+    /// after a restart, even if it polls fine again
+    pub fn is_degraded(&self, now: DateTime<Utc>) -> bool {
+        let window = Duration::seconds(RESTART_WINDOW.as_secs() as i64);
+        self.restarting_at.is_some()
+            || self.last_restart_at.is_some_and(|restarted_at| now - restarted_at <= window)
+    }
+}
+
+/// Status of every running monitor, shared by the run loops and the HTTP server
+#[derive(Default)]
+pub struct StatusRegistry {
+    monitors: RwLock<BTreeMap<String, MonitorStatus>>,
+}
+
+impl StatusRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a monitor that is starting, resetting any previous status unless the
+    /// supervisor is restarting it
+    pub fn monitor_started(&self, exchange: &str, interval_seconds: u64) {
+        let mut monitors = self.monitors.write().unwrap();
+        if let Some(status) = monitors.get_mut(exchange).filter(|status| status.restarting_at.is_some()) {
+            let now = Utc::now();
+            status.interval_seconds = interval_seconds;
+            status.started_at = now;
+            status.consecutive_failures = 0;
+            status.running = true;
+            status.last_restart_at = Some(now);
+            status.restarting_at = None;
+            return;
+        }
+
+        let status = MonitorStatus {
+            exchange: exchange.to_string(),
+            interval_seconds,
+            started_at: Utc::now(),
+            last_poll_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            last_error_at: None,
+            proxy: None,
+            running: true,
+            restarts: 0,
+            last_restart_at: None,
+            restarting_at: None,
+            healthy: true,
+            degraded: false,
+        };
+        monitors.insert(exchange.to_string(), status);
+    }
+
+    /// Record a successful poll
+    pub fn poll_succeeded(&self, exchange: &str, proxy: Option<String>) {
+        self.update(exchange, |status| {
+            let now = Utc::now();
+            status.last_poll_at = Some(now);
+            status.last_success_at = Some(now);
+            status.consecutive_failures = 0;
+            status.proxy = proxy;
+        });
+    }
+
+    /// Record a failed poll
+    pub fn poll_failed(&self, exchange: &str, error: &str, proxy: Option<String>) {
+        self.update(exchange, |status| {
+            let now = Utc::now();
+            status.last_poll_at = Some(now);
+            status.consecutive_failures += 1;
+            status.last_error = Some(error.to_string());
+            status.last_error_at = Some(now);
+            status.proxy = proxy;
+        });
+    }
+
+    /// Record that a monitor's run loop exited
+    pub fn monitor_stopped(&self, exchange: &str, error: Option<&str>) {
+        self.update(exchange, |status| {
+            status.running = false;
+            if let Some(error) = error {
+                status.last_error = Some(error.to_string());
+                status.last_error_at = Some(Utc::now());
+            }
+        });
+    }
+
+    /// Record that the supervisor restarts a crashed monitor after `delay`
+    pub fn monitor_restarting(&self, exchange: &str, delay: std::time::Duration) {
+        self.update(exchange, |status| {
+            status.restarts += 1;
+            status.restarting_at = Some(Utc::now() + Duration::seconds(delay.as_secs() as i64));
+        });
+    }
+
+    /// Record a new polling interval of a running monitor
+    pub fn interval_changed(&self, exchange: &str, interval_seconds: u64) {
+        self.update(exchange, |status| status.interval_seconds = interval_seconds);
+    }
+
+    /// Forget a monitor that was disabled
+    pub fn monitor_removed(&self, exchange: &str) {
+        self.monitors.write().unwrap().remove(exchange);
+    }
+
+    /// Status of all monitors, sorted by exchange, with `healthy` and `degraded` evaluated now
+    pub fn snapshot(&self) -> Vec<MonitorStatus> {
+        let now = Utc::now();
+        self.monitors.read().unwrap()
+            .values()
+            .cloned()
+            .map(|mut status| {
+                status.healthy = status.is_healthy(now);
+                status.degraded = status.is_degraded(now);
+                status
+            })
+            .collect()
+    }
+
+    fn update(&self, exchange: &str, f: impl FnOnce(&mut MonitorStatus)) {
+        if let Some(status) = self.monitors.write().unwrap().get_mut(exchange) {
+            f(status);
+        }
+    }
+}
+
+/// Spawn the consumer that keeps the registry up to date from the monitor events
+pub fn spawn_status_updater(bus: &EventBus, registry: Arc<StatusRegistry>) -> JoinHandle<()> {
+    events::spawn_consumer(bus, "status", move |event| {
+        match &event {
+            MonitorEvent::MonitorStarted { exchange, interval_seconds } => {
+                registry.monitor_started(exchange, *interval_seconds);
+            }
+            MonitorEvent::MonitorStopped { exchange, error } => {
+                registry.monitor_stopped(exchange, error.as_deref());
+            }
+            MonitorEvent::MonitorRestarting { exchange, delay } => {
+                registry.monitor_restarting(exchange, *delay);
+            }
+            MonitorEvent::MonitorRemoved { exchange } => {
+                registry.monitor_removed(exchange);
+            }
+            MonitorEvent::IntervalChanged { exchange, interval_seconds } => {
+                registry.interval_changed(exchange, *interval_seconds);
+            }
+            MonitorEvent::AnnouncementsFetched { exchange, .. } => {
+                registry.poll_succeeded(exchange, utils::proxy_in_use(exchange));
+            }
+            MonitorEvent::FetchFailed { exchange, error, .. } => {
+                registry.poll_failed(exchange, error, utils::proxy_in_use(exchange));
+            }
+            MonitorEvent::NewAnnouncement(_) | MonitorEvent::AnnouncementUpdated(_) => {}
+        }
+        async {}
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(registry: &StatusRegistry, exchange: &str) -> MonitorStatus {
+        registry.snapshot().into_iter().find(|s| s.exchange == exchange).unwrap()
+    }
+
+    #[test]
+    fn becomes_unhealthy_after_repeated_failures_or_going_stale() {
+        let registry = StatusRegistry::new();
+        registry.monitor_started("Binance", 60);
+        assert!(status(&registry, "Binance").healthy);
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            registry.poll_failed("Binance", "timed out", None);
+        }
+        let failing = status(&registry, "Binance");
+        assert!(!failing.healthy);
+        assert_eq!(failing.last_error.as_deref(), Some("timed out"));
+
+        registry.poll_succeeded("Binance", Some("http://proxy:8080".to_string()));
+        let recovered = status(&registry, "Binance");
+        assert!(recovered.healthy);
+        assert_eq!(recovered.consecutive_failures, 0);
+        assert_eq!(recovered.proxy.as_deref(), Some("http://proxy:8080"));
+
+        // 3 intervals of 60s plus the grace period without a successful poll
+        let now = recovered.last_success_at.unwrap();
+        assert!(recovered.is_healthy(now + Duration::seconds(300)));
+        assert!(!recovered.is_healthy(now + Duration::seconds(301)));
+
+        registry.monitor_stopped("Binance", Some("panicked"));
+        assert!(!status(&registry, "Binance").healthy);
+    }
+
+    #[test]
+    fn is_degraded_while_restarting_and_for_the_restart_window() {
+        let registry = StatusRegistry::new();
+        registry.monitor_started("OKX", 60);
+        registry.poll_succeeded("OKX", None);
+        registry.monitor_stopped("OKX", Some("connection reset"));
+        registry.monitor_restarting("OKX", std::time::Duration::from_secs(5));
+
+        let restarting = status(&registry, "OKX");
+        assert!(restarting.degraded);
+        assert_eq!(restarting.restarts, 1);
+
+        registry.monitor_started("OKX", 60);
+        let restarted = status(&registry, "OKX");
+        assert!(restarted.running && restarted.degraded);
+        assert_eq!(restarted.restarts, 1);
+        // The history of the monitor is kept across a restart
+        assert!(restarted.last_success_at.is_some());
+
+        let window = Duration::seconds(RESTART_WINDOW.as_secs() as i64);
+        let restarted_at = restarted.last_restart_at.unwrap();
+        assert!(!restarted.is_degraded(restarted_at + window + Duration::seconds(1)));
+
+        // Starting a monitor that is not being restarted starts from scratch
+        registry.monitor_started("OKX", 120);
+        let fresh = status(&registry, "OKX");
+        assert_eq!((fresh.restarts, fresh.interval_seconds), (0, 120));
+        assert!(fresh.last_success_at.is_none());
+
+        registry.monitor_removed("OKX");
+        assert!(registry.snapshot().is_empty());
+    }
+}
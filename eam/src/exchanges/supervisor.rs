@@ -0,0 +1,448 @@
+use anyhow::anyhow;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::{Id, JoinSet};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::events::MonitorEvent;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorContext};
+
+/// Delay before restarting a crashed monitor, doubled for every earlier restart
+/// within the window
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Longest delay before a restart
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Restarts allowed within `RESTART_WINDOW` before a monitor is given up on
+const MAX_RESTARTS: usize = 5;
+
+/// Period over which restarts are counted. A monitor restarted within this period is
+/// reported as degraded.
+pub const RESTART_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Run a monitor until `shutdown` is cancelled, restarting its run loop whenever it
+/// panics or returns an error.
+///
+/// Restarts are delayed with exponential backoff, and a monitor that crashes more than
+/// `MAX_RESTARTS` times within `RESTART_WINDOW` stays stopped. Returns the exchange
+/// name once the monitor has stopped for good.
+pub async fn supervise(
+    monitor: Arc<dyn ExchangeMonitor>,
+    interval_seconds: watch::Receiver<u64>,
+    mut context: MonitorContext,
+    shutdown: CancellationToken,
+) -> String {
+    let exchange_name = monitor.exchange_name().to_string();
+    let mut restarts: VecDeque<Instant> = VecDeque::new();
+
+    loop {
+        // A separate task turns a panic of the run loop into an error
+        let run = tokio::spawn({
+            let monitor = monitor.clone();
+            let context = context.clone();
+            let interval_seconds = interval_seconds.clone();
+            let shutdown = shutdown.clone();
+            async move { monitor.run(interval_seconds, context, shutdown).await }
+        });
+
+        let error = match run.await {
+            Ok(Ok(())) if shutdown.is_cancelled() => None,
+            Ok(Ok(())) => Some(anyhow!("Run loop returned without being stopped")),
+            Ok(Err(e)) => Some(e),
+            Err(e) => Some(anyhow!("Run loop panicked: {}", e)),
+        };
+
+        if let Some(e) = &error {
+            tracing::error!(
+                exchange = exchange_name,
+                error = %e,
+                "Monitor for {} crashed: {:#}",
+                exchange_name, e
+            );
+        }
+        context.events.publish(MonitorEvent::MonitorStopped {
+            exchange: exchange_name.clone(),
+            error: error.as_ref().map(|e| format!("{:#}", e)),
+        });
+
+        if error.is_none() || shutdown.is_cancelled() {
+            return exchange_name;
+        }
+
+        let now = Instant::now();
+        while restarts.front().is_some_and(|&at| now.duration_since(at) > RESTART_WINDOW) {
+            restarts.pop_front();
+        }
+
+        if restarts.len() >= MAX_RESTARTS {
+            tracing::error!(
+                exchange = exchange_name,
+                restarts = restarts.len(),
+                "Monitor for {} crashed after {} restarts within {} minutes, giving up",
+                exchange_name, restarts.len(), RESTART_WINDOW.as_secs() / 60
+            );
+            return exchange_name;
+        }
+
+        let delay = backoff(restarts.len());
+        tracing::warn!(
+            exchange = exchange_name,
+            delay_seconds = delay.as_secs(),
+            "Restarting monitor for {} in {} seconds",
+            exchange_name, delay.as_secs()
+        );
+        context.events.publish(MonitorEvent::MonitorRestarting {
+            exchange: exchange_name.clone(),
+            delay,
+        });
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.cancelled() => return exchange_name,
+        }
+        restarts.push_back(Instant::now());
+
+        // Everything online before the crash was already reported, a second baseline
+        // would swallow what was published since
+        context.baseline_first_poll = false;
+    }
+}
+
+/// The supervised monitors of the process, started and stopped as the configuration
+/// changes
+pub struct MonitorSet {
+    tasks: JoinSet<String>,
+    /// Supervisors by exchange, including the ones asked to stop that have not yet
+    monitors: HashMap<String, Supervised>,
+    /// Monitors to start once the previous instance for their exchange has stopped,
+    /// with their interval and whether their first poll is a baseline
+    pending: HashMap<String, (Arc<dyn ExchangeMonitor>, u64, bool)>,
+    context: MonitorContext,
+    shutdown: CancellationToken,
+}
+
+struct Supervised {
+    task: Id,
+    interval_seconds: watch::Sender<u64>,
+    stop: CancellationToken,
+}
+
+impl MonitorSet {
+    pub fn new(context: MonitorContext, shutdown: CancellationToken) -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            monitors: HashMap::new(),
+            pending: HashMap::new(),
+            context,
+            shutdown,
+        }
+    }
+
+    /// Whether no supervisor is left, not even one that is stopping
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Whether the exchange has a supervised monitor that was not asked to stop
+    pub fn is_running(&self, exchange: &str) -> bool {
+        self.monitors.get(exchange).is_some_and(|m| !m.stop.is_cancelled())
+    }
+
+    /// Start supervising a monitor, with a baseline first poll if the context asks for
+    /// one. If the exchange's previous monitor is still stopping, the new one starts
+    /// once it has stopped.
+    pub fn start(&mut self, monitor: Arc<dyn ExchangeMonitor>, interval_seconds: u64) {
+        let baseline_first_poll = self.context.baseline_first_poll;
+        self.start_with(monitor, interval_seconds, baseline_first_poll);
+    }
+
+    /// Start supervising a monitor like `start`, but without a baseline poll: a monitor
+    /// replaced or brought back while the process runs reports what was published
+    /// since its previous instance instead of recording it as seen.
+    pub fn restart(&mut self, monitor: Arc<dyn ExchangeMonitor>, interval_seconds: u64) {
+        self.start_with(monitor, interval_seconds, false);
+    }
+
+    fn start_with(&mut self, monitor: Arc<dyn ExchangeMonitor>, interval_seconds: u64, baseline_first_poll: bool) {
+        let exchange = monitor.exchange_name().to_string();
+        if self.monitors.contains_key(&exchange) {
+            self.stop(&exchange);
+            self.pending.insert(exchange, (monitor, interval_seconds, baseline_first_poll));
+            return;
+        }
+
+        let context = MonitorContext {
+            baseline_first_poll,
+            ..self.context.clone()
+        };
+        let (sender, receiver) = watch::channel(interval_seconds);
+        let stop = self.shutdown.child_token();
+        let task = self.tasks.spawn(supervise(monitor, receiver, context, stop.clone()));
+        self.monitors.insert(exchange, Supervised {
+            task: task.id(),
+            interval_seconds: sender,
+            stop,
+        });
+    }
+
+    /// Ask an exchange's monitor to stop after its fetch in flight, if any
+    pub fn stop(&mut self, exchange: &str) {
+        self.pending.remove(exchange);
+        if let Some(monitor) = self.monitors.get(exchange) {
+            monitor.stop.cancel();
+        }
+    }
+
+    /// Change the polling interval of a running monitor
+    pub fn set_interval(&mut self, exchange: &str, interval_seconds: u64) {
+        if let Some(monitor) = self.monitors.get(exchange) {
+            monitor.interval_seconds.send_replace(interval_seconds);
+        }
+    }
+
+    /// Wait for the next supervisor to finish and clean up after it: start the monitor
+    /// waiting for it, or report a monitor that was given up on. Returns immediately
+    /// if the set is empty.
+    pub async fn join_next(&mut self) {
+        let task = match self.tasks.join_next_with_id().await {
+            Some(Ok((task, _))) => task,
+            Some(Err(e)) => e.id(),
+            None => return,
+        };
+
+        let Some(exchange) = self.monitors.iter()
+            .find(|(_, monitor)| monitor.task == task)
+            .map(|(exchange, _)| exchange.clone())
+        else {
+            return;
+        };
+        let monitor = self.monitors.remove(&exchange).unwrap();
+
+        if self.shutdown.is_cancelled() {
+            return;
+        }
+        if !monitor.stop.is_cancelled() {
+            tracing::error!(exchange = exchange, "No longer monitoring {}", exchange);
+            return;
+        }
+
+        match self.pending.remove(&exchange) {
+            Some((next, interval_seconds, baseline_first_poll)) => {
+                self.start_with(next, interval_seconds, baseline_first_poll);
+            }
+            None => {
+                tracing::info!(exchange = exchange, "Stopped monitoring {}", exchange);
+                self.context.events.publish(MonitorEvent::MonitorRemoved { exchange });
+            }
+        }
+    }
+
+    /// Wait for every supervisor to finish
+    pub async fn join_all(&mut self) {
+        while !self.tasks.is_empty() {
+            self.join_next().await;
+        }
+    }
+}
+
+/// Delay before the restart that follows `recent_restarts` restarts within the window
+fn backoff(recent_restarts: usize) -> Duration {
+    let factor = 1u32 << recent_restarts.min(16);
+    INITIAL_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use tokio::sync::broadcast;
+
+    use crate::events::EventBus;
+    use crate::models::announcement::Announcement;
+    use crate::store::seen::SeenStore;
+
+    struct NoSeenStore;
+
+    #[async_trait]
+    impl SeenStore for NoSeenStore {
+        async fn mark_seen(&self, _announcement: &Announcement) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    /// A monitor whose first `crashes` runs fail, half of them by panicking, and
+    /// whose later runs wait to be stopped. Records whether each run started with
+    /// a baseline poll.
+    struct CrashingMonitor {
+        crashes: usize,
+        baselines: Mutex<Vec<bool>>,
+    }
+
+    #[async_trait]
+    impl ExchangeMonitor for CrashingMonitor {
+        fn exchange_name(&self) -> &str {
+            "Test"
+        }
+
+        fn endpoint(&self) -> &str {
+            "http://localhost"
+        }
+
+        async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+            Ok(Vec::new())
+        }
+
+        async fn run(
+            &self,
+            _interval_seconds: watch::Receiver<u64>,
+            context: MonitorContext,
+            shutdown: CancellationToken,
+        ) -> Result<()> {
+            let run = {
+                let mut baselines = self.baselines.lock().unwrap();
+                baselines.push(context.baseline_first_poll);
+                baselines.len() - 1
+            };
+            if run < self.crashes {
+                if run.is_multiple_of(2) {
+                    anyhow::bail!("connection reset");
+                }
+                panic!("unexpected response");
+            }
+            shutdown.cancelled().await;
+            Ok(())
+        }
+    }
+
+    /// A monitor that always finds the same announcement, polled by the default `run`
+    struct FixedMonitor;
+
+    #[async_trait]
+    impl ExchangeMonitor for FixedMonitor {
+        fn exchange_name(&self) -> &str {
+            "Test"
+        }
+
+        fn endpoint(&self) -> &str {
+            "http://localhost"
+        }
+
+        async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
+            Ok(vec![Announcement::sample("1", "Binance Will List Example (EXM)")])
+        }
+    }
+
+    /// Wait for the next poll and return whether it was a baseline, along with the
+    /// number of announcements published before it
+    async fn next_poll(receiver: &mut broadcast::Receiver<MonitorEvent>) -> (bool, usize) {
+        let mut published = 0;
+        loop {
+            match receiver.recv().await.unwrap() {
+                MonitorEvent::NewAnnouncement(_) => published += 1,
+                MonitorEvent::AnnouncementsFetched { baseline, .. } => return (baseline, published),
+                _ => {}
+            }
+        }
+    }
+
+    /// Supervise a `CrashingMonitor` started with a baseline poll, and return whether
+    /// each of its runs started with one, along with the restart delays
+    async fn supervise_crashing(crashes: usize, shutdown: CancellationToken) -> (Vec<bool>, Vec<Duration>) {
+        let monitor = Arc::new(CrashingMonitor { crashes, baselines: Mutex::default() });
+        let events = EventBus::new(64);
+        let mut receiver = events.subscribe();
+        let context = MonitorContext {
+            seen_store: Arc::new(NoSeenStore),
+            baseline_first_poll: true,
+            events,
+        };
+        let (_sender, interval_seconds) = watch::channel(60);
+
+        supervise(monitor.clone(), interval_seconds, context, shutdown).await;
+
+        let mut delays = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let MonitorEvent::MonitorRestarting { delay, .. } = event {
+                delays.push(delay);
+            }
+        }
+        let baselines = monitor.baselines.lock().unwrap().clone();
+        (baselines, delays)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff(0), Duration::from_secs(5));
+        assert_eq!(backoff(1), Duration::from_secs(10));
+        assert_eq!(backoff(5), Duration::from_secs(160));
+        assert_eq!(backoff(6), MAX_BACKOFF);
+        assert_eq!(backoff(usize::MAX), MAX_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_too_many_restarts() {
+        let (baselines, delays) = supervise_crashing(usize::MAX, CancellationToken::new()).await;
+
+        assert_eq!(baselines.len(), MAX_RESTARTS + 1);
+        assert!(baselines[0]);
+        assert!(baselines[1..].iter().all(|&baseline| !baseline));
+        assert_eq!(delays, [5, 10, 20, 40, 80].map(Duration::from_secs));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restarts_until_the_monitor_runs_again() {
+        let shutdown = CancellationToken::new();
+        let stop = shutdown.clone();
+        tokio::spawn(async move {
+            // Well after the second restart, which comes 5 + 10 seconds after the crashes
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            stop.cancel();
+        });
+
+        let (baselines, delays) = supervise_crashing(2, shutdown).await;
+        assert_eq!(baselines, [true, false, false]);
+        assert_eq!(delays, [5, 10].map(Duration::from_secs));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_restart_a_stopped_monitor() {
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+
+        let (baselines, delays) = supervise_crashing(0, shutdown).await;
+        assert_eq!(baselines, [true]);
+        assert!(delays.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restarted_monitor_skips_the_baseline_poll() {
+        let events = EventBus::new(64);
+        let mut receiver = events.subscribe();
+        let context = MonitorContext {
+            seen_store: Arc::new(NoSeenStore),
+            baseline_first_poll: true,
+            events,
+        };
+        let shutdown = CancellationToken::new();
+        let mut monitors = MonitorSet::new(context, shutdown.clone());
+
+        monitors.start(Arc::new(FixedMonitor), 60);
+        assert_eq!(next_poll(&mut receiver).await, (true, 0));
+
+        // Replaced as on a reload, the new monitor starts once the old one has stopped
+        monitors.restart(Arc::new(FixedMonitor), 60);
+        monitors.join_next().await;
+        assert!(monitors.is_running("Test"));
+        assert_eq!(next_poll(&mut receiver).await, (false, 1));
+
+        shutdown.cancel();
+        monitors.join_all().await;
+        assert!(monitors.is_empty());
+    }
+}
@@ -1,15 +1,21 @@
-use crate::exchanges::monitor::ExchangeMonitor;
+use crate::exchanges::monitor::{ExchangeMonitor, MonitorOptions};
 use crate::models::announcement::Announcement;
+use crate::metrics::diagnostics::unparsed_date;
+use crate::transport::{HttpTransport, MonitorHttp};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc, TimeZone};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use reqwest::Client;
+use std::sync::Arc;
 
 /// Upbit announcement monitor
 pub struct UpbitMonitor {
     client: Client,
+    transport: Arc<dyn HttpTransport>,
     base_url: String,
+    endpoint: String,
+    page_size: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,25 +30,27 @@ struct UpbitAnnouncement {
     title: String,
     #[serde(rename = "created_at")]
     created_at: String,
-    #[serde(rename = "view_count")]
-    view_count: u64,
 }
 
 impl UpbitMonitor {
-    /// Create a new Upbit monitor
-    pub fn new() -> Self {
+    /// Create a new Upbit monitor with the request settings from the config file
+    pub fn with_options(options: &MonitorOptions, http: MonitorHttp) -> Self {
+        let base_url = options.base_url_or("https://api-manager.upbit.com");
         Self {
-            client: Client::new(),
-            base_url: "https://api-manager.upbit.com/api/v1/notices".to_string(),
+            client: http.client_or(Client::new),
+            transport: http.transport,
+            endpoint: format!("{}/api/v1/notices", base_url),
+            base_url,
+            page_size: options.page_size_or(20),
         }
     }
     
     /// Fetch the content for a specific announcement
     async fn fetch_announcement_content(&self, id: u64) -> Result<String> {
-        let url = format!("https://api-manager.upbit.com/api/v1/notices/{}", id);
+        let url = format!("{}/api/v1/notices/{}", self.base_url, id);
         
-        let response = self.client.get(&url)
-            .send()
+        let request = self.client.get(&url);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Upbit announcement details")?;
         
@@ -54,11 +62,7 @@ impl UpbitMonitor {
         
         #[derive(Debug, Deserialize)]
         struct UpbitAnnouncementContent {
-            id: u64,
-            title: String,
             content: String,
-            #[serde(rename = "created_at")]
-            created_at: String,
         }
         
         let detail: UpbitAnnouncementDetail = response.json()
@@ -79,18 +83,23 @@ impl ExchangeMonitor for UpbitMonitor {
         "Upbit"
     }
     
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+    
     async fn fetch_announcements(&self) -> Result<Vec<Announcement>> {
         // Request parameters for the Upbit API
+        let page_size = self.page_size.to_string();
         let params = [
             ("page", "1"),
-            ("per_page", "20"),
+            ("per_page", page_size.as_str()),
             ("thread_name", "general"), // General announcements
         ];
         
         // Make the API request
-        let response = self.client.get(&self.base_url)
-            .query(&params)
-            .send()
+        let request = self.client.get(&self.endpoint)
+            .query(&params);
+        let response = self.transport.send(request)
             .await
             .context("Failed to request Upbit announcements")?;
         
@@ -98,6 +107,7 @@ impl ExchangeMonitor for UpbitMonitor {
         let upbit_response: UpbitAnnouncementResponse = response.json()
             .await
             .context("Failed to parse Upbit announcement response")?;
+        let listed_at = Utc::now();
         
         // Check if the request was successful
         if !upbit_response.success {
@@ -109,15 +119,19 @@ impl ExchangeMonitor for UpbitMonitor {
         for upbit_announcement in upbit_response.data {
             // Parse publish time - Upbit typically uses ISO 8601 format
             let published_at = DateTime::parse_from_rfc3339(&upbit_announcement.created_at)
-                .unwrap_or_else(|_| Utc::now().into())
+                .unwrap_or_else(|_| unparsed_date(&upbit_announcement.created_at).into())
                 .with_timezone(&Utc);
             
             // Construct the URL for the announcement
             let url = format!("https://upbit.com/service_center/notice?id={}", upbit_announcement.id);
             
             // Fetch the full content
+            let mut content_fetched_at = None;
             let content = match self.fetch_announcement_content(upbit_announcement.id).await {
-                Ok(content) => content,
+                Ok(content) => {
+                    content_fetched_at = Some(Utc::now());
+                    content
+                }
                 Err(e) => {
                     tracing::warn!(
                         exchange = self.exchange_name(),
@@ -138,6 +152,8 @@ impl ExchangeMonitor for UpbitMonitor {
                 self.exchange_name().to_string(),
                 published_at,
             );
+            announcement.listed_at = Some(listed_at);
+            announcement.content_fetched_at = content_fetched_at;
             
             // Analyze if this is a new listing
             announcement.analyze_for_new_listing();
@@ -147,3 +163,37 @@ impl ExchangeMonitor for UpbitMonitor {
         Ok(announcements)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::metrics::diagnostics;
+    use crate::models::announcement::AnnouncementKind;
+    use crate::transport::replay::fixtures;
+
+    #[tokio::test]
+    async fn parses_recorded_notices_and_details() {
+        let monitor = UpbitMonitor::with_options(&MonitorOptions::default(), MonitorHttp::with_transport(fixtures("upbit")));
+        let (announcements, diagnostics) = diagnostics::collect(monitor.fetch_announcements()).await;
+        let announcements = announcements.unwrap();
+
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(diagnostics.date_parse_failures, 0);
+
+        let listing = &announcements[0];
+        assert_eq!(listing.id, "4402");
+        assert_eq!(listing.url, "https://upbit.com/service_center/notice?id=4402");
+        assert_eq!(listing.published_at, Utc.with_ymd_and_hms(2024, 6, 20, 7, 10, 0).unwrap());
+        assert_eq!(listing.kind, AnnouncementKind::SpotListing);
+        assert_eq!(listing.token_symbols, ["ZRO"]);
+        assert!(listing.content.contains("지오(ZRO)"));
+        assert!(listing.content_fetched_at.is_some());
+
+        // A failed detail request leaves the announcement without content
+        let maintenance = &announcements[1];
+        assert_eq!(maintenance.content, "");
+        assert!(maintenance.content_fetched_at.is_none());
+        assert_eq!(maintenance.kind, AnnouncementKind::Maintenance);
+    }
+}
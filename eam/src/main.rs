@@ -1,63 +1,143 @@
 use anyhow::{Result, Context};
 use futures::future;
-use tokio::task::JoinSet;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use std::sync::Arc;
+use std::time::Duration;
 
 mod models;
 mod exchanges;
 mod config;
 mod utils;
+mod store;
+mod notifications;
+mod classifier;
+mod commands;
+mod metrics;
+mod events;
+mod server;
+mod transport;
 
+use crate::classifier::RuleSet;
 use crate::config::Config;
-use crate::exchanges::monitor::ExchangeMonitor;
-use crate::exchanges::{
-    binance::BinanceMonitor,
-    okx::OkxMonitor,
-    bybit::BybitMonitor,
-    bitmex::BitmexMonitor,
-    gateio::GateioMonitor,
-    kraken::KrakenMonitor,
-    coinbase::CoinbaseMonitor,
-    upbit::UpbitMonitor,
-    bitget::BitgetMonitor,
-    htx::HtxMonitor,
-    mexc::MexcMonitor,
-    kucoin::KucoinMonitor,
-};
-
-/// Create and return all available exchange monitors
-fn create_exchange_monitors() -> Vec<Box<dyn ExchangeMonitor>> {
-    vec![
-        Box::new(BinanceMonitor::new()),
-        Box::new(OkxMonitor::new()),
-        Box::new(BybitMonitor::new()),
-        Box::new(BitmexMonitor::new()),
-        Box::new(GateioMonitor::new()),
-        Box::new(KrakenMonitor::new()),
-        Box::new(CoinbaseMonitor::new()),
-        Box::new(UpbitMonitor::new()),
-        Box::new(BitgetMonitor::new()),
-        Box::new(HtxMonitor::new()),
-        Box::new(MexcMonitor::new()),
-        Box::new(KucoinMonitor::new()),
-    ]
-}
+use crate::events::EventBus;
+use crate::exchanges::monitor::MonitorContext;
+use crate::exchanges::status::StatusRegistry;
+use crate::exchanges::supervisor::MonitorSet;
+use crate::metrics::latency::LatencyTracker;
+use crate::notifications::SinkRegistry;
+use crate::store::archive::AnnouncementArchive;
+use crate::store::seen::{FileSeenStore, SeenStore};
+
+/// How often the classification rules file is checked for changes
+const RULES_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the config file is checked for changes
+const CONFIG_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Monitor events buffered for each consumer before it starts missing some
+const EVENT_BUS_CAPACITY: usize = 1024;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load configuration
     let config = Config::new().context("Failed to load configuration")?;
     
-    // Initialize logging
-    utils::init_logger();
+    // Initialize logging, subcommands keep stdout for their output
+    utils::init_logger(&config.log_level, config.command.is_some());
+    for warning in &config.warnings {
+        tracing::warn!("{}", warning);
+    }
+    
+    // Load custom classification rules, the built-in rules are used otherwise
+    if let Some(rules_path) = &config.rules_path {
+        classifier::load_and_install(rules_path)
+            .await
+            .context("Failed to load classification rules")?;
+    }
+    
+    // Run a subcommand instead of the monitors if one was given
+    if let Some(command) = &config.command {
+        return commands::run(command, &config).await;
+    }
     
     tracing::info!("Starting Exchange Announcement Monitoring...");
     
-    // Create exchange monitors
-    let all_monitors = create_exchange_monitors();
+    // Bind the HTTP server first so a port conflict fails before anything else runs
+    let listener = match config.http_addr {
+        Some(http_addr) => Some(server::bind(http_addr).await?),
+        None => None,
+    };
+    
+    // Reload the classification rules whenever the file changes
+    let mut rules_reloader = config.rules_path.clone()
+        .map(|rules_path| classifier::spawn_rules_reloader(rules_path, RULES_RELOAD_INTERVAL));
+    
+    // Open the seen-announcement store shared by all monitors
+    let seen_store = Arc::new(
+        FileSeenStore::open(&config.seen_store_path)
+            .await
+            .context("Failed to open seen-announcement store")?,
+    );
+    
+    // Open the archive that keeps every fetched announcement
+    let archive = Arc::new(
+        AnnouncementArchive::open(&config.archive_path)
+            .await
+            .context("Failed to open announcement archive")?,
+    );
+    
+    // Set up the notification sinks that receive new listings
+    let sinks = Arc::new(
+        SinkRegistry::from_config(&config)
+            .context("Failed to set up notification sinks")?,
+    );
+    
+    // Everything that consumes the monitors' results subscribes to the event bus
+    let events = EventBus::new(EVENT_BUS_CAPACITY);
+    let status = Arc::new(StatusRegistry::new());
+    let consumers = vec![
+        exchanges::status::spawn_status_updater(&events, status.clone()),
+        metrics::spawn_recorder(&events, Arc::new(LatencyTracker::new())),
+        store::archive::spawn_archiver(&events, archive.clone()),
+        notifications::spawn_dispatcher(&events, sinks.clone()),
+    ];
     
-    // Create a JoinSet to manage all the monitoring tasks
-    let mut tasks = JoinSet::new();
+    // Cancelled on SIGINT or SIGTERM, stops the monitors and the HTTP server
+    let shutdown = CancellationToken::new();
+    spawn_signal_handler(shutdown.clone());
+    
+    // Create exchange monitors, one client serves all monitors outside the proxy pool
+    let http_client = utils::create_shared_client();
+    let all_monitors = exchanges::create_monitors(&config, &http_client);
+    for (exchange, settings) in &config.exchange_settings {
+        utils::set_proxy_policy(exchange, settings.proxy);
+    }
+    
+    let server = listener.map(|listener| {
+        let state = server::AppState {
+            started_at: chrono::Utc::now(),
+            status: status.clone(),
+            archive: archive.clone(),
+            exchanges: Arc::new(all_monitors.iter().map(|m| m.exchange_name().to_string()).collect()),
+            events: events.clone(),
+            shutdown: shutdown.clone(),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = server::serve(listener, state).await {
+                tracing::error!(error = %e, "HTTP server stopped: {:#}", e);
+            }
+        })
+    });
+    
+    let context = MonitorContext {
+        seen_store: seen_store.clone(),
+        baseline_first_poll: config.baseline_first_poll,
+        events: events.clone(),
+    };
+    
+    // Every monitor runs under a supervisor that restarts it if it crashes
+    let mut monitors = MonitorSet::new(context, shutdown.clone());
     
     // Start monitoring for each enabled exchange
     for monitor in all_monitors {
@@ -78,45 +158,338 @@ async fn main() -> Result<()> {
             exchange_name, interval
         );
         
-        // Move the monitor into a thread-safe reference
-        let monitor = Arc::new(monitor);
-        
-        // Spawn a task to run the monitor
-        tasks.spawn(async move {
-            let result = monitor.run(interval).await;
-            if let Err(e) = result {
-                tracing::error!(
-                    exchange = exchange_name,
-                    error = %e,
-                    "Monitor for {} exited with error: {}",
-                    exchange_name, e
-                );
-            }
-            exchange_name
-        });
+        monitors.start(Arc::from(monitor), interval);
     }
     
-    // Wait for tasks to complete (which should not happen in normal operation)
-    while let Some(result) = tasks.join_next().await {
-        match result {
-            Ok(exchange_name) => {
-                tracing::error!(
-                    exchange = exchange_name,
-                    "Monitor for {} has unexpectedly terminated",
-                    exchange_name
-                );
+    // Apply changes of the config file, or of anything else on SIGHUP
+    let mut reload_requests = config::reload::spawn_reload_watcher(config.config_path.clone(), CONFIG_RELOAD_INTERVAL);
+    let mut config = config;
+    
+    // Run until a shutdown signal. Without a config file to re-enable them, there is
+    // nothing left to do once every monitor has stopped.
+    loop {
+        tokio::select! {
+            _ = monitors.join_next(), if !monitors.is_empty() => {
+                if monitors.is_empty() && config.config_path.is_none() {
+                    tracing::info!("All monitors have terminated");
+                    break;
+                }
             }
-            Err(e) => {
-                tracing::error!(
-                    error = %e,
-                    "A monitor task panicked: {}",
-                    e
-                );
+            Some(reason) = reload_requests.recv() => {
+                config = reload_config(config, reason, &mut monitors, &sinks, &http_client, &mut rules_reloader).await;
             }
+            _ = shutdown.cancelled() => break,
         }
     }
+    shutdown.cancel();
+    
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout);
+    let drained = drain(monitors, server, events, consumers, seen_store.as_ref(), &archive, shutdown_timeout).await;
+    if !drained {
+        tracing::error!(
+            timeout_seconds = config.shutdown_timeout,
+            "Shutdown did not complete within {} seconds, exiting anyway",
+            config.shutdown_timeout
+        );
+        std::process::exit(1);
+    }
     
-    tracing::info!("All monitors have terminated. Exiting.");
+    tracing::info!("Shutdown complete");
     
     Ok(())
 }
+
+/// Let everything finish its work once `shutdown` was cancelled: polls in flight
+/// publish their results, the consumers handle every queued event, which delivers
+/// the pending notifications, and the stores are flushed. Returns false if that did
+/// not complete within `timeout`.
+async fn drain(
+    mut monitors: MonitorSet,
+    server: Option<JoinHandle<()>>,
+    events: EventBus,
+    consumers: Vec<JoinHandle<()>>,
+    seen_store: &dyn SeenStore,
+    archive: &AnnouncementArchive,
+    timeout: Duration,
+) -> bool {
+    let graceful_shutdown = async move {
+        monitors.join_all().await;
+        if let Some(server) = server {
+            let _ = server.await;
+        }
+        
+        // Consumers exit once the bus is closed and they have handled every queued event
+        drop(monitors);
+        drop(events);
+        future::join_all(consumers).await;
+        
+        if let Err(e) = seen_store.flush().await {
+            tracing::error!(error = %e, "Failed to flush seen-announcement store: {:#}", e);
+        }
+        if let Err(e) = archive.flush().await {
+            tracing::error!(error = %e, "Failed to flush announcement archive: {:#}", e);
+        }
+    };
+    
+    tokio::time::timeout(timeout, graceful_shutdown).await.is_ok()
+}
+
+/// Reload the configuration and apply it to the running process: start and stop
+/// monitors, retune polling intervals and swap the sink routing, proxy policies and
+/// classification rules. Returns the configuration in effect afterwards.
+///
+/// An invalid configuration or rules file is logged and the previous configuration
+/// stays in effect. Settings that cannot change at runtime, such as the sinks
+/// themselves or the HTTP address, come from the command line and stay the same.
+async fn reload_config(
+    current: Config,
+    reason: &str,
+    monitors: &mut MonitorSet,
+    sinks: &SinkRegistry,
+    http_client: &reqwest::Client,
+    rules_reloader: &mut Option<JoinHandle<()>>,
+) -> Config {
+    tracing::info!(reason = reason, "Reloading configuration ({})", reason);
+    
+    let config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!(
+                error = %format!("{:#}", e),
+                "Failed to reload configuration, keeping the previous configuration"
+            );
+            return current;
+        }
+    };
+    
+    if config.rules_path != current.rules_path {
+        let installed = match &config.rules_path {
+            Some(rules_path) => classifier::load_and_install(rules_path).await,
+            None => RuleSet::builtin().map(classifier::install_rules),
+        };
+        if let Err(e) = installed {
+            tracing::error!(
+                error = %format!("{:#}", e),
+                "Failed to load classification rules, keeping the previous configuration"
+            );
+            return current;
+        }
+        
+        if let Some(reloader) = rules_reloader.take() {
+            reloader.abort();
+        }
+        *rules_reloader = config.rules_path.clone()
+            .map(|rules_path| classifier::spawn_rules_reloader(rules_path, RULES_RELOAD_INTERVAL));
+    }
+    
+    for warning in &config.warnings {
+        tracing::warn!("{}", warning);
+    }
+    
+    let changes = current.changes(&config);
+    if changes.is_empty() {
+        tracing::info!("Configuration reloaded, nothing changed");
+    }
+    for change in &changes {
+        tracing::info!(change = change, "Configuration changed: {}", change);
+    }
+    
+    for &(exchange, _) in exchanges::SUPPORTED_EXCHANGES {
+        let policy = config.exchange_settings.get(exchange).map(|settings| settings.proxy).unwrap_or_default();
+        utils::set_proxy_policy(exchange, policy);
+    }
+    sinks.apply_settings(&config);
+    
+    for monitor in exchanges::create_monitors(&config, http_client) {
+        let exchange_name = monitor.exchange_name().to_string();
+        let interval = config.get_polling_interval(&exchange_name);
+        
+        if !config.should_monitor_exchange(&exchange_name) {
+            monitors.stop(&exchange_name);
+        } else if !monitors.is_running(&exchange_name) {
+            // Also brings back a monitor that was given up on
+            monitors.restart(Arc::from(monitor), interval);
+        } else if config.monitor_options(&exchange_name) != current.monitor_options(&exchange_name) {
+            // Request settings are fixed when a monitor is created, so it is replaced
+            monitors.restart(Arc::from(monitor), interval);
+        } else if interval != current.get_polling_interval(&exchange_name) {
+            monitors.set_interval(&exchange_name, interval);
+        }
+    }
+    
+    config
+}
+
+/// Cancel `shutdown` on the first SIGINT or SIGTERM and exit immediately on the second
+fn spawn_signal_handler(shutdown: CancellationToken) {
+    tokio::spawn(async move {
+        let signal = shutdown_signal().await;
+        tracing::info!(signal = signal, "Received {}, shutting down", signal);
+        shutdown.cancel();
+        
+        let signal = shutdown_signal().await;
+        tracing::warn!(signal = signal, "Received {} again, exiting immediately", signal);
+        std::process::exit(130);
+    });
+}
+
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM and return its name
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to listen for SIGTERM, only SIGINT stops the monitors");
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    use crate::events::MonitorEvent;
+    use crate::models::announcement::{Announcement, AnnouncementKind};
+    use crate::models::update::AnnouncementUpdate;
+    use crate::notifications::NotificationSink;
+
+    /// A sink that takes `delay` to deliver each notification
+    struct SlowSink {
+        delay: Duration,
+        delivered: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NotificationSink for SlowSink {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn notify(&self, announcement: &Announcement) -> Result<()> {
+            tokio::time::sleep(self.delay).await;
+            self.delivered.lock().unwrap().push(announcement.id.clone());
+            Ok(())
+        }
+
+        async fn notify_update(&self, _update: &AnnouncementUpdate) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct FlushedSeenStore {
+        flushed: AtomicBool,
+    }
+
+    #[async_trait]
+    impl SeenStore for FlushedSeenStore {
+        async fn mark_seen(&self, _announcement: &Announcement) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn flush(&self) -> Result<()> {
+            self.flushed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    struct Shutdown {
+        sink: Arc<SlowSink>,
+        seen_store: Arc<FlushedSeenStore>,
+        archive: Arc<AnnouncementArchive>,
+        archive_path: std::path::PathBuf,
+        drained: bool,
+    }
+
+    /// Cancel a running process right after a poll found a new listing, while the
+    /// sink takes `delivery` to deliver it, and drain it within `timeout`
+    async fn shut_down(name: &str, delivery: Duration, timeout: Duration) -> Shutdown {
+        let dir = std::env::temp_dir().join(format!("eam-drain-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("announcements.db");
+        let archive = Arc::new(AnnouncementArchive::open(&archive_path).await.unwrap());
+
+        let sink = Arc::new(SlowSink { delay: delivery, delivered: Mutex::default() });
+        let mut sinks = SinkRegistry::new();
+        sinks.register(sink.clone());
+
+        let events = EventBus::new(EVENT_BUS_CAPACITY);
+        let consumers = vec![
+            store::archive::spawn_archiver(&events, archive.clone()),
+            notifications::spawn_dispatcher(&events, Arc::new(sinks)),
+        ];
+        let seen_store = Arc::new(FlushedSeenStore::default());
+        let context = MonitorContext {
+            seen_store: seen_store.clone(),
+            baseline_first_poll: false,
+            events: events.clone(),
+        };
+        let shutdown = CancellationToken::new();
+        let monitors = MonitorSet::new(context, shutdown.clone());
+
+        let mut listing = Announcement::sample("1", "Binance Will List Example (EXM)");
+        listing.set_kind(AnnouncementKind::SpotListing, 0.9);
+        events.publish(MonitorEvent::NewAnnouncement(Arc::new(listing.clone())));
+        events.publish(MonitorEvent::AnnouncementsFetched {
+            exchange: "Binance".to_string(),
+            announcements: vec![listing].into(),
+            new_announcements: 1,
+            baseline: false,
+            duration: Duration::from_millis(100),
+        });
+        shutdown.cancel();
+
+        let drained = drain(monitors, None, events, consumers, seen_store.as_ref(), &archive, timeout).await;
+        Shutdown {
+            sink,
+            seen_store,
+            archive,
+            archive_path,
+            drained,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_delivers_pending_notifications_and_flushes_the_stores() {
+        let shutdown = shut_down("delivered", Duration::from_secs(20), Duration::from_secs(30)).await;
+
+        assert!(shutdown.drained);
+        assert_eq!(*shutdown.sink.delivered.lock().unwrap(), ["1"]);
+        assert!(shutdown.seen_store.flushed.load(Ordering::SeqCst));
+        assert!(shutdown.archive.get("Binance", "1").await.unwrap().is_some());
+
+        // The checkpoint moved the write-ahead log into the database file
+        let wal = shutdown.archive_path.with_extension("db-wal");
+        assert_eq!(std::fs::metadata(wal).map(|metadata| metadata.len()).unwrap_or(0), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_gives_up_after_the_shutdown_timeout() {
+        let started = tokio::time::Instant::now();
+        let shutdown = shut_down("timeout", Duration::from_secs(3600), Duration::from_secs(30)).await;
+
+        assert!(!shutdown.drained);
+        assert_eq!(started.elapsed(), Duration::from_secs(30));
+        assert!(shutdown.sink.delivered.lock().unwrap().is_empty());
+        assert!(!shutdown.seen_store.flushed.load(Ordering::SeqCst));
+    }
+}
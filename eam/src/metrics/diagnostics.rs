@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+tokio::task_local! {
+    /// Diagnostics of the fetch running in the current task, only set by `collect`
+    static DIAGNOSTICS: Arc<Mutex<FetchDiagnostics>>;
+}
+
+/// What the shared HTTP helpers and the monitors observed during one fetch, for the
+/// `doctor` command
+#[derive(Debug, Clone, Default)]
+pub struct FetchDiagnostics {
+    /// Status codes of the responses received by `retry_request`, in order
+    pub statuses: Vec<u16>,
+    /// Content type of the last response parsed by `extract_response_data`
+    pub content_type: Option<String>,
+    /// How the last response parsed by `extract_response_data` was read
+    pub body_format: Option<BodyFormat>,
+    /// Number of dates that failed to parse and were replaced by the current time
+    pub date_parse_failures: usize,
+    /// First date that failed to parse
+    pub unparsed_date: Option<String>,
+    /// Proxy of the latest client created, passwords masked
+    pub proxy: Option<String>,
+}
+
+/// How a response body was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    Json,
+    /// The body was not the expected JSON and the monitor's HTML extractor was used
+    HtmlFallback,
+}
+
+/// Run a future and return what was observed while it ran
+pub async fn collect<F: Future>(future: F) -> (F::Output, FetchDiagnostics) {
+    let diagnostics = Arc::new(Mutex::new(FetchDiagnostics::default()));
+    let output = DIAGNOSTICS.scope(diagnostics.clone(), future).await;
+    let diagnostics = diagnostics.lock().unwrap().clone();
+    (output, diagnostics)
+}
+
+/// Update the diagnostics of the current task, if they are being collected
+pub fn record(update: impl FnOnce(&mut FetchDiagnostics)) {
+    let _ = DIAGNOSTICS.try_with(|diagnostics| update(&mut diagnostics.lock().unwrap()));
+}
+
+/// What was observed so far in the current task, None if not collecting
+pub fn current() -> Option<FetchDiagnostics> {
+    DIAGNOSTICS.try_with(|diagnostics| diagnostics.lock().unwrap().clone()).ok()
+}
+
+/// Timestamp used in place of a date that failed to parse: the current time. The
+/// failure is counted by the `doctor` command.
+pub fn unparsed_date(value: impl ToString) -> DateTime<Utc> {
+    record(|diagnostics| {
+        diagnostics.date_parse_failures += 1;
+        diagnostics.unparsed_date.get_or_insert_with(|| value.to_string());
+    });
+    Utc::now()
+}
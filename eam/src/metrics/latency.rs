@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Number of recent samples kept per exchange for the in-process percentiles
+const WINDOW_SIZE: usize = 500;
+
+/// Percentiles of a set of latencies, in seconds
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl LatencySummary {
+    /// Summarize latencies in seconds, None if there are none
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        Some(Self {
+            count: sorted.len(),
+            p50: percentile(&sorted, 0.50),
+            p90: percentile(&sorted, 0.90),
+            p99: percentile(&sorted, 0.99),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted samples, `p` between 0.0 and 1.0
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Latency between two times in seconds.
+///
+/// Exchanges sometimes date announcements slightly in the future, or the clocks
+/// disagree; such latencies count as zero rather than skewing the percentiles.
+pub fn latency_seconds(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    ((to - from).num_milliseconds() as f64 / 1000.0).max(0.0)
+}
+
+/// Detection latencies of recently reported announcements, per exchange.
+///
+/// Only a sliding window of recent samples is kept, the archive has the full history
+/// (see the `latency-report` subcommand).
+#[derive(Default)]
+pub struct LatencyTracker {
+    samples: Mutex<HashMap<String, VecDeque<f64>>>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the latency from publication to detection of one announcement
+    pub fn record(&self, exchange: &str, latency_seconds: f64) {
+        let mut samples = self.samples.lock().unwrap();
+        let window = samples.entry(exchange.to_string()).or_default();
+        if window.len() == WINDOW_SIZE {
+            window.pop_front();
+        }
+        window.push_back(latency_seconds);
+    }
+
+    /// Percentiles of the recent detection latencies of an exchange
+    pub fn summary(&self, exchange: &str) -> Option<LatencySummary> {
+        let samples = self.samples.lock().unwrap();
+        let window = samples.get(exchange)?;
+        LatencySummary::from_samples(&window.iter().copied().collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn uses_nearest_rank_percentiles() {
+        let samples: Vec<f64> = (1..=100).rev().map(f64::from).collect();
+        let summary = LatencySummary::from_samples(&samples).unwrap();
+
+        assert_eq!(summary.count, 100);
+        assert_eq!((summary.p50, summary.p90, summary.p99, summary.max), (50.0, 90.0, 99.0, 100.0));
+
+        let single = LatencySummary::from_samples(&[3.5]).unwrap();
+        assert_eq!((single.p50, single.p99, single.max), (3.5, 3.5, 3.5));
+        assert!(LatencySummary::from_samples(&[]).is_none());
+        assert_eq!(percentile(&[1.0, 2.0, 3.0], 0.0), 1.0);
+    }
+
+    #[test]
+    fn clamps_negative_latencies_to_zero() {
+        let published = Utc.with_ymd_and_hms(2024, 5, 16, 6, 0, 0).unwrap();
+        let detected = published + chrono::Duration::milliseconds(2500);
+
+        assert_eq!(latency_seconds(published, detected), 2.5);
+        assert_eq!(latency_seconds(detected, published), 0.0);
+    }
+
+    #[test]
+    fn keeps_a_sliding_window_per_exchange() {
+        let tracker = LatencyTracker::new();
+        for latency in 0..WINDOW_SIZE + 10 {
+            tracker.record("Binance", latency as f64);
+        }
+        tracker.record("OKX", 1.0);
+
+        let binance = tracker.summary("Binance").unwrap();
+        assert_eq!(binance.count, WINDOW_SIZE);
+        // The ten oldest samples were dropped
+        assert_eq!(binance.max, (WINDOW_SIZE + 9) as f64);
+        assert_eq!(binance.p50, 259.0);
+        assert_eq!(tracker.summary("OKX").unwrap().count, 1);
+        assert!(tracker.summary("Bybit").is_none());
+    }
+}
@@ -0,0 +1,305 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec_with_registry, register_int_counter_vec_with_registry,
+    register_int_gauge_vec_with_registry, Encoder, HistogramVec, IntCounterVec, IntGaugeVec,
+    Registry, TextEncoder,
+};
+use chrono::Utc;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+use crate::events::{self, EventBus, MonitorEvent};
+
+use self::latency::{latency_seconds, LatencyTracker};
+
+pub mod diagnostics;
+pub mod latency;
+
+/// Label used for requests made outside of a monitor's fetch, e.g. by a subcommand
+const UNKNOWN_EXCHANGE: &str = "unknown";
+
+tokio::task_local! {
+    /// Exchange whose announcements the current task is fetching.
+    ///
+    /// The HTTP helpers in `utils` are shared by all monitors and do not know which
+    /// exchange they work for; the run loop sets this around each fetch so that their
+    /// metrics can still be labelled per exchange.
+    static CURRENT_EXCHANGE: String;
+}
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::new();
+
+    pub static ref POLLS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_polls_total",
+        "Number of polls of the announcement endpoints",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref FETCH_ERRORS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_fetch_errors_total",
+        "Number of failed polls by error class",
+        &["exchange", "class"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref HTTP_RESPONSES: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_http_responses_total",
+        "HTTP status codes received by retried requests",
+        &["exchange", "status"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref HTTP_RETRIES: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_http_retries_total",
+        "Number of retried HTTP requests",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref HTML_FALLBACKS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_html_fallbacks_total",
+        "Number of responses parsed with the HTML fallback instead of JSON",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref ANNOUNCEMENTS_FETCHED: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_announcements_fetched_total",
+        "Number of announcements returned by the exchanges, including already seen ones",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref NEW_LISTINGS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_new_listings_total",
+        "Number of new listings reported",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref ANNOUNCEMENT_UPDATES: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_announcement_updates_total",
+        "Number of archived announcements whose title or content was edited",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref MONITOR_RESTARTS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "eam_monitor_restarts_total",
+        "Number of times a crashed monitor was restarted",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref LAST_SUCCESS: IntGaugeVec = register_int_gauge_vec_with_registry!(
+        "eam_last_success_timestamp_seconds",
+        "Unix time of the last successful poll",
+        &["exchange"],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref POLL_DURATION: HistogramVec = register_histogram_vec_with_registry!(
+        "eam_poll_duration_seconds",
+        "Time taken to fetch and analyze the announcements of one poll",
+        &["exchange"],
+        vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0],
+        REGISTRY
+    ).unwrap();
+
+    pub static ref DETECTION_LATENCY: HistogramVec = register_histogram_vec_with_registry!(
+        "eam_detection_latency_seconds",
+        "Time from publication to detection of new announcements",
+        &["exchange"],
+        vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0, 21600.0, 86400.0],
+        REGISTRY
+    ).unwrap();
+}
+
+/// Run a future with `exchange` as the exchange label of the HTTP helper metrics
+pub async fn with_exchange<F: Future>(exchange: &str, future: F) -> F::Output {
+    CURRENT_EXCHANGE.scope(exchange.to_string(), future).await
+}
+
+/// Exchange label of the current task, see `with_exchange`
+pub fn current_exchange() -> String {
+    CURRENT_EXCHANGE
+        .try_with(|exchange| exchange.clone())
+        .unwrap_or_else(|_| UNKNOWN_EXCHANGE.to_string())
+}
+
+/// Coarse class of a fetch error, for the `class` label of `eam_fetch_errors_total`.
+///
+/// Most monitors wrap errors into new messages, so the error chain is checked for
+/// typed errors first and the message is used as a fallback.
+pub fn error_class(error: &anyhow::Error) -> &'static str {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return if e.is_timeout() {
+                "timeout"
+            } else if e.is_connect() {
+                "connect"
+            } else if e.is_decode() {
+                "parse"
+            } else if e.is_status() {
+                "http_status"
+            } else {
+                "request"
+            };
+        }
+        if cause.downcast_ref::<serde_json::Error>().is_some() {
+            return "parse";
+        }
+    }
+
+    let message = format!("{:#}", error).to_lowercase();
+    if ["403", "429", "forbidden", "cloudfront", "html", "restricted"].iter().any(|m| message.contains(m)) {
+        "blocked"
+    } else if message.contains("timed out") || message.contains("timeout") {
+        "timeout"
+    } else if message.contains("parse") || message.contains("decode") {
+        "parse"
+    } else if message.contains("failed to request") {
+        "request"
+    } else {
+        "other"
+    }
+}
+
+/// Spawn the consumer that updates the poll metrics and detection latencies from
+/// the monitor events
+pub fn spawn_recorder(bus: &EventBus, latency: Arc<LatencyTracker>) -> JoinHandle<()> {
+    events::spawn_consumer(bus, "metrics", move |event| {
+        record_event(&event, &latency);
+        async {}
+    })
+}
+
+fn record_event(event: &MonitorEvent, latency: &LatencyTracker) {
+    let exchange = event.exchange();
+    match event {
+        MonitorEvent::AnnouncementsFetched { announcements, new_announcements, baseline, duration, .. } => {
+            POLLS.with_label_values(&[exchange]).inc();
+            POLL_DURATION.with_label_values(&[exchange]).observe(duration.as_secs_f64());
+            ANNOUNCEMENTS_FETCHED.with_label_values(&[exchange]).inc_by(announcements.len() as u64);
+            LAST_SUCCESS.with_label_values(&[exchange]).set(Utc::now().timestamp());
+
+            if *new_announcements > 0 && !baseline {
+                if let Some(summary) = latency.summary(exchange) {
+                    tracing::info!(
+                        exchange = exchange,
+                        samples = summary.count,
+                        p50_seconds = summary.p50,
+                        p90_seconds = summary.p90,
+                        p99_seconds = summary.p99,
+                        "Detection latency for {}: p50 {:.1}s, p90 {:.1}s, p99 {:.1}s over {} announcements",
+                        exchange, summary.p50, summary.p90, summary.p99, summary.count
+                    );
+                }
+            }
+        }
+        MonitorEvent::FetchFailed { class, duration, .. } => {
+            POLLS.with_label_values(&[exchange]).inc();
+            POLL_DURATION.with_label_values(&[exchange]).observe(duration.as_secs_f64());
+            FETCH_ERRORS.with_label_values(&[exchange, class]).inc();
+        }
+        MonitorEvent::NewAnnouncement(announcement) => {
+            if let Some(detected_at) = announcement.detected_at {
+                let seconds = latency_seconds(announcement.published_at, detected_at);
+                latency.record(exchange, seconds);
+                DETECTION_LATENCY.with_label_values(&[exchange]).observe(seconds);
+            }
+            if announcement.is_new_listing {
+                NEW_LISTINGS.with_label_values(&[exchange]).inc();
+            }
+        }
+        MonitorEvent::AnnouncementUpdated(_) => {
+            ANNOUNCEMENT_UPDATES.with_label_values(&[exchange]).inc();
+        }
+        MonitorEvent::MonitorRestarting { .. } => {
+            MONITOR_RESTARTS.with_label_values(&[exchange]).inc();
+        }
+        MonitorEvent::MonitorStarted { .. }
+        | MonitorEvent::MonitorStopped { .. }
+        | MonitorEvent::MonitorRemoved { .. }
+        | MonitorEvent::IntervalChanged { .. } => {}
+    }
+}
+
+/// Render all metrics in the Prometheus text format
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    // Encoding into a Vec only fails on invalid metric families, which we never create
+    let _ = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer);
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::models::announcement::{Announcement, AnnouncementKind};
+
+    #[test]
+    fn classifies_errors_by_type_then_message() {
+        let parse = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let wrapped = anyhow::Error::new(parse).context("Failed to parse Bybit response");
+
+        assert_eq!(error_class(&wrapped), "parse");
+        assert_eq!(error_class(&anyhow::anyhow!("Binance returned status 403 Forbidden")), "blocked");
+        assert_eq!(error_class(&anyhow::anyhow!("operation timed out")), "timeout");
+        assert_eq!(error_class(&anyhow::anyhow!("Failed to request OKX announcements")), "request");
+        assert_eq!(error_class(&anyhow::anyhow!("No announcements found")), "other");
+    }
+
+    #[tokio::test]
+    async fn labels_metrics_with_the_current_exchange() {
+        assert_eq!(current_exchange(), UNKNOWN_EXCHANGE);
+        assert_eq!(with_exchange("Kraken", async { current_exchange() }).await, "Kraken");
+    }
+
+    #[test]
+    fn records_polls_and_new_listings() {
+        // A label of its own, the registry is shared with every other test
+        let exchange = "metrics-test";
+        let latency = LatencyTracker::new();
+
+        let mut announcement = Announcement::new(
+            "1".to_string(),
+            "Will List NOT".to_string(),
+            String::new(),
+            String::new(),
+            exchange.to_string(),
+            Utc::now() - chrono::Duration::seconds(30),
+        );
+        announcement.set_kind(AnnouncementKind::SpotListing, 0.9);
+        announcement.detected_at = Some(Utc::now());
+
+        record_event(&MonitorEvent::NewAnnouncement(Arc::new(announcement.clone())), &latency);
+        record_event(&MonitorEvent::AnnouncementsFetched {
+            exchange: exchange.to_string(),
+            announcements: Arc::from(vec![announcement]),
+            new_announcements: 1,
+            baseline: false,
+            duration: Duration::from_millis(300),
+        }, &latency);
+        record_event(&MonitorEvent::FetchFailed {
+            exchange: exchange.to_string(),
+            error: "timed out".to_string(),
+            class: "timeout",
+            duration: Duration::from_secs(10),
+        }, &latency);
+
+        assert_eq!(POLLS.with_label_values(&[exchange]).get(), 2);
+        assert_eq!(NEW_LISTINGS.with_label_values(&[exchange]).get(), 1);
+        assert_eq!(FETCH_ERRORS.with_label_values(&[exchange, "timeout"]).get(), 1);
+        assert_eq!(latency.summary(exchange).unwrap().count, 1);
+
+        let rendered = render();
+        assert!(rendered.contains("eam_polls_total{exchange=\"metrics-test\"} 2"));
+        assert!(rendered.contains("eam_detection_latency_seconds_count{exchange=\"metrics-test\"} 1"));
+    }
+}
@@ -1,5 +1,127 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::classifier::{self, schedule, symbols};
+
+/// AnnouncementKind describes what an announcement is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementKind {
+    /// A new token listed for spot trading
+    SpotListing,
+    /// A new futures / perpetual contract
+    FuturesListing,
+    /// A new margin trading pair
+    MarginListing,
+    /// A token or trading pair being removed
+    Delisting,
+    /// A new Launchpool (stake to farm a new token)
+    Launchpool,
+    /// A new Launchpad (token sale)
+    Launchpad,
+    /// An airdrop or token distribution
+    Airdrop,
+    /// Wallet or system maintenance, deposit/withdrawal suspensions
+    Maintenance,
+    /// Anything else
+    Other,
+}
+
+impl AnnouncementKind {
+    /// Whether this kind is a new listing of any market type
+    pub fn is_listing(&self) -> bool {
+        matches!(
+            self,
+            AnnouncementKind::SpotListing | AnnouncementKind::FuturesListing | AnnouncementKind::MarginListing
+        )
+    }
+
+    /// The snake_case name used in serialized output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnouncementKind::SpotListing => "spot_listing",
+            AnnouncementKind::FuturesListing => "futures_listing",
+            AnnouncementKind::MarginListing => "margin_listing",
+            AnnouncementKind::Delisting => "delisting",
+            AnnouncementKind::Launchpool => "launchpool",
+            AnnouncementKind::Launchpad => "launchpad",
+            AnnouncementKind::Airdrop => "airdrop",
+            AnnouncementKind::Maintenance => "maintenance",
+            AnnouncementKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for AnnouncementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AnnouncementKind {
+    type Err = anyhow::Error;
+
+    /// Parse the snake_case name used in serialized output
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s.trim().to_lowercase().as_str() {
+            "spot_listing" => AnnouncementKind::SpotListing,
+            "futures_listing" => AnnouncementKind::FuturesListing,
+            "margin_listing" => AnnouncementKind::MarginListing,
+            "delisting" => AnnouncementKind::Delisting,
+            "launchpool" => AnnouncementKind::Launchpool,
+            "launchpad" => AnnouncementKind::Launchpad,
+            "airdrop" => AnnouncementKind::Airdrop,
+            "maintenance" => AnnouncementKind::Maintenance,
+            "other" => AnnouncementKind::Other,
+            _ => anyhow::bail!(
+                "Unknown announcement kind '{}', expected one of spot_listing, futures_listing, \
+                 margin_listing, delisting, launchpool, launchpad, airdrop, maintenance, other",
+                s
+            ),
+        };
+        Ok(kind)
+    }
+}
+
+/// Market a pair is listed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketType {
+    Spot,
+    /// Perpetual or delivery futures
+    Futures,
+    Margin,
+}
+
+impl MarketType {
+    /// The snake_case name used in serialized output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketType::Spot => "spot",
+            MarketType::Futures => "futures",
+            MarketType::Margin => "margin",
+        }
+    }
+}
+
+impl fmt::Display for MarketType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A trading pair mentioned in a listing or delisting announcement
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ListedPair {
+    /// The token being listed, e.g. "ABC"
+    pub base: String,
+    /// The quote currency, e.g. "USDT", if the announcement names one
+    pub quote: Option<String>,
+    /// Market the pair trades on
+    pub market_type: MarketType,
+}
 
 /// Announcement represents a standardized format for exchange announcements
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,10 +138,33 @@ pub struct Announcement {
     pub exchange: String,
     /// Publication date and time of the announcement
     pub published_at: DateTime<Utc>,
-    /// Whether this announcement is about a new token listing
+    /// What the announcement is about
+    pub kind: AnnouncementKind,
+    /// Confidence of the classification, between 0.0 and 1.0
+    pub confidence: f32,
+    /// Whether this announcement is about a new token listing.
+    /// Derived from `kind`, kept for consumers of the serialized format
     pub is_new_listing: bool,
-    /// If this is a new listing, the token symbol(s) mentioned
+    /// For listings and delistings, the token symbol(s) mentioned.
+    /// The base currencies of `listed_pairs`, without duplicates
     pub token_symbols: Vec<String>,
+    /// For listings and delistings, the trading pairs mentioned
+    pub listed_pairs: Vec<ListedPair>,
+    /// For listings, when deposits open, if the content says so
+    pub deposit_open_at: Option<DateTime<Utc>>,
+    /// For listings, when trading opens, if the content says so.
+    /// Not the same as `published_at`, listings are usually announced in advance
+    pub trading_open_at: Option<DateTime<Utc>>,
+    /// For listings, when withdrawals open, if the content says so
+    pub withdrawal_open_at: Option<DateTime<Utc>>,
+    /// When the monitor parsed the list response the announcement was in, before
+    /// requesting any details
+    pub listed_at: Option<DateTime<Utc>>,
+    /// For exchanges that serve the content from a separate detail endpoint, when
+    /// the content was retrieved
+    pub content_fetched_at: Option<DateTime<Utc>>,
+    /// When this process first reported the announcement as new
+    pub detected_at: Option<DateTime<Utc>>,
 }
 
 impl Announcement {
@@ -32,10 +177,6 @@ impl Announcement {
         exchange: String,
         published_at: DateTime<Utc>,
     ) -> Self {
-        // Default is not a new listing
-        let is_new_listing = false;
-        let token_symbols = Vec::new();
-
         Self {
             id,
             title,
@@ -43,53 +184,73 @@ impl Announcement {
             url,
             exchange,
             published_at,
-            is_new_listing,
-            token_symbols,
+            // Unclassified until analyzed
+            kind: AnnouncementKind::Other,
+            confidence: 0.0,
+            is_new_listing: false,
+            token_symbols: Vec::new(),
+            listed_pairs: Vec::new(),
+            deposit_open_at: None,
+            trading_open_at: None,
+            withdrawal_open_at: None,
+            listed_at: None,
+            content_fetched_at: None,
+            detected_at: None,
         }
     }
 
-    /// Analyzes the announcement content to determine if it's about a new token listing
-    /// and extracts relevant token symbols
+    /// Analyzes the announcement to determine its kind, and extracts relevant
+    /// token symbols for listings and delistings, and scheduled open times for listings
+    ///
+    /// Classification uses the active rule set of the classifier, see `classifier`.
     pub fn analyze_for_new_listing(&mut self) {
-        // Keywords that typically indicate a new token listing
-        let listing_keywords = [
-            "new listing", "listing", "new token", "new coin", "new cryptocurrency",
-            "will list", "now available", "deposits open", "trading pairs", "添加", "上线",
-        ];
-
-        // Check if title or content contains listing keywords
-        let title_lower = self.title.to_lowercase();
-        let content_lower = self.content.to_lowercase();
-        
-        self.is_new_listing = listing_keywords.iter().any(|keyword| {
-            title_lower.contains(keyword) || content_lower.contains(keyword)
-        });
-
-        // If this is a listing announcement, try to extract token symbols
-        if self.is_new_listing {
-            // This is a simplified approach - in reality you would use more sophisticated
-            // NLP or pattern matching techniques to extract token symbols
-            let mut symbols = Vec::new();
-            
-            // Look for patterns like "(BTC)" or "[ETH]" in the title and content
-            let symbol_pattern = regex::Regex::new(r"[\(\[]([\w]{2,10})[\)\]]").unwrap();
-            
-            for cap in symbol_pattern.captures_iter(&self.title) {
-                if let Some(symbol) = cap.get(1) {
-                    symbols.push(symbol.as_str().to_uppercase());
-                }
-            }
-            
-            for cap in symbol_pattern.captures_iter(&self.content) {
-                if let Some(symbol) = cap.get(1) {
-                    let symbol = symbol.as_str().to_uppercase();
-                    if !symbols.contains(&symbol) {
-                        symbols.push(symbol);
-                    }
-                }
+        let classification = classifier::active_rules().classify(&self.exchange, &self.title, &self.content);
+        self.set_kind(classification.kind, classification.confidence);
+    }
+
+    /// Set the kind of the announcement, keeping the derived fields in sync
+    pub fn set_kind(&mut self, kind: AnnouncementKind, confidence: f32) {
+        self.kind = kind;
+        self.confidence = confidence.clamp(0.0, 1.0);
+        self.is_new_listing = kind.is_listing();
+
+        self.listed_pairs = if kind.is_listing() || kind == AnnouncementKind::Delisting {
+            symbols::extract_pairs(&classifier::active_rules(), &self.title, &self.content, kind)
+        } else {
+            Vec::new()
+        };
+
+        self.token_symbols = Vec::new();
+        for pair in &self.listed_pairs {
+            if !self.token_symbols.contains(&pair.base) {
+                self.token_symbols.push(pair.base.clone());
             }
-            
-            self.token_symbols = symbols.into_iter().map(String::from).collect();
         }
+
+        let schedule = if kind.is_listing() {
+            schedule::extract_schedule(&self.content, schedule::default_offset(&self.exchange))
+        } else {
+            schedule::Schedule::default()
+        };
+        self.deposit_open_at = schedule.deposit_open_at;
+        self.trading_open_at = schedule.trading_open_at;
+        self.withdrawal_open_at = schedule.withdrawal_open_at;
+    }
+}
+
+#[cfg(test)]
+impl Announcement {
+    /// Binance announcement published at a fixed time, the starting point of tests
+    pub fn sample(id: &str, title: &str) -> Self {
+        use chrono::TimeZone;
+
+        Self::new(
+            id.to_string(),
+            title.to_string(),
+            String::new(),
+            format!("https://www.binance.com/en/support/announcement/{}", id),
+            "Binance".to_string(),
+            Utc.with_ymd_and_hms(2024, 5, 16, 6, 0, 0).unwrap(),
+        )
     }
 }
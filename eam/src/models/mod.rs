@@ -1 +1,2 @@
 pub mod announcement;
+pub mod update;
@@ -0,0 +1,96 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::models::announcement::Announcement;
+
+lazy_static! {
+    /// Tags that end a line of text, turned into line breaks so that the diff is per line
+    static ref BLOCK_TAG: Regex = Regex::new(r"(?i)<br\s*/?>|</(p|div|li|tr|h[1-6]|table|ul|ol)\s*>").unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// An edit to an announcement that was archived before
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnouncementUpdate {
+    /// The announcement as it is now
+    pub announcement: Announcement,
+    pub previous_title: String,
+    pub previous_content: String,
+    /// Unified diff of the title and the text of the content, previous to current
+    pub diff: String,
+}
+
+impl AnnouncementUpdate {
+    /// Describe the change from `previous` to `announcement`
+    pub fn new(previous: &Announcement, announcement: Announcement) -> Self {
+        let diff = TextDiff::from_lines(&diff_text(previous), &diff_text(&announcement))
+            .unified_diff()
+            .context_radius(1)
+            .header("previous", "current")
+            .to_string();
+
+        Self {
+            previous_title: previous.title.clone(),
+            previous_content: previous.content.clone(),
+            announcement,
+            diff,
+        }
+    }
+}
+
+/// Title and content as plain text with one line per paragraph, list item or row
+fn diff_text(announcement: &Announcement) -> String {
+    let with_breaks = BLOCK_TAG.replace_all(&announcement.content, "\n");
+    let without_tags = HTML_TAG.replace_all(&with_breaks, "");
+    let content = html_escape::decode_html_entities(&without_tags);
+
+    let mut text = format!("{}\n\n", announcement.title.trim());
+    for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement(title: &str, content: &str) -> Announcement {
+        let mut announcement = Announcement::sample("1", title);
+        announcement.content = content.to_string();
+        announcement
+    }
+
+    #[test]
+    fn diffs_the_text_of_html_content_per_line() {
+        let previous = announcement(
+            "Bybit Will List ZRO",
+            "<p>Deposits open at 08:00</p><p>Trading opens at 10:00 &amp; more</p><ul><li>ZRO/USDT</li></ul>",
+        );
+        let current = announcement(
+            "Bybit Will List ZRO",
+            "<p>Deposits open at 08:00</p><p>Trading opens at 12:00 &amp; more</p><ul><li>ZRO/USDT</li></ul>",
+        );
+
+        let update = AnnouncementUpdate::new(&previous, current);
+        assert_eq!(
+            update.diff,
+            "--- previous\n+++ current\n@@ -3,3 +3,3 @@\n Deposits open at 08:00\n\
+             -Trading opens at 10:00 & more\n+Trading opens at 12:00 & more\n ZRO/USDT\n"
+        );
+        assert_eq!(update.previous_content, previous.content);
+    }
+
+    #[test]
+    fn includes_title_changes() {
+        let update = AnnouncementUpdate::new(
+            &announcement("Bybit Will List ZRO", ""),
+            announcement("Bybit Will List ZRO (Updated)", ""),
+        );
+        assert!(update.diff.contains("-Bybit Will List ZRO\n+Bybit Will List ZRO (Updated)\n"));
+        assert_eq!(update.previous_title, "Bybit Will List ZRO");
+    }
+}
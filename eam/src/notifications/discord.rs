@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use std::time::Duration;
+
+use crate::models::announcement::Announcement;
+use crate::models::update::AnnouncementUpdate;
+use crate::notifications::{exchange_filter_accepts, post_json_with_rate_limit, truncate_chars, NotificationSink};
+
+/// Configuration of the Discord incoming-webhook sink
+#[derive(Debug, Clone)]
+pub struct DiscordSinkConfig {
+    /// Discord webhook URL
+    pub webhook_url: String,
+    /// Exchanges forwarded to Discord, all exchanges if empty
+    pub exchanges: Vec<String>,
+    /// Maximum number of delivery attempts
+    pub max_retries: usize,
+}
+
+/// Discord sink posting a rich embed for every new listing
+pub struct DiscordSink {
+    client: Client,
+    config: DiscordSinkConfig,
+}
+
+impl DiscordSink {
+    /// Create a new Discord sink
+    pub fn new(config: DiscordSinkConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create Discord HTTP client")?;
+
+        Ok(Self { client, config })
+    }
+}
+
+#[async_trait]
+impl NotificationSink for DiscordSink {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn accepts(&self, exchange: &str) -> bool {
+        exchange_filter_accepts(&self.config.exchanges, exchange)
+    }
+
+    async fn notify(&self, announcement: &Announcement) -> Result<()> {
+        let payload = build_payload(announcement);
+        post_json_with_rate_limit(&self.client, &self.config.webhook_url, &payload, self.config.max_retries)
+            .await
+            .context("Failed to post Discord webhook")
+    }
+
+    async fn notify_update(&self, update: &AnnouncementUpdate) -> Result<()> {
+        let payload = build_update_payload(update);
+        post_json_with_rate_limit(&self.client, &self.config.webhook_url, &payload, self.config.max_retries)
+            .await
+            .context("Failed to post Discord webhook")
+    }
+}
+
+/// Build the webhook payload with a single embed.
+///
+/// Embed limits from the Discord API (256 characters for titles, 1024 for field
+/// values) are enforced here, since Discord rejects the whole message otherwise.
+fn build_payload(announcement: &Announcement) -> serde_json::Value {
+    let symbols = if announcement.token_symbols.is_empty() {
+        "-".to_string()
+    } else {
+        announcement.token_symbols.join(", ")
+    };
+
+    // Discord renders <t:...> timestamps in the reader's local timezone
+    let published = format!("<t:{}:F>", announcement.published_at.timestamp());
+
+    let mut embed = serde_json::json!({
+        "title": truncate_chars(&announcement.title, 256),
+        "description": format!("New listing on **{}**", announcement.exchange),
+        "color": exchange_color(&announcement.exchange),
+        "fields": [
+            { "name": "Exchange", "value": truncate_chars(&announcement.exchange, 1024), "inline": true },
+            { "name": "Symbols", "value": truncate_chars(&symbols, 1024), "inline": true },
+            { "name": "Published", "value": published, "inline": true },
+        ],
+        "timestamp": announcement.published_at.to_rfc3339(),
+        "footer": { "text": "Exchange Announcement Monitoring" },
+    });
+
+    if let Some(trading_open_at) = announcement.trading_open_at {
+        if let Some(fields) = embed["fields"].as_array_mut() {
+            fields.push(serde_json::json!({
+                "name": "Trading opens",
+                "value": format!("<t:{}:F>", trading_open_at.timestamp()),
+                "inline": true,
+            }));
+        }
+    }
+
+    // Discord rejects embeds with an invalid URL, so only set it when we have one
+    if announcement.url.starts_with("http") {
+        embed["url"] = serde_json::Value::String(announcement.url.clone());
+    }
+
+    serde_json::json!({ "embeds": [embed] })
+}
+
+/// Build the webhook payload for an edited announcement, the diff is shown as a code
+/// block in the embed description (limited to 4096 characters)
+fn build_update_payload(update: &AnnouncementUpdate) -> serde_json::Value {
+    let announcement = &update.announcement;
+    // A literal fence in the diff would end the code block early
+    let diff = truncate_chars(update.diff.trim_end(), 3800).replace("```", "'''");
+
+    let mut embed = serde_json::json!({
+        "title": truncate_chars(&announcement.title, 256),
+        "description": format!("Announcement updated on **{}**\n```diff\n{}\n```", announcement.exchange, diff),
+        "color": exchange_color(&announcement.exchange),
+        "timestamp": announcement.published_at.to_rfc3339(),
+        "footer": { "text": "Exchange Announcement Monitoring" },
+    });
+
+    if announcement.url.starts_with("http") {
+        embed["url"] = serde_json::Value::String(announcement.url.clone());
+    }
+
+    serde_json::json!({ "embeds": [embed] })
+}
+
+/// Embed color per exchange, roughly matching each exchange's brand color
+fn exchange_color(exchange: &str) -> u32 {
+    match exchange.to_lowercase().as_str() {
+        "binance" => 0xF0B90B,
+        "okx" => 0x121212,
+        "bybit" => 0xF7A600,
+        "bitmex" => 0xE3343A,
+        "gate.io" => 0x2354E6,
+        "kraken" => 0x5741D9,
+        "coinbase" => 0x0052FF,
+        "upbit" => 0x093687,
+        "bitget" => 0x00CED1,
+        "htx" => 0x008FDD,
+        "mexc" => 0x1972E2,
+        "kucoin" => 0x23AF91,
+        _ => 0x5865F2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn announcement() -> Announcement {
+        let mut announcement = Announcement::sample("195342", "Binance Will List Notcoin (NOT)");
+        announcement.token_symbols = vec!["NOT".to_string()];
+        announcement
+    }
+
+    #[test]
+    fn builds_an_embed_per_announcement() {
+        let mut announcement = announcement();
+        announcement.trading_open_at = Some(Utc.with_ymd_and_hms(2024, 5, 16, 12, 0, 0).unwrap());
+        let payload = build_payload(&announcement);
+
+        let embed = &payload["embeds"][0];
+        assert_eq!(embed["title"], "Binance Will List Notcoin (NOT)");
+        assert_eq!(embed["description"], "New listing on **Binance**");
+        assert_eq!(embed["color"], 0xF0B90B);
+        assert_eq!(embed["url"], "https://www.binance.com/en/support/announcement/195342");
+        assert_eq!(embed["fields"][1]["value"], "NOT");
+        assert_eq!(embed["fields"][2]["value"], "<t:1715839200:F>");
+        assert_eq!(embed["fields"][3]["name"], "Trading opens");
+        assert_eq!(embed["fields"][3]["value"], "<t:1715860800:F>");
+    }
+
+    #[test]
+    fn leaves_out_urls_discord_would_reject() {
+        let mut announcement = announcement();
+        announcement.url = "/en/support/announcement/195342".to_string();
+        let payload = build_payload(&announcement);
+        assert!(payload["embeds"][0].get("url").is_none());
+    }
+
+    #[test]
+    fn keeps_the_diff_inside_its_code_block() {
+        let update = AnnouncementUpdate {
+            announcement: announcement(),
+            previous_title: "Binance Will List Notcoin (NOT)".to_string(),
+            previous_content: String::new(),
+            diff: format!("+ ```rm -rf```\n{}", "x".repeat(5000)),
+        };
+        let payload = build_update_payload(&update);
+
+        let description = payload["embeds"][0]["description"].as_str().unwrap();
+        assert!(description.chars().count() <= 4096);
+        assert!(description.starts_with("Announcement updated on **Binance**\n```diff\n+ '''rm -rf'''\n"));
+        assert!(description.ends_with("…\n```"));
+    }
+}
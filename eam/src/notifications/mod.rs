@@ -0,0 +1,408 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::future;
+use reqwest::{header, Client, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::config::{Config, UpdateNotifications};
+use crate::events::{self, EventBus, MonitorEvent};
+use crate::metrics::latency::latency_seconds;
+use crate::models::announcement::Announcement;
+use crate::models::update::AnnouncementUpdate;
+
+pub mod discord;
+pub mod slack;
+pub mod telegram;
+pub mod webhook;
+
+use self::discord::DiscordSink;
+use self::slack::SlackSink;
+use self::telegram::TelegramSink;
+use self::webhook::WebhookSink;
+
+/// Names of the notification sinks, as returned by `NotificationSink::name`
+pub const SINK_NAMES: &[&str] = &["webhook", "telegram", "discord", "slack"];
+
+/// Longest rate limit delay a delivery waits for. A longer one fails the delivery
+/// instead of holding up every notification queued behind it.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// NotificationSink is a destination for new-listing alerts
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// Human readable name of the sink, used in logs
+    fn name(&self) -> &str;
+    
+    /// Check if the sink wants notifications for the given exchange
+    fn accepts(&self, _exchange: &str) -> bool {
+        true
+    }
+    
+    /// Deliver a notification about the given announcement
+    async fn notify(&self, announcement: &Announcement) -> Result<()>;
+    
+    /// Deliver a notification about an edit to an announcement, see `--notify-updates`
+    async fn notify_update(&self, update: &AnnouncementUpdate) -> Result<()>;
+}
+
+/// SinkRegistry holds every configured notification sink and fans out
+/// announcements to all of them
+pub struct SinkRegistry {
+    sinks: Vec<Arc<dyn NotificationSink>>,
+    /// Sinks an exchange is restricted to by the `sinks` key of its config section
+    routes: RwLock<HashMap<String, Vec<String>>>,
+    /// Which edited announcements are delivered, see `--notify-updates`
+    notify_updates: RwLock<UpdateNotifications>,
+}
+
+impl Default for SinkRegistry {
+    fn default() -> Self {
+        Self {
+            sinks: Vec::new(),
+            routes: RwLock::default(),
+            notify_updates: RwLock::new(UpdateNotifications::Off),
+        }
+    }
+}
+
+impl SinkRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Build the registry from the sinks enabled in the configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut registry = Self::new();
+        registry.apply_settings(config);
+        
+        if let Some(webhook_config) = &config.webhook {
+            let sink = WebhookSink::new(webhook_config.clone())
+                .context("Failed to create webhook notification sink")?;
+            registry.register(Arc::new(sink));
+        }
+        
+        if let Some(telegram_config) = &config.telegram {
+            let sink = TelegramSink::new(telegram_config.clone())
+                .context("Failed to create Telegram notification sink")?;
+            registry.register(Arc::new(sink));
+        }
+        
+        if let Some(discord_config) = &config.discord {
+            let sink = DiscordSink::new(discord_config.clone())
+                .context("Failed to create Discord notification sink")?;
+            registry.register(Arc::new(sink));
+        }
+        
+        if let Some(slack_config) = &config.slack {
+            let sink = SlackSink::new(slack_config.clone())
+                .context("Failed to create Slack notification sink")?;
+            registry.register(Arc::new(sink));
+        }
+        
+        Ok(registry)
+    }
+    
+    /// Add a sink to the registry
+    pub fn register(&mut self, sink: Arc<dyn NotificationSink>) {
+        tracing::info!(sink = sink.name(), "Registered notification sink {}", sink.name());
+        self.sinks.push(sink);
+    }
+    
+    /// Replace the per-exchange routes and the `notify_updates` policy with the ones
+    /// of `config`, e.g. after a configuration reload
+    pub fn apply_settings(&self, config: &Config) {
+        *self.routes.write().unwrap() = config.exchange_settings.iter()
+            .filter_map(|(exchange, settings)| Some((exchange.clone(), settings.sinks.clone()?)))
+            .collect();
+        *self.notify_updates.write().unwrap() = config.notify_updates;
+    }
+    
+    /// Check if a sink should receive notifications about an exchange
+    fn delivers(&self, sink: &dyn NotificationSink, exchange: &str) -> bool {
+        sink.accepts(exchange)
+            && self.routes.read().unwrap()
+                .get(exchange)
+                .is_none_or(|sinks| sinks.iter().any(|name| name == sink.name()))
+    }
+    
+    /// Check if an edit of the announcement should be delivered
+    fn delivers_update(&self, update: &AnnouncementUpdate) -> bool {
+        self.notify_updates.read().unwrap().accepts(&update.announcement)
+    }
+    
+    /// Send the announcement to every registered sink.
+    ///
+    /// Sinks are notified concurrently so that one slow destination does not delay
+    /// the others. A failing sink is logged and does not affect the rest.
+    pub async fn dispatch(&self, announcement: &Announcement) {
+        let deliveries = self.sinks.iter()
+            .filter(|sink| self.delivers(sink.as_ref(), &announcement.exchange))
+            .map(|sink| async move {
+                if let Err(e) = sink.notify(announcement).await {
+                    tracing::error!(
+                        sink = sink.name(),
+                        exchange = announcement.exchange,
+                        announcement_id = announcement.id,
+                        error = %e,
+                        "Failed to deliver notification via {}: {}",
+                        sink.name(), e
+                    );
+                }
+            });
+        
+        future::join_all(deliveries).await;
+    }
+    
+    /// Send an edit of an announcement to every registered sink, like `dispatch`
+    pub async fn dispatch_update(&self, update: &AnnouncementUpdate) {
+        let announcement = &update.announcement;
+        let deliveries = self.sinks.iter()
+            .filter(|sink| self.delivers(sink.as_ref(), &announcement.exchange))
+            .map(|sink| async move {
+                if let Err(e) = sink.notify_update(update).await {
+                    tracing::error!(
+                        sink = sink.name(),
+                        exchange = announcement.exchange,
+                        announcement_id = announcement.id,
+                        error = %e,
+                        "Failed to deliver update notification via {}: {}",
+                        sink.name(), e
+                    );
+                }
+            });
+        
+        future::join_all(deliveries).await;
+    }
+}
+
+/// Spawn the consumer that logs every new listing and sends it to the sinks, together
+/// with the edits selected by the registry's `notify_updates` policy
+pub fn spawn_dispatcher(bus: &EventBus, sinks: Arc<SinkRegistry>) -> JoinHandle<()> {
+    events::spawn_consumer(bus, "notifications", move |event| {
+        let sinks = sinks.clone();
+        async move {
+            match event {
+                MonitorEvent::NewAnnouncement(announcement) if announcement.is_new_listing => {
+                    log_new_listing(&announcement);
+                    sinks.dispatch(&announcement).await;
+                }
+                MonitorEvent::AnnouncementUpdated(update) if sinks.delivers_update(&update) => {
+                    sinks.dispatch_update(&update).await;
+                }
+                _ => {}
+            }
+        }
+    })
+}
+
+fn log_new_listing(announcement: &Announcement) {
+    let token_list = announcement.token_symbols.join(", ");
+    let latency = announcement.detected_at
+        .map(|detected_at| latency_seconds(announcement.published_at, detected_at))
+        .unwrap_or_default();
+    tracing::info!(
+        exchange = announcement.exchange,
+        title = announcement.title,
+        kind = %announcement.kind,
+        confidence = announcement.confidence,
+        tokens = token_list,
+        url = announcement.url,
+        latency_seconds = latency,
+        "New listing announcement: {}",
+        announcement.title
+    );
+}
+
+/// Check whether an exchange is part of a sink's exchange filter, an empty filter
+/// accepts every exchange
+pub fn exchange_filter_accepts(exchanges: &[String], exchange: &str) -> bool {
+    exchanges.is_empty() || exchanges.iter().any(|e| e.eq_ignore_ascii_case(exchange))
+}
+
+/// POST a JSON payload to an incoming webhook, honouring rate limit responses.
+///
+/// Chat webhooks such as Discord and Slack answer `429 Too Many Requests` with the
+/// exact time to wait, either in the `Retry-After` header or a `retry_after` field
+/// in the JSON body. Waiting for that long instead of a blind exponential backoff
+/// gets the message through as early as the service allows, unless it is longer
+/// than `MAX_RETRY_AFTER`.
+pub async fn post_json_with_rate_limit(
+    client: &Client,
+    url: &str,
+    payload: &serde_json::Value,
+    max_retries: usize,
+) -> Result<()> {
+    let mut delay = Duration::from_millis(500);
+    let mut wait = Duration::ZERO;
+    let mut last_error = None;
+    
+    for attempt in 0..max_retries {
+        // Wait between attempts, never after the last one
+        if attempt > 0 {
+            tokio::time::sleep(wait).await;
+        }
+        
+        let response = match client.post(url).json(payload).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("Webhook attempt {} failed: {}", attempt + 1, e);
+                last_error = Some(anyhow::anyhow!(e));
+                wait = delay;
+                delay *= 2;
+                continue;
+            }
+        };
+        
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        
+        let header_retry_after = response.headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok());
+        let body = response.text().await.unwrap_or_default();
+        let error = anyhow::anyhow!("Webhook returned status {}: {}", status, body);
+        
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let body_retry_after = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v.get("retry_after").and_then(|r| r.as_f64()));
+            let retry_after = header_retry_after
+                .or(body_retry_after)
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .map(Duration::from_secs_f64)
+                .unwrap_or(delay);
+            if retry_after > MAX_RETRY_AFTER {
+                return Err(error.context(format!(
+                    "Webhook rate limited for {:?}, longer than the {:?} a delivery waits",
+                    retry_after, MAX_RETRY_AFTER
+                )));
+            }
+            
+            tracing::warn!(
+                retry_after_ms = retry_after.as_millis() as u64,
+                "Webhook rate limit hit, retrying in {:?}",
+                retry_after
+            );
+            wait = retry_after;
+            last_error = Some(error);
+        } else if status.is_server_error() {
+            wait = delay;
+            delay *= 2;
+            last_error = Some(error);
+        } else {
+            return Err(error);
+        }
+    }
+    
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Failed after {} attempts", max_retries)))
+}
+
+/// Truncate text to at most `max_chars` characters, appending an ellipsis when cut
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode as AxumStatus, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn truncates_on_character_boundaries() {
+        assert_eq!(truncate_chars("abc", 3), "abc");
+        assert_eq!(truncate_chars("abcd", 3), "ab…");
+        assert_eq!(truncate_chars("상장안내", 3), "상장…");
+    }
+
+    #[test]
+    fn empty_exchange_filter_accepts_everything() {
+        assert!(exchange_filter_accepts(&[], "Binance"));
+        assert!(exchange_filter_accepts(&["binance".to_string()], "Binance"));
+        assert!(!exchange_filter_accepts(&["OKX".to_string()], "Binance"));
+    }
+
+    /// Serve a webhook that answers the first request with `first` and later ones with 204
+    async fn webhook(first: (AxumStatus, Vec<(&'static str, &'static str)>, &'static str)) -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/hook", post(move |State(calls): State<Arc<AtomicUsize>>| {
+                let first = first.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        let mut headers = axum::http::HeaderMap::new();
+                        for (name, value) in first.1 {
+                            headers.insert(name, value.parse().unwrap());
+                        }
+                        (first.0, headers, first.2)
+                    } else {
+                        (AxumStatus::NO_CONTENT, axum::http::HeaderMap::new(), "")
+                    }
+                }
+            }))
+            .with_state(calls.clone());
+        (format!("{}/hook", crate::utils::serve(router).await), calls)
+    }
+
+    #[tokio::test]
+    async fn retries_after_the_rate_limit_delay() {
+        let client = Client::new();
+        let payload = serde_json::json!({ "text": "hello" });
+
+        let (url, calls) = webhook((AxumStatus::TOO_MANY_REQUESTS, vec![("retry-after", "0")], "")).await;
+        post_json_with_rate_limit(&client, &url, &payload, 3).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let (url, calls) = webhook((AxumStatus::TOO_MANY_REQUESTS, vec![], r#"{"retry_after": 0.01}"#)).await;
+        post_json_with_rate_limit(&client, &url, &payload, 3).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_without_waiting_for_a_long_rate_limit() {
+        let started = std::time::Instant::now();
+        let (url, calls) = webhook((AxumStatus::TOO_MANY_REQUESTS, vec![("retry-after", "3600")], "")).await;
+        let error = post_json_with_rate_limit(&Client::new(), &url, &serde_json::json!({}), 3)
+            .await
+            .unwrap_err();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(format!("{:#}", error).contains("longer than the 60s a delivery waits"), "{:#}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn does_not_wait_after_the_last_attempt() {
+        let started = std::time::Instant::now();
+        let (url, calls) = webhook((AxumStatus::TOO_MANY_REQUESTS, vec![("retry-after", "30")], "")).await;
+        post_json_with_rate_limit(&Client::new(), &url, &serde_json::json!({}), 1)
+            .await
+            .unwrap_err();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_client_errors() {
+        let (url, calls) = webhook((AxumStatus::NOT_FOUND, vec![], "Unknown Webhook")).await;
+        let error = post_json_with_rate_limit(&Client::new(), &url, &serde_json::json!({}), 3)
+            .await
+            .unwrap_err();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(error.to_string().contains("Unknown Webhook"));
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use std::time::Duration;
+
+use crate::models::announcement::Announcement;
+use crate::models::update::AnnouncementUpdate;
+use crate::notifications::{exchange_filter_accepts, post_json_with_rate_limit, truncate_chars, NotificationSink};
+
+/// Configuration of the Slack incoming-webhook sink
+#[derive(Debug, Clone)]
+pub struct SlackSinkConfig {
+    /// Slack incoming webhook URL
+    pub webhook_url: String,
+    /// Exchanges forwarded to Slack, all exchanges if empty
+    pub exchanges: Vec<String>,
+    /// Maximum number of delivery attempts
+    pub max_retries: usize,
+}
+
+/// Slack sink posting a Block Kit message for every new listing
+pub struct SlackSink {
+    client: Client,
+    config: SlackSinkConfig,
+}
+
+impl SlackSink {
+    /// Create a new Slack sink
+    pub fn new(config: SlackSinkConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create Slack HTTP client")?;
+
+        Ok(Self { client, config })
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SlackSink {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn accepts(&self, exchange: &str) -> bool {
+        exchange_filter_accepts(&self.config.exchanges, exchange)
+    }
+
+    async fn notify(&self, announcement: &Announcement) -> Result<()> {
+        let payload = build_payload(announcement);
+        post_json_with_rate_limit(&self.client, &self.config.webhook_url, &payload, self.config.max_retries)
+            .await
+            .context("Failed to post Slack webhook")
+    }
+
+    async fn notify_update(&self, update: &AnnouncementUpdate) -> Result<()> {
+        let payload = build_update_payload(update);
+        post_json_with_rate_limit(&self.client, &self.config.webhook_url, &payload, self.config.max_retries)
+            .await
+            .context("Failed to post Slack webhook")
+    }
+}
+
+/// Build the Block Kit payload.
+///
+/// The top-level `text` is the fallback shown in notifications and by clients that
+/// cannot render blocks.
+fn build_payload(announcement: &Announcement) -> serde_json::Value {
+    let symbols = if announcement.token_symbols.is_empty() {
+        "-".to_string()
+    } else {
+        announcement.token_symbols.join(", ")
+    };
+
+    let title = escape_mrkdwn_truncated(&announcement.title, 2000);
+    let title_text = if announcement.url.is_empty() {
+        format!("*{}*", title)
+    } else {
+        format!("*<{}|{}>*", announcement.url, title)
+    };
+
+    // Slack renders <!date^...> in the reader's timezone and falls back to the text
+    let published = format!(
+        "<!date^{}^{{date_short_pretty}} {{time}}|{}>",
+        announcement.published_at.timestamp(),
+        announcement.published_at.format("%Y-%m-%d %H:%M UTC")
+    );
+
+    let mut fields = vec![
+        serde_json::json!({ "type": "mrkdwn", "text": format!("*Symbols*\n{}", escape_mrkdwn(&symbols)) }),
+        serde_json::json!({ "type": "mrkdwn", "text": format!("*Published*\n{}", published) }),
+    ];
+    if let Some(trading_open_at) = announcement.trading_open_at {
+        let trading_open = format!(
+            "<!date^{}^{{date_short_pretty}} {{time}}|{}>",
+            trading_open_at.timestamp(),
+            trading_open_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        fields.push(serde_json::json!({ "type": "mrkdwn", "text": format!("*Trading opens*\n{}", trading_open) }));
+    }
+
+    serde_json::json!({
+        "text": format!("New listing on {}: {}", announcement.exchange, announcement.title),
+        "blocks": [
+            {
+                "type": "header",
+                "text": {
+                    "type": "plain_text",
+                    "text": truncate_chars(&format!("New listing on {}", announcement.exchange), 150),
+                },
+            },
+            {
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": title_text },
+            },
+            {
+                "type": "section",
+                "fields": fields,
+            },
+        ],
+    })
+}
+
+/// Build the Block Kit payload for an edited announcement, with the diff as a code
+/// block (section texts are limited to 3000 characters)
+fn build_update_payload(update: &AnnouncementUpdate) -> serde_json::Value {
+    let announcement = &update.announcement;
+
+    let title = escape_mrkdwn_truncated(&announcement.title, 2000);
+    let title_text = if announcement.url.is_empty() {
+        format!("*{}*", title)
+    } else {
+        format!("*<{}|{}>*", announcement.url, title)
+    };
+    let diff = escape_mrkdwn_truncated(update.diff.trim_end(), 2500);
+
+    serde_json::json!({
+        "text": format!("Announcement updated on {}: {}", announcement.exchange, announcement.title),
+        "blocks": [
+            {
+                "type": "header",
+                "text": {
+                    "type": "plain_text",
+                    "text": truncate_chars(&format!("Announcement updated on {}", announcement.exchange), 150),
+                },
+            },
+            {
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": title_text },
+            },
+            {
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": format!("```{}```", diff) },
+            },
+        ],
+    })
+}
+
+/// Escape the control characters of Slack's mrkdwn format
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escape text for mrkdwn, then truncate it to `max_chars` with an ellipsis. Escaping
+/// lengthens the text, so the limit applies to the escaped text and whole characters
+/// are dropped, never half of an entity.
+fn escape_mrkdwn_truncated(text: &str, max_chars: usize) -> String {
+    let escaped = escape_mrkdwn(text);
+    if escaped.chars().count() <= max_chars {
+        return escaped;
+    }
+
+    let mut truncated = String::new();
+    let mut length = 0;
+    for c in text.chars() {
+        let piece = escape_mrkdwn(c.encode_utf8(&mut [0; 4]));
+        length += piece.chars().count();
+        if length > max_chars.saturating_sub(1) {
+            break;
+        }
+        truncated.push_str(&piece);
+    }
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn announcement(title: &str) -> Announcement {
+        let mut announcement = Announcement::sample("195342", title);
+        announcement.token_symbols = vec!["NOT".to_string()];
+        announcement
+    }
+
+    #[test]
+    fn builds_block_kit_payload() {
+        let mut announcement = announcement("Binance Will List <NOT> & More");
+        announcement.trading_open_at = Some(Utc.with_ymd_and_hms(2024, 5, 16, 12, 0, 0).unwrap());
+        let payload = build_payload(&announcement);
+
+        assert_eq!(payload["text"], "New listing on Binance: Binance Will List <NOT> & More");
+        assert_eq!(payload["blocks"][0]["text"]["text"], "New listing on Binance");
+        assert_eq!(
+            payload["blocks"][1]["text"]["text"],
+            "*<https://www.binance.com/en/support/announcement/195342|Binance Will List &lt;NOT&gt; &amp; More>*"
+        );
+        assert_eq!(payload["blocks"][2]["fields"][0]["text"], "*Symbols*\nNOT");
+        assert_eq!(
+            payload["blocks"][2]["fields"][1]["text"],
+            "*Published*\n<!date^1715839200^{date_short_pretty} {time}|2024-05-16 06:00 UTC>"
+        );
+        assert_eq!(
+            payload["blocks"][2]["fields"][2]["text"],
+            "*Trading opens*\n<!date^1715860800^{date_short_pretty} {time}|2024-05-16 12:00 UTC>"
+        );
+    }
+
+    #[test]
+    fn truncates_after_escaping() {
+        assert_eq!(escape_mrkdwn_truncated("a<b", 10), "a&lt;b");
+        // "a&lt;" would be 5 characters with the ellipsis, the entity is dropped whole
+        assert_eq!(escape_mrkdwn_truncated("a<b<c", 5), "a…");
+        assert_eq!(escape_mrkdwn_truncated("abcdef", 4), "abc…");
+    }
+
+    #[test]
+    fn keeps_escaped_diffs_under_the_section_limit() {
+        let update = AnnouncementUpdate {
+            announcement: announcement("Binance Will List NOT"),
+            previous_title: "Binance Will List NOT".to_string(),
+            previous_content: String::new(),
+            diff: "<&>".repeat(2000),
+        };
+        let payload = build_update_payload(&update);
+
+        let text = payload["blocks"][2]["text"]["text"].as_str().unwrap();
+        assert!(text.chars().count() <= 3000);
+        assert!(text.ends_with("&gt;…```"));
+    }
+}
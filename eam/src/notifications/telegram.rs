@@ -0,0 +1,421 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::future;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::models::announcement::Announcement;
+use crate::models::update::AnnouncementUpdate;
+use crate::notifications::{exchange_filter_accepts, truncate_chars, NotificationSink, MAX_RETRY_AFTER};
+
+/// Longest diff included in an update message, Telegram messages are limited to 4096 characters
+const MAX_DIFF_CHARS: usize = 3000;
+
+/// Minimum delay between two messages to the same chat.
+///
+/// Telegram allows roughly one message per second per chat (and 20 per minute in
+/// groups); staying under it avoids 429 responses in the first place.
+const PER_CHAT_INTERVAL: Duration = Duration::from_millis(1100);
+
+/// A Telegram chat that receives notifications
+#[derive(Debug, Clone)]
+pub struct TelegramChat {
+    /// Chat id, e.g. `-1001234567890` for a channel or group
+    pub chat_id: String,
+    /// Exchanges this chat is interested in, all exchanges if empty
+    pub exchanges: Vec<String>,
+}
+
+impl TelegramChat {
+    /// Check if the chat wants notifications for the given exchange
+    fn accepts(&self, exchange: &str) -> bool {
+        exchange_filter_accepts(&self.exchanges, exchange)
+    }
+}
+
+/// Configuration of the Telegram bot sink
+#[derive(Debug, Clone)]
+pub struct TelegramSinkConfig {
+    /// Bot token issued by @BotFather
+    pub bot_token: String,
+    /// Base URL of the Bot API, overridable to test against a mock server
+    pub api_base_url: String,
+    /// Chats that receive notifications
+    pub chats: Vec<TelegramChat>,
+    /// Maximum number of delivery attempts per chat
+    pub max_retries: usize,
+}
+
+/// Telegram sink sending MarkdownV2 formatted messages via the Bot API
+pub struct TelegramSink {
+    client: Client,
+    config: TelegramSinkConfig,
+    /// Time of the last message per chat, used to pace messages to each chat
+    last_sent: HashMap<String, Mutex<Option<Instant>>>,
+}
+
+/// Subset of the Bot API response we care about
+#[derive(Debug, Deserialize)]
+struct TelegramResponse {
+    ok: bool,
+    description: Option<String>,
+    parameters: Option<TelegramResponseParameters>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TelegramResponseParameters {
+    retry_after: Option<u64>,
+}
+
+impl TelegramSink {
+    /// Create a new Telegram sink
+    pub fn new(config: TelegramSinkConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create Telegram HTTP client")?;
+
+        let last_sent = config.chats.iter()
+            .map(|chat| (chat.chat_id.clone(), Mutex::new(None)))
+            .collect();
+
+        Ok(Self {
+            client,
+            config,
+            last_sent,
+        })
+    }
+
+    /// Send a message to one chat, respecting the per-chat rate limit.
+    ///
+    /// The per-chat lock is held for the whole delivery so that messages to the same
+    /// chat are sent one after another, while different chats proceed in parallel.
+    async fn send_to_chat(&self, chat_id: &str, text: &str) -> Result<()> {
+        let mut last_sent = self.last_sent
+            .get(chat_id)
+            .context("Unknown Telegram chat")?
+            .lock()
+            .await;
+
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.config.api_base_url.trim_end_matches('/'),
+            self.config.bot_token
+        );
+        let payload = serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "parse_mode": "MarkdownV2",
+            "disable_web_page_preview": true,
+        });
+
+        let mut delay = Duration::from_millis(500);
+        let mut wait = Duration::ZERO;
+        let mut last_error = None;
+
+        for attempt in 0..self.config.max_retries {
+            // Wait between attempts, never after the last one
+            if attempt > 0 {
+                tokio::time::sleep(wait).await;
+            }
+            if let Some(previous) = *last_sent {
+                tokio::time::sleep_until(previous + PER_CHAT_INTERVAL).await;
+            }
+            *last_sent = Some(Instant::now());
+
+            let response = match self.client.post(&url).json(&payload).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::warn!(chat_id = chat_id, "Telegram attempt {} failed: {}", attempt + 1, e);
+                    last_error = Some(anyhow::anyhow!(e));
+                    wait = delay;
+                    delay *= 2;
+                    continue;
+                }
+            };
+
+            let status = response.status();
+            let body: Option<TelegramResponse> = response.json().await.ok();
+
+            if status.is_success() && body.as_ref().is_none_or(|b| b.ok) {
+                return Ok(());
+            }
+
+            let description = body.as_ref()
+                .and_then(|b| b.description.clone())
+                .unwrap_or_else(|| "Unknown error".to_string());
+            let error = anyhow::anyhow!("Telegram API returned status {}: {}", status, description);
+
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                // Telegram tells us exactly how long to back off
+                let retry_after = body.as_ref()
+                    .and_then(|b| b.parameters.as_ref())
+                    .and_then(|p| p.retry_after)
+                    .map(Duration::from_secs)
+                    .unwrap_or(delay);
+                if retry_after > MAX_RETRY_AFTER {
+                    return Err(error.context(format!(
+                        "Telegram rate limited for {:?}, longer than the {:?} a delivery waits",
+                        retry_after, MAX_RETRY_AFTER
+                    )));
+                }
+                tracing::warn!(
+                    chat_id = chat_id,
+                    retry_after_seconds = retry_after.as_secs(),
+                    "Telegram rate limit hit, retrying in {:?}",
+                    retry_after
+                );
+                wait = retry_after;
+                last_error = Some(error);
+            } else if status.is_server_error() {
+                wait = delay;
+                delay *= 2;
+                last_error = Some(error);
+            } else {
+                // Client errors such as a bad chat id or broken markup will not get better
+                return Err(error);
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Failed after {} attempts", self.config.max_retries)))
+    }
+
+    /// Send a message to every chat interested in the exchange
+    async fn send_to_chats(&self, exchange: &str, text: &str) -> Result<()> {
+        let deliveries = self.config.chats.iter()
+            .filter(|chat| chat.accepts(exchange))
+            .map(|chat| async {
+                self.send_to_chat(&chat.chat_id, text)
+                    .await
+                    .with_context(|| format!("Failed to send Telegram message to chat {}", chat.chat_id))
+            });
+
+        let errors: Vec<String> = future::join_all(deliveries)
+            .await
+            .into_iter()
+            .filter_map(|result| result.err())
+            .map(|e| format!("{:#}", e))
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(errors.join("; ")))
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for TelegramSink {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn notify(&self, announcement: &Announcement) -> Result<()> {
+        self.send_to_chats(&announcement.exchange, &format_message(announcement)).await
+    }
+
+    async fn notify_update(&self, update: &AnnouncementUpdate) -> Result<()> {
+        self.send_to_chats(&update.announcement.exchange, &format_update_message(update)).await
+    }
+}
+
+/// Format an announcement as a Telegram MarkdownV2 message
+fn format_message(announcement: &Announcement) -> String {
+    let mut text = format!(
+        "*New listing on {}*\n{}\n",
+        escape_markdown(&announcement.exchange),
+        escape_markdown(&announcement.title)
+    );
+
+    if !announcement.token_symbols.is_empty() {
+        text.push_str(&format!(
+            "\nTokens: `{}`",
+            escape_code(&announcement.token_symbols.join(", "))
+        ));
+    }
+
+    text.push_str(&format!(
+        "\nPublished: {}",
+        escape_markdown(&announcement.published_at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+    ));
+
+    if let Some(trading_open_at) = announcement.trading_open_at {
+        text.push_str(&format!(
+            "\nTrading opens: {}",
+            escape_markdown(&trading_open_at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        ));
+    }
+
+    if !announcement.url.is_empty() {
+        text.push_str(&format!("\n[Open announcement]({})", escape_link(&announcement.url)));
+    }
+
+    text
+}
+
+/// Format an edit of an announcement as a Telegram MarkdownV2 message with the diff
+fn format_update_message(update: &AnnouncementUpdate) -> String {
+    let announcement = &update.announcement;
+    let mut text = format!(
+        "*Announcement updated on {}*\n{}\n",
+        escape_markdown(&announcement.exchange),
+        escape_markdown(&announcement.title)
+    );
+
+    text.push_str(&format!(
+        "\n```\n{}\n```",
+        escape_code(&truncate_chars(update.diff.trim_end(), MAX_DIFF_CHARS))
+    ));
+
+    if !announcement.url.is_empty() {
+        text.push_str(&format!("\n[Open announcement]({})", escape_link(&announcement.url)));
+    }
+
+    text
+}
+
+/// Escape text for MarkdownV2, every reserved character must be prefixed with a backslash
+fn escape_markdown(text: &str) -> String {
+    const RESERVED: &[char] = &[
+        '\\', '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
+    ];
+    escape_chars(text, RESERVED)
+}
+
+/// Escape text inside an inline code entity
+fn escape_code(text: &str) -> String {
+    escape_chars(text, &['\\', '`'])
+}
+
+/// Escape the URL part of an inline link
+fn escape_link(url: &str) -> String {
+    escape_chars(url, &['\\', ')'])
+}
+
+fn escape_chars(text: &str, reserved: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if reserved.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode as AxumStatus, routing::post, Json, Router};
+    use chrono::{TimeZone, Utc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn announcement() -> Announcement {
+        let mut announcement = Announcement::sample("195342", "Binance Will List Notcoin (NOT) with Seed Tag Applied");
+        announcement.token_symbols = vec!["NOT".to_string(), "1000`SATS".to_string()];
+        announcement.trading_open_at = Some(Utc.with_ymd_and_hms(2024, 5, 16, 12, 0, 0).unwrap());
+        announcement
+    }
+
+    #[test]
+    fn escapes_reserved_markdown_characters() {
+        assert_eq!(escape_markdown("a.b-c_(d)!"), "a\\.b\\-c\\_\\(d\\)\\!");
+        assert_eq!(escape_code("a`b\\c.d"), "a\\`b\\\\c.d");
+        assert_eq!(escape_link("https://x.com/a_(b)"), "https://x.com/a_(b\\)");
+    }
+
+    #[test]
+    fn formats_a_new_listing() {
+        assert_eq!(
+            format_message(&announcement()),
+            "*New listing on Binance*\n\
+             Binance Will List Notcoin \\(NOT\\) with Seed Tag Applied\n\
+             \nTokens: `NOT, 1000\\`SATS`\
+             \nPublished: 2024\\-05\\-16 06:00:00 UTC\
+             \nTrading opens: 2024\\-05\\-16 12:00:00 UTC\
+             \n[Open announcement](https://www.binance.com/en/support/announcement/195342)"
+        );
+    }
+
+    #[test]
+    fn formats_an_update_with_its_diff_in_a_code_block() {
+        let update = AnnouncementUpdate {
+            announcement: announcement(),
+            previous_title: "Binance Will List Notcoin (NOT)".to_string(),
+            previous_content: String::new(),
+            diff: "- opens at 10:00\n+ opens at `12:00`\n".to_string(),
+        };
+
+        let text = format_update_message(&update);
+        assert!(text.starts_with("*Announcement updated on Binance*\n"));
+        assert!(text.contains("\n```\n- opens at 10:00\n+ opens at \\`12:00\\`\n```\n"));
+    }
+
+    #[tokio::test]
+    async fn retries_after_the_delay_telegram_asks_for() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/bottoken/sendMessage", post(|State(calls): State<Arc<AtomicUsize>>| async move {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    (AxumStatus::TOO_MANY_REQUESTS, Json(serde_json::json!({
+                        "ok": false,
+                        "description": "Too Many Requests: retry after 0",
+                        "parameters": { "retry_after": 0 },
+                    })))
+                } else {
+                    (AxumStatus::OK, Json(serde_json::json!({ "ok": true })))
+                }
+            }))
+            .with_state(calls.clone());
+        let base_url = crate::utils::serve(router).await;
+
+        let sink = TelegramSink::new(TelegramSinkConfig {
+            bot_token: "token".to_string(),
+            api_base_url: base_url,
+            chats: vec![
+                TelegramChat { chat_id: "1".to_string(), exchanges: vec![] },
+                TelegramChat { chat_id: "2".to_string(), exchanges: vec!["OKX".to_string()] },
+            ],
+            max_retries: 3,
+        }).unwrap();
+
+        sink.notify(&announcement()).await.unwrap();
+        // Chat 2 only follows OKX, chat 1 needed a second attempt
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_without_waiting_for_a_long_rate_limit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/bottoken/sendMessage", post(|State(calls): State<Arc<AtomicUsize>>| async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                (AxumStatus::TOO_MANY_REQUESTS, Json(serde_json::json!({
+                    "ok": false,
+                    "description": "Too Many Requests: retry after 3600",
+                    "parameters": { "retry_after": 3600 },
+                })))
+            }))
+            .with_state(calls.clone());
+        let base_url = crate::utils::serve(router).await;
+
+        let sink = TelegramSink::new(TelegramSinkConfig {
+            bot_token: "token".to_string(),
+            api_base_url: base_url,
+            chats: vec![TelegramChat { chat_id: "1".to_string(), exchanges: vec![] }],
+            max_retries: 3,
+        }).unwrap();
+
+        let started = std::time::Instant::now();
+        let error = sink.notify(&announcement()).await.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(format!("{:#}", error).contains("longer than the 60s a delivery waits"), "{:#}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use reqwest::{header, Client};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::models::announcement::Announcement;
+use crate::models::update::AnnouncementUpdate;
+use crate::notifications::NotificationSink;
+use crate::utils::retry_request;
+
+lazy_static! {
+    /// A `{{field}}` placeholder of a body template
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{\{(\w+)\}\}").unwrap();
+}
+
+/// Configuration of the generic JSON webhook sink
+#[derive(Debug, Clone)]
+pub struct WebhookSinkConfig {
+    /// URL the notifications are POSTed to
+    pub url: String,
+    /// Extra request headers, e.g. for authentication
+    pub headers: Vec<(String, String)>,
+    /// Optional body template, the serialized announcement is sent when absent
+    pub body_template: Option<String>,
+    /// Maximum number of delivery attempts
+    pub max_retries: usize,
+}
+
+/// Generic webhook sink that POSTs a JSON document for every new listing
+pub struct WebhookSink {
+    client: Client,
+    config: WebhookSinkConfig,
+    headers: header::HeaderMap,
+}
+
+impl WebhookSink {
+    /// Create a new webhook sink, validating the configured headers
+    pub fn new(config: WebhookSinkConfig) -> Result<Self> {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+
+        for (name, value) in &config.headers {
+            let name = header::HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid webhook header name: {}", name))?;
+            let value = header::HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for webhook header {}", name))?;
+            headers.insert(name, value);
+        }
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create webhook HTTP client")?;
+
+        Ok(Self {
+            client,
+            config,
+            headers,
+        })
+    }
+
+    /// Build the request body for an announcement
+    fn render_body(&self, announcement: &Announcement) -> Result<String> {
+        match &self.config.body_template {
+            Some(template) => render_template(template, announcement),
+            None => serde_json::to_string(announcement).context("Failed to serialize announcement"),
+        }
+    }
+
+    /// Build the request body for an edited announcement, the serialized update
+    /// unless a template is configured
+    fn render_update_body(&self, update: &AnnouncementUpdate) -> Result<String> {
+        match &self.config.body_template {
+            Some(template) => render_template_with_diff(template, &update.announcement, &update.diff),
+            None => serde_json::to_string(update).context("Failed to serialize announcement update"),
+        }
+    }
+
+    /// POST a body to the webhook, retrying transient failures
+    async fn post(&self, body: String) -> Result<()> {
+        let response = retry_request(
+            || {
+                let request = self.client
+                    .post(&self.config.url)
+                    .headers(self.headers.clone())
+                    .body(body.clone());
+                async move {
+                    request.send()
+                        .await
+                        .context("Failed to send webhook notification")
+                }
+            },
+            self.config.max_retries,
+            500, // initial delay in ms
+        ).await?;
+
+        // retry_request hands back non-retryable error statuses for the caller to handle
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Webhook returned status {}: {}", status, error_text
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn notify(&self, announcement: &Announcement) -> Result<()> {
+        self.post(self.render_body(announcement)?).await?;
+
+        tracing::debug!(
+            exchange = announcement.exchange,
+            announcement_id = announcement.id,
+            "Delivered webhook notification"
+        );
+
+        Ok(())
+    }
+
+    async fn notify_update(&self, update: &AnnouncementUpdate) -> Result<()> {
+        self.post(self.render_update_body(update)?).await?;
+
+        tracing::debug!(
+            exchange = update.announcement.exchange,
+            announcement_id = update.announcement.id,
+            "Delivered webhook update notification"
+        );
+
+        Ok(())
+    }
+}
+
+/// Render a body template for an announcement.
+///
+/// Placeholders of the form `{{field}}` are replaced with the JSON-escaped value of
+/// the field (without surrounding quotes), so they can be used inside JSON string
+/// literals. `{{announcement}}` inserts the whole announcement as a JSON object.
+/// The template is rendered in a single pass, placeholders inside the inserted values
+/// are left as they are. Unknown placeholders are kept verbatim.
+///
+/// Supported fields: `id`, `title`, `content`, `url`, `exchange`, `published_at`,
+/// `tokens` (comma separated), `diff` (empty for new listings) and `announcement`.
+pub fn render_template(template: &str, announcement: &Announcement) -> Result<String> {
+    render_template_with_diff(template, announcement, "")
+}
+
+/// Render a body template for an edited announcement, see `render_template`
+pub fn render_template_with_diff(template: &str, announcement: &Announcement, diff: &str) -> Result<String> {
+    let announcement_json = serde_json::to_string(announcement)
+        .context("Failed to serialize announcement")?;
+
+    let fields: HashMap<&str, String> = HashMap::from([
+        ("id", json_escape(&announcement.id)),
+        ("title", json_escape(&announcement.title)),
+        ("content", json_escape(&announcement.content)),
+        ("url", json_escape(&announcement.url)),
+        ("exchange", json_escape(&announcement.exchange)),
+        ("published_at", announcement.published_at.to_rfc3339()),
+        ("tokens", json_escape(&announcement.token_symbols.join(", "))),
+        ("diff", json_escape(diff)),
+        ("announcement", announcement_json),
+    ]);
+
+    let rendered = PLACEHOLDER.replace_all(template, |cap: &Captures| {
+        fields.get(&cap[1]).cloned().unwrap_or_else(|| cap[0].to_string())
+    });
+
+    Ok(rendered.into_owned())
+}
+
+/// Escape a value for use inside a JSON string literal
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    // Strip the surrounding quotes added by the serializer
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement(title: &str, content: &str) -> Announcement {
+        let mut announcement = Announcement::sample("195342", title);
+        announcement.content = content.to_string();
+        announcement.token_symbols = vec!["NOT".to_string(), "ZRO".to_string()];
+        announcement
+    }
+
+    #[test]
+    fn fills_placeholders_with_escaped_values() {
+        let template = r#"{"text": "{{exchange}}: {{title}} ({{tokens}}) {{url}}", "at": "{{published_at}}"}"#;
+        let rendered = render_template(template, &announcement("Binance Will List \"NOT\"\nToday", "")).unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            body["text"],
+            "Binance: Binance Will List \"NOT\"\nToday (NOT, ZRO) https://www.binance.com/en/support/announcement/195342"
+        );
+        assert_eq!(body["at"], "2024-05-16T06:00:00+00:00");
+    }
+
+    #[test]
+    fn inserts_the_whole_announcement_as_an_object() {
+        let rendered = render_template(r#"{"event": "listing", "data": {{announcement}}}"#, &announcement("Title", "")).unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(body["data"]["id"], "195342");
+        assert_eq!(body["data"]["token_symbols"][1], "ZRO");
+    }
+
+    #[test]
+    fn does_not_expand_placeholders_inside_values() {
+        let template = r#"{"title": "{{title}}", "content": "{{content}}", "diff": "{{diff}}", "other": "{{unknown}}"}"#;
+        let announcement = announcement("Listing {{url}}", "See {{announcement}} and {{title}}");
+        let rendered = render_template_with_diff(template, &announcement, "- {{id}}").unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(body["title"], "Listing {{url}}");
+        assert_eq!(body["content"], "See {{announcement}} and {{title}}");
+        assert_eq!(body["diff"], "- {{id}}");
+        assert_eq!(body["other"], "{{unknown}}");
+    }
+}
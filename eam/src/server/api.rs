@@ -0,0 +1,139 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{parse_since, parse_until};
+use crate::exchanges::status::MonitorStatus;
+use crate::models::announcement::{Announcement, AnnouncementKind};
+use crate::server::AppState;
+use crate::store::archive::ArchiveQuery;
+
+/// Number of announcements returned when the request does not set `limit`
+const DEFAULT_LIMIT: usize = 50;
+
+/// Upper bound of `limit`, larger values are clamped
+const MAX_LIMIT: usize = 500;
+
+/// Query string of `GET /announcements`, dates are parsed like the query subcommand's
+#[derive(Debug, Deserialize)]
+pub struct AnnouncementsParams {
+    exchange: Option<String>,
+    kind: Option<String>,
+    symbol: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+}
+
+/// An exchange as reported by `GET /exchanges`
+#[derive(Serialize)]
+pub struct ExchangeInfo {
+    name: String,
+    /// Whether a monitor is running for this exchange
+    monitored: bool,
+    /// Announcements of this exchange in the archive
+    archived_announcements: u64,
+    latest_published_at: Option<DateTime<Utc>>,
+    status: Option<MonitorStatus>,
+}
+
+/// Error response, rendered as `{"error": "..."}`
+pub enum ApiError {
+    BadRequest(String),
+    NotFound(String),
+    Internal(anyhow::Error),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (code, message) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::Internal(e) => {
+                tracing::error!(error = %e, "HTTP API request failed: {:#}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal error".to_string())
+            }
+        };
+        (code, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError::Internal(e)
+    }
+}
+
+/// Search archived announcements, newest first
+pub async fn list_announcements(
+    State(state): State<AppState>,
+    Query(params): Query<AnnouncementsParams>,
+) -> Result<Json<Vec<Announcement>>, ApiError> {
+    let kind = params.kind
+        .as_deref()
+        .map(str::parse::<AnnouncementKind>)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let since = params.since
+        .as_deref()
+        .map(parse_since)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let until = params.until
+        .as_deref()
+        .map(parse_until)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let query = ArchiveQuery {
+        exchange: params.exchange,
+        symbol: params.symbol,
+        kind,
+        since,
+        until,
+        detected_only: false,
+        limit: Some(params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)),
+    };
+
+    let announcements = state.archive.query(&query)
+        .await?
+        .into_iter()
+        .map(|archived| archived.announcement)
+        .collect();
+    Ok(Json(announcements))
+}
+
+/// A single archived announcement
+pub async fn get_announcement(
+    State(state): State<AppState>,
+    Path((exchange, id)): Path<(String, String)>,
+) -> Result<Json<Announcement>, ApiError> {
+    match state.archive.get(&exchange, &id).await? {
+        Some(archived) => Ok(Json(archived.announcement)),
+        None => Err(ApiError::NotFound(format!("No announcement {} on {}", id, exchange))),
+    }
+}
+
+/// Every supported exchange with its monitor status and archive totals
+pub async fn list_exchanges(State(state): State<AppState>) -> Result<Json<Vec<ExchangeInfo>>, ApiError> {
+    let stats = state.archive.exchange_stats().await?;
+    let monitors = state.status.snapshot();
+
+    let exchanges = state.exchanges.iter()
+        .map(|name| {
+            let stats = stats.iter().find(|s| s.exchange.eq_ignore_ascii_case(name));
+            let status = monitors.iter().find(|m| m.exchange.eq_ignore_ascii_case(name)).cloned();
+            ExchangeInfo {
+                name: name.clone(),
+                monitored: status.is_some(),
+                archived_announcements: stats.map(|s| s.announcements).unwrap_or(0),
+                latest_published_at: stats.map(|s| s.latest_published_at),
+                status,
+            }
+        })
+        .collect();
+    Ok(Json(exchanges))
+}
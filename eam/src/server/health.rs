@@ -0,0 +1,67 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::exchanges::status::MonitorStatus;
+use crate::server::AppState;
+
+#[derive(Serialize)]
+struct HealthResponse {
+    /// "ok" if every monitor is healthy, "degraded" if some are or a monitor recently
+    /// crashed, "unhealthy" if none is
+    status: &'static str,
+    healthy_monitors: usize,
+    degraded_monitors: usize,
+    total_monitors: usize,
+}
+
+#[derive(Serialize)]
+struct StatusResponse {
+    started_at: DateTime<Utc>,
+    monitors: Vec<MonitorStatus>,
+}
+
+/// Liveness: fails only when no monitor is healthy, so that an orchestrator restarts
+/// the process when everything is stuck but not when a single exchange is down
+pub async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+    let monitors = state.status.snapshot();
+    let healthy = monitors.iter().filter(|m| m.healthy).count();
+    let degraded = monitors.iter().filter(|m| m.degraded).count();
+
+    let (code, status) = if monitors.is_empty() || healthy == 0 {
+        (StatusCode::SERVICE_UNAVAILABLE, "unhealthy")
+    } else if healthy < monitors.len() || degraded > 0 {
+        (StatusCode::OK, "degraded")
+    } else {
+        (StatusCode::OK, "ok")
+    };
+
+    let body = HealthResponse {
+        status,
+        healthy_monitors: healthy,
+        degraded_monitors: degraded,
+        total_monitors: monitors.len(),
+    };
+    (code, Json(body))
+}
+
+/// Readiness: succeeds once at least one monitor has completed a successful poll
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let ready = state.status.snapshot().iter().any(|m| m.last_success_at.is_some());
+    if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not ready")
+    }
+}
+
+/// Detailed status of every monitor
+pub async fn status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(StatusResponse {
+        started_at: state.started_at,
+        monitors: state.status.snapshot(),
+    })
+}
@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::exchanges::status::StatusRegistry;
+use crate::events::EventBus;
+use crate::metrics;
+use crate::store::archive::AnnouncementArchive;
+
+mod api;
+mod health;
+mod stream;
+
+/// State shared by all HTTP handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub started_at: DateTime<Utc>,
+    pub status: Arc<StatusRegistry>,
+    pub archive: Arc<AnnouncementArchive>,
+    /// Names of all supported exchanges, monitored or not
+    pub exchanges: Arc<Vec<String>>,
+    /// Monitor events, streamed to the push clients
+    pub events: EventBus,
+    /// Cancelled on shutdown, ends the push streams
+    pub shutdown: CancellationToken,
+}
+
+/// Bind the embedded HTTP server.
+///
+/// Binding happens before the monitors start so that a port conflict is reported
+/// as a startup error instead of a log line nobody reads.
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind HTTP server to {}", addr))
+}
+
+/// Serve the HTTP endpoints until the state's shutdown token is cancelled and every
+/// open request has completed
+pub async fn serve(listener: TcpListener, state: AppState) -> Result<()> {
+    let shutdown = state.shutdown.clone();
+    let router = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/status", get(health::status))
+        .route("/announcements", get(api::list_announcements))
+        .route("/announcements/{exchange}/{id}", get(api::get_announcement))
+        .route("/announcements/stream", get(stream::sse))
+        .route("/announcements/ws", get(stream::websocket))
+        .route("/exchanges", get(api::list_exchanges))
+        .with_state(state);
+
+    tracing::info!(addr = %listener.local_addr()?, "HTTP server listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+        .context("HTTP server failed")
+}
+
+/// Prometheus text exposition of all metrics
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    use crate::events::MonitorEvent;
+    use crate::models::announcement::{Announcement, AnnouncementKind};
+
+    /// Serve the endpoints on a free local port, returning the base URL
+    async fn start(status: Arc<StatusRegistry>, archive: AnnouncementArchive) -> (String, AppState) {
+        let state = AppState {
+            started_at: Utc::now(),
+            status,
+            archive: Arc::new(archive),
+            exchanges: Arc::new(vec!["Binance".to_string(), "OKX".to_string()]),
+            events: EventBus::new(16),
+            shutdown: CancellationToken::new(),
+        };
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, state.clone()));
+        (base_url, state)
+    }
+
+    async fn get(url: &str) -> (StatusCode, String) {
+        let response = reqwest::get(url).await.unwrap();
+        (response.status(), response.text().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn reports_health_and_readiness() {
+        let status = Arc::new(StatusRegistry::new());
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        let (base_url, _state) = start(status.clone(), archive).await;
+
+        let (code, body) = get(&format!("{}/healthz", base_url)).await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains(r#""status":"unhealthy""#));
+        assert_eq!(get(&format!("{}/readyz", base_url)).await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+        status.monitor_started("Binance", 60);
+        status.poll_succeeded("Binance", None);
+        let (code, body) = get(&format!("{}/healthz", base_url)).await;
+        assert_eq!(code, StatusCode::OK);
+        assert!(body.contains(r#""status":"ok""#));
+        assert_eq!(get(&format!("{}/readyz", base_url)).await, (StatusCode::OK, "ready".to_string()));
+
+        status.monitor_started("OKX", 60);
+        status.monitor_stopped("OKX", Some("panicked"));
+        let (code, body) = get(&format!("{}/healthz", base_url)).await;
+        assert_eq!(code, StatusCode::OK);
+        assert!(body.contains(r#""status":"degraded","healthy_monitors":1"#));
+
+        let (_, body) = get(&format!("{}/status", base_url)).await;
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["monitors"][1]["exchange"], "OKX");
+        assert_eq!(body["monitors"][1]["last_error"], "panicked");
+    }
+
+    #[tokio::test]
+    async fn serves_archived_announcements() {
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        let mut listing = Announcement::sample("195342", "Binance Will List Notcoin (NOT)");
+        listing.set_kind(AnnouncementKind::SpotListing, 0.9);
+        let mut delisting = Announcement::sample("195343", "Binance Will Delist MobileCoin (MOB)");
+        delisting.set_kind(AnnouncementKind::Delisting, 0.9);
+        archive.upsert_all(&[listing, delisting]).await.unwrap();
+        let (base_url, _state) = start(Arc::new(StatusRegistry::new()), archive).await;
+
+        let ids = |body: String| -> Vec<String> {
+            serde_json::from_str::<Vec<serde_json::Value>>(&body).unwrap()
+                .iter()
+                .map(|announcement| announcement["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let (code, body) = get(&format!("{}/announcements?exchange=binance&kind=spot_listing", base_url)).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(ids(body), ["195342"]);
+        let (_, body) = get(&format!("{}/announcements?symbol=mob&limit=1", base_url)).await;
+        assert_eq!(ids(body), ["195343"]);
+
+        let (code, body) = get(&format!("{}/announcements?kind=listing", base_url)).await;
+        assert_eq!(code, StatusCode::BAD_REQUEST);
+        assert!(body.contains("Unknown announcement kind 'listing'"));
+        assert_eq!(get(&format!("{}/announcements?since=yesterday", base_url)).await.0, StatusCode::BAD_REQUEST);
+
+        let (code, body) = get(&format!("{}/announcements/BINANCE/195342", base_url)).await;
+        assert_eq!(code, StatusCode::OK);
+        assert!(body.contains(r#""token_symbols":["NOT"]"#));
+        let (code, body) = get(&format!("{}/announcements/Binance/1", base_url)).await;
+        assert_eq!(code, StatusCode::NOT_FOUND);
+        assert_eq!(body, r#"{"error":"No announcement 1 on Binance"}"#);
+
+        let (_, body) = get(&format!("{}/exchanges", base_url)).await;
+        let exchanges: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(exchanges[0]["name"], "Binance");
+        assert_eq!(exchanges[0]["archived_announcements"], 2);
+        assert_eq!(exchanges[1]["archived_announcements"], 0);
+        assert_eq!(exchanges[1]["monitored"], false);
+    }
+
+    #[tokio::test]
+    async fn streams_matching_announcements_as_server_sent_events() {
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        let (base_url, state) = start(Arc::new(StatusRegistry::new()), archive).await;
+
+        let mut response = reqwest::get(format!("{}/announcements/stream?symbol=zro", base_url)).await.unwrap();
+        assert_eq!(response.headers()[reqwest::header::CONTENT_TYPE], "text/event-stream");
+
+        let announcement = |id: &str, symbol: &str| {
+            let mut announcement = Announcement::sample(id, &format!("OKX to list {}", symbol));
+            announcement.exchange = "OKX".to_string();
+            announcement.token_symbols = vec![symbol.to_string()];
+            MonitorEvent::NewAnnouncement(Arc::new(announcement))
+        };
+        state.events.publish(announcement("1", "NOT"));
+        state.events.publish(announcement("2", "ZRO"));
+
+        let mut received = String::new();
+        while !received.contains("\n\n") {
+            let chunk = response.chunk().await.unwrap().unwrap();
+            received.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert!(received.starts_with("event: announcement\nid: OKX:2\ndata: {\"id\":\"2\","), "{}", received);
+
+        // Open streams end with the server
+        state.shutdown.cancel();
+        while response.chunk().await.unwrap().is_some() {}
+    }
+}
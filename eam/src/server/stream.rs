@@ -0,0 +1,209 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::events::MonitorEvent;
+use crate::models::announcement::{Announcement, AnnouncementKind};
+use crate::server::api::ApiError;
+use crate::server::AppState;
+
+/// Interval of the heartbeat frames that keep idle connections and proxies alive
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Query string of the streaming endpoints, every filter is optional
+#[derive(Debug, Deserialize)]
+pub struct StreamParams {
+    exchange: Option<String>,
+    kind: Option<String>,
+    symbol: Option<String>,
+}
+
+/// Server-side filter of a streaming client
+struct StreamFilter {
+    exchange: Option<String>,
+    kind: Option<AnnouncementKind>,
+    symbol: Option<String>,
+}
+
+impl StreamFilter {
+    fn from_params(params: StreamParams) -> Result<Self, ApiError> {
+        let kind = params.kind
+            .as_deref()
+            .map(str::parse::<AnnouncementKind>)
+            .transpose()
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        Ok(Self {
+            exchange: params.exchange,
+            kind,
+            symbol: params.symbol,
+        })
+    }
+
+    /// Exchange and symbol are case-insensitive, like in `GET /announcements`
+    fn matches(&self, announcement: &Announcement) -> bool {
+        self.exchange.as_ref().is_none_or(|e| announcement.exchange.eq_ignore_ascii_case(e))
+            && self.kind.is_none_or(|k| announcement.kind == k)
+            && self.symbol.as_ref().is_none_or(|s| {
+                announcement.token_symbols.iter().any(|symbol| symbol.eq_ignore_ascii_case(s))
+            })
+    }
+}
+
+/// What a subscriber receives next
+enum Item {
+    Announcement(Arc<Announcement>),
+    /// The client was too slow and this many events were dropped
+    Lagged(u64),
+}
+
+/// A WebSocket text frame
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Frame<'a> {
+    Announcement { announcement: &'a Announcement },
+    Lagged { skipped: u64 },
+    Heartbeat { at: DateTime<Utc> },
+}
+
+/// Wait for the next new announcement passing the filter, None once the bus is closed
+async fn next_item(receiver: &mut broadcast::Receiver<MonitorEvent>, filter: &StreamFilter) -> Option<Item> {
+    loop {
+        match receiver.recv().await {
+            Ok(MonitorEvent::NewAnnouncement(announcement)) if filter.matches(&announcement) => {
+                return Some(Item::Announcement(announcement))
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => return Some(Item::Lagged(skipped)),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Server-Sent Events stream of newly detected announcements.
+///
+/// Every announcement is an `announcement` event with the JSON announcement as data,
+/// a `lagged` event reports announcements dropped for a slow client, and a comment
+/// line is sent as a heartbeat while nothing happens.
+pub async fn sse(
+    State(state): State<AppState>,
+    Query(params): Query<StreamParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let filter = StreamFilter::from_params(params)?;
+    let receiver = state.events.subscribe();
+
+    let stream = futures::stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        let event = match next_item(&mut receiver, &filter).await? {
+            Item::Announcement(announcement) => Event::default()
+                .event("announcement")
+                .id(format!("{}:{}", announcement.exchange, announcement.id))
+                .json_data(announcement.as_ref())
+                .unwrap_or_else(|_| Event::default().event("error")),
+            Item::Lagged(skipped) => Event::default()
+                .event("lagged")
+                .data(serde_json::json!({ "skipped": skipped }).to_string()),
+        };
+        Some((Ok(event), (receiver, filter)))
+    });
+    // Open streams would otherwise hold up the server's graceful shutdown
+    let stream = stream.take_until(state.shutdown.cancelled_owned());
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(HEARTBEAT_INTERVAL).text("heartbeat")))
+}
+
+/// WebSocket stream of newly detected announcements.
+///
+/// Every frame is a JSON text message with a `type` of `announcement`, `lagged` or
+/// `heartbeat`; messages from the client are ignored.
+pub async fn websocket(
+    State(state): State<AppState>,
+    Query(params): Query<StreamParams>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let filter = StreamFilter::from_params(params)?;
+    let receiver = state.events.subscribe();
+    let shutdown = state.shutdown.clone();
+    Ok(upgrade.on_upgrade(move |socket| push_to_websocket(socket, receiver, filter, shutdown)))
+}
+
+async fn push_to_websocket(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<MonitorEvent>,
+    filter: StreamFilter,
+    shutdown: CancellationToken,
+) {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+
+    loop {
+        let text = tokio::select! {
+            item = next_item(&mut receiver, &filter) => match item {
+                Some(Item::Announcement(announcement)) => {
+                    serde_json::to_string(&Frame::Announcement { announcement: &announcement })
+                }
+                Some(Item::Lagged(skipped)) => serde_json::to_string(&Frame::Lagged { skipped }),
+                None => break,
+            },
+            _ = heartbeat.tick() => serde_json::to_string(&Frame::Heartbeat { at: Utc::now() }),
+            _ = shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        let Ok(text) = text else {
+            continue;
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(exchange: Option<&str>, kind: Option<&str>, symbol: Option<&str>) -> Result<StreamFilter, ApiError> {
+        StreamFilter::from_params(StreamParams {
+            exchange: exchange.map(str::to_string),
+            kind: kind.map(str::to_string),
+            symbol: symbol.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn filters_case_insensitively() {
+        let mut announcement = Announcement::sample("1", "OKX to list ZRO");
+        announcement.exchange = "OKX".to_string();
+        announcement.kind = AnnouncementKind::SpotListing;
+        announcement.token_symbols = vec!["ZRO".to_string()];
+
+        assert!(filter(None, None, None).ok().unwrap().matches(&announcement));
+        assert!(filter(Some("okx"), Some("spot_listing"), Some("zro")).ok().unwrap().matches(&announcement));
+        assert!(!filter(Some("Binance"), None, None).ok().unwrap().matches(&announcement));
+        assert!(!filter(None, Some("delisting"), None).ok().unwrap().matches(&announcement));
+        assert!(!filter(None, None, Some("ZR")).ok().unwrap().matches(&announcement));
+        assert!(filter(None, Some("listing"), None).is_err());
+    }
+
+    #[test]
+    fn tags_websocket_frames_with_their_type() {
+        assert_eq!(
+            serde_json::to_string(&Frame::Lagged { skipped: 3 }).unwrap(),
+            r#"{"type":"lagged","skipped":3}"#
+        );
+    }
+}
@@ -0,0 +1,593 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::events::{self, EventBus, MonitorEvent};
+use crate::models::announcement::{Announcement, AnnouncementKind};
+use crate::models::update::AnnouncementUpdate;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS announcements (
+    exchange      TEXT NOT NULL,
+    id            TEXT NOT NULL,
+    title         TEXT NOT NULL,
+    url           TEXT NOT NULL,
+    kind          TEXT NOT NULL,
+    published_at  TEXT NOT NULL,
+    symbols       TEXT NOT NULL,
+    content_hash  TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at  TEXT NOT NULL,
+    data          TEXT NOT NULL,
+    listed_at     TEXT,
+    content_fetched_at TEXT,
+    detected_at   TEXT,
+    PRIMARY KEY (exchange, id)
+);
+CREATE INDEX IF NOT EXISTS announcements_published_at ON announcements (published_at);
+CREATE INDEX IF NOT EXISTS announcements_kind ON announcements (kind);
+";
+
+/// What an upsert did to the archived copy of an announcement
+#[derive(Debug, Clone)]
+pub enum UpsertOutcome {
+    /// First time this announcement was archived
+    Inserted,
+    /// Already archived with the same title and content
+    Unchanged,
+    /// Already archived, but the title or content changed since, with the archived copy
+    Updated(Box<Announcement>),
+}
+
+/// An announcement as stored in the archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedAnnouncement {
+    #[serde(flatten)]
+    pub announcement: Announcement,
+    /// When any monitor first fetched the announcement
+    pub first_seen_at: DateTime<Utc>,
+    /// When any monitor last fetched the announcement
+    pub last_seen_at: DateTime<Utc>,
+    /// SHA-256 of the title and content, see `content_hash`
+    pub content_hash: String,
+}
+
+/// Filters of an archive query, all optional
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveQuery {
+    /// Exchange name, case-insensitive
+    pub exchange: Option<String>,
+    /// Token symbol, case-insensitive
+    pub symbol: Option<String>,
+    pub kind: Option<AnnouncementKind>,
+    /// Only announcements published at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only announcements published before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only announcements this process reported as new, see `Announcement::detected_at`
+    pub detected_only: bool,
+    /// Maximum number of results, newest first, all if None
+    pub limit: Option<usize>,
+}
+
+/// Archived announcements of one exchange
+#[derive(Debug, Clone, Serialize)]
+pub struct ExchangeStats {
+    pub exchange: String,
+    pub announcements: u64,
+    pub latest_published_at: DateTime<Utc>,
+}
+
+/// SQLite archive of every announcement fetched by any monitor.
+///
+/// Unlike the seen store, which only answers "was this reported", the archive keeps
+/// the full announcement, keyed on `(exchange, id)`, with the first and last time it
+/// was fetched and a hash of its content. The listing, content and detection times
+/// are kept from the first poll that had them, later polls never overwrite them. rusqlite is blocking,
+/// so all database work runs on the blocking thread pool.
+pub struct AnnouncementArchive {
+    path: PathBuf,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl AnnouncementArchive {
+    /// Open (or create) the archive database at `path`
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+
+        let open_path = path.clone();
+        let connection = tokio::task::spawn_blocking(move || -> Result<Connection> {
+            let connection = Connection::open(&open_path)?;
+            // WAL lets the query subcommand read while the monitors write
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+            connection.execute_batch(SCHEMA)?;
+            Ok(connection)
+        })
+        .await
+        .context("Archive task panicked")?
+        .with_context(|| format!("Failed to open archive {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Insert or refresh a batch of fetched announcements in a single transaction.
+    ///
+    /// Returns what happened to each announcement, in order. An announcement fetched
+    /// without content keeps its archived content.
+    pub async fn upsert_all(&self, announcements: &[Announcement]) -> Result<Vec<UpsertOutcome>> {
+        let announcements = announcements.to_vec();
+        let now = format_time(&Utc::now());
+
+        self.with_connection(move |connection| {
+            let transaction = connection.transaction()?;
+            let mut outcomes = Vec::with_capacity(announcements.len());
+
+            for announcement in &announcements {
+                let existing = transaction
+                    .query_row(
+                        "SELECT content_hash, data FROM announcements WHERE exchange = ?1 AND id = ?2",
+                        params![announcement.exchange, announcement.id],
+                        |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                    )
+                    .optional()?;
+                let existing = existing
+                    .map(|(hash, data)| -> Result<(String, Announcement)> {
+                        Ok((hash, serde_json::from_str(&data).context("Malformed archived announcement")?))
+                    })
+                    .transpose()?;
+
+                // A failed detail request leaves the content empty, which is not an edit
+                let mut announcement = announcement.clone();
+                if let Some((_, previous)) = &existing {
+                    if announcement.content.is_empty() && !previous.content.is_empty() {
+                        announcement.content = previous.content.clone();
+                    }
+                }
+                let hash = content_hash(&announcement);
+
+                let outcome = match existing {
+                    None => UpsertOutcome::Inserted,
+                    Some((existing, _)) if existing == hash => UpsertOutcome::Unchanged,
+                    Some((_, previous)) => UpsertOutcome::Updated(Box::new(previous)),
+                };
+
+                // The stored copy is refreshed even when unchanged, the classification
+                // may differ after a rules reload
+                transaction.execute(
+                    "INSERT INTO announcements
+                        (exchange, id, title, url, kind, published_at, symbols, content_hash, first_seen_at, last_seen_at, data,
+                         listed_at, content_fetched_at, detected_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9, ?10, ?11, ?12, ?13)
+                     ON CONFLICT (exchange, id) DO UPDATE SET
+                        title = excluded.title,
+                        url = excluded.url,
+                        kind = excluded.kind,
+                        published_at = excluded.published_at,
+                        symbols = excluded.symbols,
+                        content_hash = excluded.content_hash,
+                        last_seen_at = excluded.last_seen_at,
+                        data = excluded.data,
+                        listed_at = coalesce(announcements.listed_at, excluded.listed_at),
+                        content_fetched_at = coalesce(announcements.content_fetched_at, excluded.content_fetched_at),
+                        detected_at = coalesce(announcements.detected_at, excluded.detected_at)",
+                    params![
+                        announcement.exchange,
+                        announcement.id,
+                        announcement.title,
+                        announcement.url,
+                        announcement.kind.as_str(),
+                        format_time(&announcement.published_at),
+                        symbols_column(&announcement.token_symbols),
+                        hash,
+                        now,
+                        serde_json::to_string(&announcement)?,
+                        announcement.listed_at.as_ref().map(format_time),
+                        announcement.content_fetched_at.as_ref().map(format_time),
+                        announcement.detected_at.as_ref().map(format_time),
+                    ],
+                )?;
+
+                outcomes.push(outcome);
+            }
+
+            transaction.commit()?;
+            Ok(outcomes)
+        })
+        .await
+        .with_context(|| format!("Failed to update archive {}", self.path.display()))
+    }
+
+    /// Search the archive, newest announcements first
+    pub async fn query(&self, query: &ArchiveQuery) -> Result<Vec<ArchivedAnnouncement>> {
+        let mut sql = format!("SELECT {} FROM announcements WHERE 1 = 1", ArchiveRow::COLUMNS);
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(exchange) = &query.exchange {
+            values.push(exchange.to_lowercase());
+            sql.push_str(&format!(" AND lower(exchange) = ?{}", values.len()));
+        }
+        if let Some(symbol) = &query.symbol {
+            // instr matches literally, LIKE would treat % and _ in the symbol as wildcards.
+            // A comma separates symbols, so a filter containing one matches nothing.
+            if symbol.contains(',') {
+                sql.push_str(" AND 0");
+            } else {
+                values.push(format!(",{},", symbol.to_uppercase()));
+                sql.push_str(&format!(" AND instr(symbols, ?{}) > 0", values.len()));
+            }
+        }
+        if let Some(kind) = &query.kind {
+            values.push(kind.as_str().to_string());
+            sql.push_str(&format!(" AND kind = ?{}", values.len()));
+        }
+        if let Some(since) = &query.since {
+            values.push(format_time(since));
+            sql.push_str(&format!(" AND published_at >= ?{}", values.len()));
+        }
+        if let Some(until) = &query.until {
+            values.push(format_time(until));
+            sql.push_str(&format!(" AND published_at < ?{}", values.len()));
+        }
+        if query.detected_only {
+            sql.push_str(" AND detected_at IS NOT NULL");
+        }
+        sql.push_str(" ORDER BY published_at DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(&sql)?;
+            let rows = statement.query_map(rusqlite::params_from_iter(values.iter()), ArchiveRow::read)?;
+
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?.into_archived()?);
+            }
+            Ok(results)
+        })
+        .await
+        .with_context(|| format!("Failed to query archive {}", self.path.display()))
+    }
+
+    /// Look up a single announcement, the exchange name is case-insensitive
+    pub async fn get(&self, exchange: &str, id: &str) -> Result<Option<ArchivedAnnouncement>> {
+        let sql = format!(
+            "SELECT {} FROM announcements WHERE lower(exchange) = ?1 AND id = ?2",
+            ArchiveRow::COLUMNS
+        );
+        let exchange = exchange.to_lowercase();
+        let id = id.to_string();
+
+        self.with_connection(move |connection| {
+            connection
+                .query_row(&sql, params![exchange, id], ArchiveRow::read)
+                .optional()?
+                .map(ArchiveRow::into_archived)
+                .transpose()
+        })
+        .await
+        .with_context(|| format!("Failed to query archive {}", self.path.display()))
+    }
+
+    /// Number of archived announcements and the latest publication time per exchange
+    pub async fn exchange_stats(&self) -> Result<Vec<ExchangeStats>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT exchange, count(*), max(published_at) FROM announcements GROUP BY exchange ORDER BY exchange",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?, row.get::<_, String>(2)?))
+            })?;
+
+            let mut stats = Vec::new();
+            for row in rows {
+                let (exchange, announcements, latest_published_at) = row?;
+                stats.push(ExchangeStats {
+                    exchange,
+                    announcements,
+                    latest_published_at: parse_time(&latest_published_at)?,
+                });
+            }
+            Ok(stats)
+        })
+        .await
+        .with_context(|| format!("Failed to query archive {}", self.path.display()))
+    }
+
+    /// Move the write-ahead log into the database file, so that the file is complete
+    /// on its own once the process exits
+    pub async fn flush(&self) -> Result<()> {
+        self.with_connection(|connection| {
+            connection.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
+            Ok(())
+        })
+        .await
+        .with_context(|| format!("Failed to checkpoint archive {}", self.path.display()))
+    }
+
+    /// Run blocking database work on the blocking thread pool
+    async fn with_connection<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Archive connection lock poisoned"))?;
+            f(&mut connection)
+        })
+        .await
+        .context("Archive task panicked")?
+    }
+}
+
+/// Raw columns of an archived announcement, before parsing
+struct ArchiveRow {
+    data: String,
+    first_seen_at: String,
+    last_seen_at: String,
+    content_hash: String,
+    listed_at: Option<String>,
+    content_fetched_at: Option<String>,
+    detected_at: Option<String>,
+}
+
+impl ArchiveRow {
+    const COLUMNS: &'static str =
+        "data, first_seen_at, last_seen_at, content_hash, listed_at, content_fetched_at, detected_at";
+
+    fn read(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            data: row.get(0)?,
+            first_seen_at: row.get(1)?,
+            last_seen_at: row.get(2)?,
+            content_hash: row.get(3)?,
+            listed_at: row.get(4)?,
+            content_fetched_at: row.get(5)?,
+            detected_at: row.get(6)?,
+        })
+    }
+
+    fn into_archived(self) -> Result<ArchivedAnnouncement> {
+        // The columns are authoritative, `data` is from the latest poll
+        let mut announcement: Announcement =
+            serde_json::from_str(&self.data).context("Malformed archived announcement")?;
+        announcement.listed_at = self.listed_at.as_deref().map(parse_time).transpose()?;
+        announcement.content_fetched_at = self.content_fetched_at.as_deref().map(parse_time).transpose()?;
+        announcement.detected_at = self.detected_at.as_deref().map(parse_time).transpose()?;
+
+        Ok(ArchivedAnnouncement {
+            announcement,
+            first_seen_at: parse_time(&self.first_seen_at)?,
+            last_seen_at: parse_time(&self.last_seen_at)?,
+            content_hash: self.content_hash,
+        })
+    }
+}
+
+/// Spawn the consumer that archives the announcements of every successful poll and
+/// publishes an `AnnouncementUpdated` event for each one that was edited since it
+/// was archived
+pub fn spawn_archiver(bus: &EventBus, archive: Arc<AnnouncementArchive>) -> JoinHandle<()> {
+    // A strong handle would keep the bus open forever, see `EventBus::downgrade`
+    let publisher = bus.downgrade();
+    events::spawn_consumer(bus, "archive", move |event| {
+        let archive = archive.clone();
+        let publisher = publisher.clone();
+        async move {
+            let MonitorEvent::AnnouncementsFetched { exchange, announcements, .. } = event else {
+                return;
+            };
+
+            match archive.upsert_all(&announcements).await {
+                Ok(outcomes) => {
+                    for (announcement, outcome) in announcements.iter().zip(outcomes) {
+                        let UpsertOutcome::Updated(previous) = outcome else {
+                            continue;
+                        };
+                        let update = AnnouncementUpdate::new(&previous, announcement.clone());
+                        tracing::info!(
+                            exchange = exchange,
+                            announcement_id = announcement.id,
+                            title = announcement.title,
+                            "Announcement on {} was edited: {}",
+                            exchange, announcement.title
+                        );
+                        tracing::debug!(exchange = exchange, announcement_id = announcement.id, "Diff:\n{}", update.diff);
+                        publisher.publish(MonitorEvent::AnnouncementUpdated(Arc::new(update)));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        exchange = exchange,
+                        error = %e,
+                        "Failed to archive announcements from {}: {:#}",
+                        exchange, e
+                    );
+                }
+            }
+        }
+    })
+}
+
+/// SHA-256 of the title and content, used to tell when an announcement was edited
+pub fn content_hash(announcement: &Announcement) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(announcement.title.as_bytes());
+    hasher.update([0]);
+    hasher.update(announcement.content.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Symbols are stored as ",ABC,DEF," so that searching for ",ABC," matches whole
+/// symbols only
+fn symbols_column(symbols: &[String]) -> String {
+    format!(",{},", symbols.join(","))
+}
+
+/// Fixed-width RFC 3339 in UTC, so that timestamps sort correctly as text
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_time(text: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(text)
+        .with_context(|| format!("Malformed archive timestamp '{}'", text))?
+        .with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn announcement(id: &str, symbols: &[&str]) -> Announcement {
+        let mut announcement = Announcement::sample(id, &format!("Binance Will List {}", symbols.join(", ")));
+        announcement.token_symbols = symbols.iter().map(|s| s.to_string()).collect();
+        announcement
+    }
+
+    fn published(announcement: Announcement, exchange: &str, day: u32) -> Announcement {
+        Announcement {
+            exchange: exchange.to_string(),
+            published_at: Utc.with_ymd_and_hms(2024, 5, day, 6, 0, 0).unwrap(),
+            ..announcement
+        }
+    }
+
+    async fn ids(archive: &AnnouncementArchive, query: ArchiveQuery) -> Vec<String> {
+        archive.query(&query).await.unwrap()
+            .into_iter()
+            .map(|archived| archived.announcement.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn upserts_once_per_exchange_and_id() {
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        let batch = [announcement("1", &["NOT"]), published(announcement("1", &["NOT"]), "OKX", 16)];
+
+        let outcomes = archive.upsert_all(&batch).await.unwrap();
+        assert!(matches!(outcomes[..], [UpsertOutcome::Inserted, UpsertOutcome::Inserted]));
+        let query = ArchiveQuery { exchange: Some("binance".to_string()), ..Default::default() };
+        let first = archive.query(&query).await.unwrap().remove(0);
+
+        let outcomes = archive.upsert_all(&batch).await.unwrap();
+        assert!(matches!(outcomes[..], [UpsertOutcome::Unchanged, UpsertOutcome::Unchanged]));
+        let second = archive.query(&query).await.unwrap();
+
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].first_seen_at, first.first_seen_at);
+        assert!(second[0].last_seen_at >= first.last_seen_at);
+        assert_eq!(second[0].content_hash, content_hash(&batch[0]));
+    }
+
+    #[tokio::test]
+    async fn filters_and_orders_queries() {
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        let mut delisting = published(announcement("2", &["MOB"]), "Binance", 10);
+        delisting.kind = AnnouncementKind::Delisting;
+        let mut okx = published(announcement("3", &["ZRO"]), "OKX", 20);
+        okx.kind = AnnouncementKind::SpotListing;
+        okx.detected_at = Some(Utc::now());
+        let mut listing = published(announcement("1", &["NOT", "1000SATS"]), "Binance", 16);
+        listing.kind = AnnouncementKind::SpotListing;
+        archive.upsert_all(&[delisting, okx, listing]).await.unwrap();
+
+        assert_eq!(ids(&archive, ArchiveQuery::default()).await, ["3", "1", "2"]);
+        assert_eq!(ids(&archive, ArchiveQuery { exchange: Some("binance".to_string()), ..Default::default() }).await, ["1", "2"]);
+        assert_eq!(ids(&archive, ArchiveQuery { symbol: Some("1000sats".to_string()), ..Default::default() }).await, ["1"]);
+        assert_eq!(ids(&archive, ArchiveQuery { kind: Some(AnnouncementKind::SpotListing), ..Default::default() }).await, ["3", "1"]);
+        assert_eq!(ids(&archive, ArchiveQuery { detected_only: true, ..Default::default() }).await, ["3"]);
+        assert_eq!(ids(&archive, ArchiveQuery { limit: Some(2), ..Default::default() }).await, ["3", "1"]);
+        assert_eq!(
+            ids(&archive, ArchiveQuery {
+                since: Some(Utc.with_ymd_and_hms(2024, 5, 10, 6, 0, 0).unwrap()),
+                until: Some(Utc.with_ymd_and_hms(2024, 5, 20, 6, 0, 0).unwrap()),
+                ..Default::default()
+            }).await,
+            ["1", "2"]
+        );
+    }
+
+    #[test]
+    fn hash_keeps_title_and_content_apart() {
+        let mut a = announcement("1", &[]);
+        a.title = "ab".to_string();
+        a.content = "c".to_string();
+        let mut b = a.clone();
+        b.title = "a".to_string();
+        b.content = "bc".to_string();
+
+        assert_ne!(content_hash(&a), content_hash(&b));
+        assert_eq!(content_hash(&a).len(), 64);
+    }
+
+    #[tokio::test]
+    async fn reports_edits_with_the_archived_copy() {
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        let mut original = announcement("1", &["NOT"]);
+        original.content = "Trading opens at 10:00".to_string();
+        archive.upsert_all(&[original.clone()]).await.unwrap();
+
+        // A failed detail request is not an edit, and keeps the archived content
+        let mut without_content = original.clone();
+        without_content.content = String::new();
+        let outcomes = archive.upsert_all(&[without_content]).await.unwrap();
+        assert!(matches!(outcomes[..], [UpsertOutcome::Unchanged]));
+        let archived = archive.get("Binance", "1").await.unwrap().unwrap();
+        assert_eq!(archived.announcement.content, "Trading opens at 10:00");
+
+        let mut edited = original.clone();
+        edited.content = "Trading opens at 12:00".to_string();
+        let outcomes = archive.upsert_all(&[edited.clone()]).await.unwrap();
+        let [UpsertOutcome::Updated(previous)] = &outcomes[..] else {
+            panic!("Expected an update, got {:?}", outcomes);
+        };
+        assert_eq!(previous.content, "Trading opens at 10:00");
+
+        let archived = archive.get("Binance", "1").await.unwrap().unwrap();
+        assert_eq!(archived.content_hash, content_hash(&edited));
+        let outcomes = archive.upsert_all(&[edited]).await.unwrap();
+        assert!(matches!(outcomes[..], [UpsertOutcome::Unchanged]));
+    }
+
+    #[tokio::test]
+    async fn symbol_filter_matches_whole_symbols_literally() {
+        let archive = AnnouncementArchive::open(":memory:").await.unwrap();
+        archive.upsert_all(&[
+            announcement("1", &["NOT"]),
+            announcement("2", &["NOTX", "ZRO"]),
+            announcement("3", &["1000SATS"]),
+        ]).await.unwrap();
+        let symbol = |symbol: &str| ArchiveQuery { symbol: Some(symbol.to_string()), ..Default::default() };
+
+        assert_eq!(ids(&archive, symbol("not")).await, ["1"]);
+        assert_eq!(ids(&archive, symbol("ZRO")).await, ["2"]);
+        assert!(ids(&archive, symbol("%")).await.is_empty());
+        assert!(ids(&archive, symbol("NO_")).await.is_empty());
+        assert!(ids(&archive, symbol("1000SAT%")).await.is_empty());
+        assert!(ids(&archive, symbol("NOTX,ZRO")).await.is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::models::announcement::Announcement;
+
+/// SeenStore keeps track of which announcements have already been reported,
+/// keyed on `(exchange, announcement id)`
+#[async_trait]
+pub trait SeenStore: Send + Sync {
+    /// Records the announcement as seen.
+    ///
+    /// Returns true if the announcement was not known before this call, which is
+    /// what the run loop uses to decide whether to report it.
+    async fn mark_seen(&self, announcement: &Announcement) -> Result<bool>;
+    
+    /// Make every recorded announcement durable, called once on shutdown
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A single entry of the seen-announcement log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeenRecord {
+    /// Exchange the announcement belongs to
+    pub exchange: String,
+    /// Exchange-specific announcement id
+    pub id: String,
+    /// When the announcement was first seen by this process
+    pub first_seen_at: DateTime<Utc>,
+}
+
+/// File-backed seen store using an append-only JSON lines log.
+///
+/// Every newly seen announcement is appended as one `SeenRecord` line, so a write
+/// never rewrites existing data and a crash can at worst lose the last line.
+/// On startup the whole log is replayed into memory; lookups never touch the disk.
+/// A final line without its newline was cut off mid-append and is dropped from the
+/// file before anything else is appended after it.
+pub struct FileSeenStore {
+    path: PathBuf,
+    inner: Mutex<FileSeenStoreInner>,
+}
+
+struct FileSeenStoreInner {
+    seen: HashSet<(String, String)>,
+    file: File,
+}
+
+/// State of the end of the log found while replaying it
+#[derive(Debug, PartialEq)]
+enum LogTail {
+    /// Empty or ends with a newline, ready for appending
+    Complete,
+    /// The last record is intact but its newline is missing
+    MissingNewline,
+    /// The last line is a partial record; the file should be cut back to this length
+    Truncated(u64),
+}
+
+impl FileSeenStore {
+    /// Open (or create) the seen-announcement log at `path`
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+
+        let (seen, tail) = Self::load(&path).await?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open seen store {}", path.display()))?;
+
+        // Otherwise the next record would be appended onto the end of the partial line
+        // and be unreadable on the following start as well
+        match tail {
+            LogTail::Complete => {}
+            LogTail::MissingNewline => file.write_all(b"\n").await?,
+            LogTail::Truncated(len) => file.set_len(len).await?,
+        }
+
+        tracing::info!(
+            path = %path.display(),
+            entries = seen.len(),
+            "Loaded {} seen announcements from {}",
+            seen.len(), path.display()
+        );
+
+        Ok(Self {
+            path,
+            inner: Mutex::new(FileSeenStoreInner { seen, file }),
+        })
+    }
+
+    /// Replay the log file into a set of keys. A missing file is an empty store.
+    async fn load(path: &Path) -> Result<(HashSet<(String, String)>, LogTail)> {
+        let mut seen = HashSet::new();
+
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((seen, LogTail::Complete)),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read seen store {}", path.display()))
+            }
+        };
+
+        let mut tail = LogTail::Complete;
+        let mut offset = 0;
+        for (index, line) in data.split_inclusive(|&b| b == b'\n').enumerate() {
+            let line_start = offset;
+            offset += line.len();
+            let terminated = line.ends_with(b"\n");
+
+            let text = String::from_utf8_lossy(line);
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<SeenRecord>(&text) {
+                Ok(record) => {
+                    seen.insert((record.exchange, record.id));
+                    if !terminated {
+                        tail = LogTail::MissingNewline;
+                    }
+                }
+                // The append of the last record was cut off, e.g. by a crash or a full disk
+                Err(e) if !terminated => {
+                    tracing::warn!(
+                        path = %path.display(),
+                        line = index + 1,
+                        error = %e,
+                        "Dropping truncated final seen store entry"
+                    );
+                    tail = LogTail::Truncated(line_start as u64);
+                }
+                // A corrupt line in the middle should not make the whole store unusable
+                Err(e) => {
+                    tracing::warn!(
+                        path = %path.display(),
+                        line = index + 1,
+                        error = %e,
+                        "Skipping malformed seen store entry"
+                    );
+                }
+            }
+        }
+
+        Ok((seen, tail))
+    }
+}
+
+#[async_trait]
+impl SeenStore for FileSeenStore {
+    async fn mark_seen(&self, announcement: &Announcement) -> Result<bool> {
+        let mut inner = self.inner.lock().await;
+
+        let key = (announcement.exchange.clone(), announcement.id.clone());
+        if inner.seen.contains(&key) {
+            return Ok(false);
+        }
+
+        let record = SeenRecord {
+            exchange: announcement.exchange.clone(),
+            id: announcement.id.clone(),
+            first_seen_at: Utc::now(),
+        };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+
+        // Persist before updating the in-memory set so that a failed write is retried
+        // on the next poll instead of being silently lost
+        inner.file
+            .write_all(line.as_bytes())
+            .await
+            .with_context(|| format!("Failed to append to seen store {}", self.path.display()))?;
+        inner.file.flush().await?;
+
+        inner.seen.insert(key);
+        Ok(true)
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let inner = self.inner.lock().await;
+        inner.file
+            .sync_all()
+            .await
+            .with_context(|| format!("Failed to sync seen store {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("eam-seen-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("seen.jsonl")
+    }
+
+    #[tokio::test]
+    async fn reports_each_announcement_once_across_restarts() {
+        let path = log_path("restart");
+
+        let store = FileSeenStore::open(&path).await.unwrap();
+        assert!(store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap());
+        assert!(!store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap());
+        store.flush().await.unwrap();
+        drop(store);
+
+        let store = FileSeenStore::open(&path).await.unwrap();
+        assert!(!store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap());
+        assert!(store.mark_seen(&Announcement::sample("2", "Notice")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn drops_a_truncated_final_line_before_appending() {
+        let path = log_path("truncated");
+        let store = FileSeenStore::open(&path).await.unwrap();
+        store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap();
+        drop(store);
+
+        let mut data = std::fs::read_to_string(&path).unwrap();
+        let complete_len = data.len();
+        data.push_str(r#"{"exchange":"Binance","id":"2","first_se"#);
+        std::fs::write(&path, &data).unwrap();
+
+        let store = FileSeenStore::open(&path).await.unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete_len as u64);
+        assert!(!store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap());
+        assert!(store.mark_seen(&Announcement::sample("2", "Notice")).await.unwrap());
+        drop(store);
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(data.lines().count(), 2);
+        assert!(data.lines().all(|line| serde_json::from_str::<SeenRecord>(line).is_ok()));
+    }
+
+    #[tokio::test]
+    async fn keeps_a_final_record_that_only_lacks_its_newline() {
+        let path = log_path("newline");
+        let store = FileSeenStore::open(&path).await.unwrap();
+        store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap();
+        drop(store);
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, data.trim_end()).unwrap();
+
+        let store = FileSeenStore::open(&path).await.unwrap();
+        assert!(!store.mark_seen(&Announcement::sample("1", "Notice")).await.unwrap());
+        assert!(store.mark_seen(&Announcement::sample("2", "Notice")).await.unwrap());
+        drop(store);
+
+        let (seen, tail) = FileSeenStore::load(&path).await.unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(tail, LogTail::Complete);
+    }
+}
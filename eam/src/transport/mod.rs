@@ -0,0 +1,144 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub mod record;
+pub mod replay;
+
+/// Name of the file listing the recorded requests of an exchange, next to the
+/// response bodies
+pub const FIXTURE_INDEX: &str = "requests.json";
+
+/// Query parameters that carry the current time, ignored when matching a request
+/// against the recorded ones
+const VOLATILE_QUERY_PARAMS: &[&str] = &["t", "timestamp", "_"];
+
+/// How the monitors send their HTTP requests. Monitors build each request with their
+/// own client, which carries the proxy and the timeouts, and hand it to the transport.
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    /// Send a request and return the response
+    async fn send(&self, request: RequestBuilder) -> Result<Response>;
+}
+
+/// Sends requests over the network, used unless a monitor is recording or replaying
+pub struct LiveTransport;
+
+#[async_trait]
+impl HttpTransport for LiveTransport {
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        Ok(request.send().await?)
+    }
+}
+
+/// The transport of monitors talking to the real exchanges
+pub fn live() -> Arc<dyn HttpTransport> {
+    Arc::new(LiveTransport)
+}
+
+/// How a monitor sends its requests: the client they are built with and the
+/// transport they go through
+#[derive(Clone)]
+pub struct MonitorHttp {
+    /// Client used for every request of the monitor, e.g. one shared by all monitors
+    /// or one with its own proxy. Without it each monitor builds its own clients, the
+    /// ones rotating through the proxy pool a new client per request.
+    pub client: Option<Client>,
+    pub transport: Arc<dyn HttpTransport>,
+}
+
+impl MonitorHttp {
+    /// Requests sent over the network with the monitor's own clients
+    pub fn live() -> Self {
+        Self::with_transport(live())
+    }
+
+    /// Requests sent over the network with a client shared with other monitors
+    pub fn shared(client: Client) -> Self {
+        Self { client: Some(client), transport: live() }
+    }
+
+    /// Requests sent through a transport with the monitor's own clients
+    pub fn with_transport(transport: Arc<dyn HttpTransport>) -> Self {
+        Self { client: None, transport }
+    }
+
+    /// The shared client, or a new one built by `build`
+    pub fn client_or(&self, build: impl FnOnce() -> Client) -> Client {
+        self.client.clone().unwrap_or_else(build)
+    }
+}
+
+/// One recorded request and the response it got, an entry of the fixture index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    pub url: String,
+    /// Body of the request, e.g. the JSON query of a POST
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// File holding the raw response body, relative to the fixture directory
+    pub body_file: String,
+}
+
+impl Fixture {
+    /// Check if this fixture answers a request, ignoring volatile query parameters
+    pub fn matches(&self, method: &str, url: &str, request_body: Option<&str>) -> bool {
+        self.method == method
+            && stable_url(&self.url) == stable_url(url)
+            && self.request_body.as_deref() == request_body
+    }
+}
+
+/// URL without the query parameters that change on every request
+fn stable_url(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let pairs: Vec<(String, String)> = parsed.query_pairs()
+        .filter(|(name, _)| !VOLATILE_QUERY_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.to_string()
+}
+
+/// Request body as text, None for requests without one or with a streaming body
+fn request_body_text(request: &reqwest::Request) -> Option<String> {
+    request.body()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_ignores_volatile_query_params() {
+        let fixture = Fixture {
+            method: "GET".to_string(),
+            url: "https://www.okx.com/queryList?t=1718600000000&language=en_US".to_string(),
+            request_body: None,
+            status: 200,
+            content_type: None,
+            body_file: "01-queryList.json".to_string(),
+        };
+
+        assert!(fixture.matches("GET", "https://www.okx.com/queryList?t=1718699999999&language=en_US", None));
+        assert!(fixture.matches("GET", "https://www.okx.com/queryList?language=en_US", None));
+        assert!(!fixture.matches("GET", "https://www.okx.com/queryList?t=1&language=zh_CN", None));
+        assert!(!fixture.matches("POST", "https://www.okx.com/queryList?t=1&language=en_US", None));
+        assert!(!fixture.matches("GET", "https://www.okx.com/queryList?t=1&language=en_US", Some("{}")));
+    }
+}
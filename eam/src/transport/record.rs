@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{header, RequestBuilder, Response};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+use super::{request_body_text, Fixture, HttpTransport, FIXTURE_INDEX};
+
+/// Sends requests over the network and saves every response as a fixture, for
+/// `ReplayTransport` to serve later. The fixture index is rewritten after each
+/// response so that an interrupted recording is still usable.
+pub struct RecordingTransport {
+    dir: PathBuf,
+    fixtures: Mutex<Vec<Fixture>>,
+}
+
+impl RecordingTransport {
+    /// Record into a directory, replacing the fixtures already in it
+    pub async fn create(dir: &Path) -> Result<Self> {
+        if dir.exists() {
+            tokio::fs::remove_dir_all(dir)
+                .await
+                .with_context(|| format!("Failed to clear fixture directory {}", dir.display()))?;
+        }
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Failed to create fixture directory {}", dir.display()))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            fixtures: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl HttpTransport for RecordingTransport {
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        let request_body = request_body_text(&request);
+
+        let response = client.execute(request).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let content_type = headers.get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let mut fixtures = self.fixtures.lock().await;
+        let body_file = body_file_name(fixtures.len() + 1, &url, content_type.as_deref());
+        tokio::fs::write(self.dir.join(&body_file), &body)
+            .await
+            .with_context(|| format!("Failed to write fixture {}", body_file))?;
+        fixtures.push(Fixture {
+            method,
+            url,
+            request_body,
+            status: status.as_u16(),
+            content_type,
+            body_file,
+        });
+        let index = serde_json::to_string_pretty(&*fixtures)?;
+        tokio::fs::write(self.dir.join(FIXTURE_INDEX), index)
+            .await
+            .context("Failed to write fixture index")?;
+        drop(fixtures);
+
+        // Hand the monitor an identical response, its body was consumed above
+        let mut rebuilt = http::Response::builder().status(status);
+        for (name, value) in &headers {
+            rebuilt = rebuilt.header(name, value);
+        }
+        Ok(Response::from(rebuilt.body(body)?))
+    }
+}
+
+/// File name of the n-th recorded body, e.g. "01-query.json", named after the last
+/// segment of the URL path
+fn body_file_name(n: usize, url: &str, content_type: Option<&str>) -> String {
+    let segment = url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.path_segments()?.rfind(|s| !s.is_empty()).map(str::to_string))
+        .unwrap_or_else(|| "index".to_string());
+    let name: String = segment.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    let extension = match content_type {
+        Some(content_type) if content_type.contains("json") => "json",
+        Some(content_type) if content_type.contains("html") => "html",
+        Some(content_type) if content_type.contains("xml") => "xml",
+        _ => "txt",
+    };
+    format!("{:02}-{}.{}", n, name, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::replay::ReplayTransport;
+    use crate::utils::serve;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use reqwest::Client;
+
+    #[tokio::test]
+    async fn recorded_responses_replay_identically() {
+        let router = Router::new()
+            .route("/api/v1/notices", get(|| async { Json(serde_json::json!({"success": true, "data": []})) }))
+            .route("/query", post(|body: String| async move { body }));
+        let base_url = serve(router).await;
+
+        let dir = std::env::temp_dir().join(format!("eam-record-{}", std::process::id()));
+        let recorder = RecordingTransport::create(&dir).await.unwrap();
+        let client = Client::new();
+
+        let notices = recorder.send(client.get(format!("{}/api/v1/notices?t=1", base_url))).await.unwrap();
+        assert_eq!(notices.status(), 200);
+        assert_eq!(notices.text().await.unwrap(), r#"{"data":[],"success":true}"#);
+        let query = recorder.send(client.post(format!("{}/query", base_url)).body("catalog=48")).await.unwrap();
+        assert_eq!(query.text().await.unwrap(), "catalog=48");
+
+        let replay = ReplayTransport::load(&dir).unwrap();
+        let index = std::fs::read_to_string(dir.join(FIXTURE_INDEX)).unwrap();
+        assert!(dir.join("01-notices.json").exists());
+        assert!(dir.join("02-query.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(index.contains(r#""request_body": "catalog=48""#));
+
+        let notices = replay.send(client.get(format!("{}/api/v1/notices?t=2", base_url))).await.unwrap();
+        assert_eq!(notices.status(), 200);
+        assert_eq!(notices.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(notices.text().await.unwrap(), r#"{"data":[],"success":true}"#);
+        let query = replay.send(client.post(format!("{}/query", base_url)).body("catalog=48")).await.unwrap();
+        assert_eq!(query.text().await.unwrap(), "catalog=48");
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{header, RequestBuilder, Response};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{request_body_text, Fixture, HttpTransport, FIXTURE_INDEX};
+
+/// Answers requests from fixtures saved by `RecordingTransport`, without touching the
+/// network. Fixtures matching the same request are served in the order they were
+/// recorded, the last one is served again once all were used.
+pub struct ReplayTransport {
+    dir: PathBuf,
+    fixtures: Vec<(Fixture, Vec<u8>)>,
+    /// Which fixtures were already served
+    served: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    /// Load the fixtures of a directory
+    pub fn load(dir: &Path) -> Result<Self> {
+        let index_path = dir.join(FIXTURE_INDEX);
+        let index = std::fs::read_to_string(&index_path)
+            .with_context(|| format!("Failed to read fixture index {}", index_path.display()))?;
+        let index: Vec<Fixture> = serde_json::from_str(&index)
+            .with_context(|| format!("Failed to parse fixture index {}", index_path.display()))?;
+
+        let fixtures = index.into_iter()
+            .map(|fixture| {
+                let body_path = dir.join(&fixture.body_file);
+                let body = std::fs::read(&body_path)
+                    .with_context(|| format!("Failed to read fixture {}", body_path.display()))?;
+                Ok((fixture, body))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            served: Mutex::new(vec![false; fixtures.len()]),
+            fixtures,
+        })
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReplayTransport {
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build()?;
+        let method = request.method().as_str();
+        let url = request.url().as_str();
+        let request_body = request_body_text(&request);
+
+        let matching: Vec<usize> = self.fixtures.iter()
+            .enumerate()
+            .filter(|(_, (fixture, _))| fixture.matches(method, url, request_body.as_deref()))
+            .map(|(index, _)| index)
+            .collect();
+
+        let index = {
+            let mut served = self.served.lock().unwrap();
+            let index = matching.iter()
+                .copied()
+                .find(|&index| !served[index])
+                .or_else(|| matching.last().copied())
+                .with_context(|| format!("No fixture for {} {} in {}", method, url, self.dir.display()))?;
+            served[index] = true;
+            index
+        };
+
+        let (fixture, body) = &self.fixtures[index];
+        let mut response = http::Response::builder().status(fixture.status);
+        if let Some(content_type) = &fixture.content_type {
+            response = response.header(header::CONTENT_TYPE, content_type);
+        }
+        Ok(Response::from(response.body(body.clone())?))
+    }
+}
+
+/// Replay the fixtures of `tests/fixtures/<name>`, for the monitor tests
+#[cfg(test)]
+pub fn fixtures(name: &str) -> std::sync::Arc<dyn HttpTransport> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    std::sync::Arc::new(ReplayTransport::load(&dir).expect("Failed to load fixtures"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+
+    /// Fixture directory with two responses to the same request and one to a POST
+    fn write_fixtures(dir: &Path) {
+        std::fs::create_dir_all(dir).unwrap();
+        let index = r#"[
+            {"method": "GET", "url": "https://example.com/list?page=1", "status": 429, "body_file": "01-list.txt"},
+            {"method": "GET", "url": "https://example.com/list?page=1", "status": 200, "content_type": "application/json", "body_file": "02-list.json"},
+            {"method": "POST", "url": "https://example.com/query", "request_body": "{\"id\":7}", "status": 200, "content_type": "application/json", "body_file": "03-query.json"}
+        ]"#;
+        std::fs::write(dir.join(FIXTURE_INDEX), index).unwrap();
+        std::fs::write(dir.join("01-list.txt"), "Too Many Requests").unwrap();
+        std::fs::write(dir.join("02-list.json"), r#"{"items":[]}"#).unwrap();
+        std::fs::write(dir.join("03-query.json"), r#"{"id":7}"#).unwrap();
+    }
+
+    #[tokio::test]
+    async fn serves_fixtures_in_recorded_order() {
+        let dir = std::env::temp_dir().join(format!("eam-replay-{}", std::process::id()));
+        write_fixtures(&dir);
+        let replay = ReplayTransport::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let client = Client::new();
+
+        let first = replay.send(client.get("https://example.com/list?page=1")).await.unwrap();
+        assert_eq!(first.status(), 429);
+        assert_eq!(first.text().await.unwrap(), "Too Many Requests");
+
+        // Once every match was served, the last one is served again
+        for _ in 0..2 {
+            let response = replay.send(client.get("https://example.com/list?page=1")).await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+            assert_eq!(response.text().await.unwrap(), r#"{"items":[]}"#);
+        }
+
+        let query = replay.send(client.post("https://example.com/query").body(r#"{"id":7}"#)).await.unwrap();
+        assert_eq!(query.text().await.unwrap(), r#"{"id":7}"#);
+
+        let error = replay.send(client.get("https://example.com/list?page=2")).await.unwrap_err();
+        assert!(error.to_string().starts_with("No fixture for GET https://example.com/list?page=2"));
+        assert!(replay.send(client.post("https://example.com/query").body("{}")).await.is_err());
+    }
+}